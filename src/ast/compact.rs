@@ -0,0 +1,1191 @@
+use std::borrow::Borrow;
+
+use crate::{
+    parser::{self, ParseError},
+    tokenizer::{KeywordType, Span, Token, TokenTag, TokenType, Tokenizer},
+};
+
+use super::tree::{
+    Asm as AsmExpr, AsmOutputKind, AssignOp, Ast, BinaryOp, Block, Capture, ContainerArg,
+    ContainerDecl, ContainerField as Field, ContainerKind, ContainerLayout, Decl, DeclKind, Expr,
+    ExprKind, FnDecl as FnDeclNode, FnProto as FnProtoNode, Ident, Linkage, Param, ParamType,
+    PointerSize, Stmt, StmtKind, SwitchProng as Prong, UnaryOp, VarDecl as VarDeclNode,
+};
+
+pub type TokenIndex = u32;
+pub type NodeIndex = u32;
+
+/// A parsed zig source file stored the way zig's own `std.zig.Ast` does
+/// it, which takes a fraction of the memory of the tree in `ast::Ast`.
+///
+/// Tokens are only a tag and a start offset, their text and value are
+/// lexed again from the source when asked for. Nodes are a tag, a main
+/// token and two `u32`s of data, kept in separate arrays. Nodes refer to
+/// each other by index, and whatever doesn't fit in the two `u32`s goes
+/// into `extra_data`, which is where `NodeData` points to in that case.
+/// What a node stores where is described on each `NodeTag`.
+///
+/// The root is always node `0`, so `0` is used for missing children.
+/// The same goes for tokens, as no optional token can be the first one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactAst<'src> {
+    src: &'src str,
+    tokens: TokenList,
+    nodes: NodeList,
+    extra_data: Vec<u32>,
+    errors: Vec<ParseError>,
+}
+
+/// The tokens of a file as parallel arrays, ending with an `Eof` token.
+/// Comments other than doc comments aren't included.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TokenList {
+    pub tags: Vec<TokenTag>,
+    pub starts: Vec<u32>,
+}
+
+/// The nodes of a file as parallel arrays, indexed by `NodeIndex`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeList {
+    pub tags: Vec<NodeTag>,
+    pub main_tokens: Vec<TokenIndex>,
+    pub data: Vec<NodeData>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NodeData {
+    pub lhs: u32,
+    pub rhs: u32,
+}
+
+/// What a node is, which decides what its main token is and what its
+/// `lhs` and `rhs` mean. `extra_data[lhs..rhs]` is a list of nodes
+/// unless said otherwise, and `extra: T` means `lhs` or `rhs` is the
+/// index of a `T` in `extra_data`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeTag {
+    /// `extra_data[lhs..rhs]` are the members of the file.
+    Root,
+    /// `main_token` is the `const` or `var`, followed by the name.
+    /// `lhs` is extra: `VarDecl`, `rhs` is the value.
+    VarDecl,
+    /// `main_token` is the `fn`. `lhs` is extra: `FnDecl`, `rhs` is the body.
+    FnDecl,
+    /// `main_token` is the `fn`, followed by the name if there is one.
+    /// `lhs` is extra: `FnProto`, `rhs` is the return type.
+    FnProto,
+    /// `main_token` is the name if `rhs` has `flags::NAMED`, and the first token
+    /// otherwise. `lhs` is the type, missing for `anytype` and `...`. `rhs` is flags.
+    Param,
+    /// `main_token` is the `test`. `lhs` is the name, `rhs` is the body.
+    TestDecl,
+    /// `comptime lhs`, as a member or as an expression.
+    Comptime,
+    /// `usingnamespace lhs;`, with `rhs` being flags.
+    UsingNamespace,
+    /// `main_token` is the name if the field is named, and the first token
+    /// otherwise. `lhs` is extra: `ContainerField`, `rhs` is the value.
+    ContainerField,
+    /// A member, statement or expression that couldn't be parsed.
+    /// `main_token` is where it starts.
+    Error,
+
+    /// `main_token` is the `{`. `extra_data[lhs..rhs]` are the statements.
+    Block,
+    /// `defer lhs`
+    Defer,
+    /// `errdefer lhs`. The capture comes right after `main_token`, if there is one.
+    Errdefer,
+
+    /// The literal is the `main_token`, as for the other literals.
+    IntegerLiteral,
+    FloatLiteral,
+    CharLiteral,
+    StringLiteral,
+    MultilineStringLiteral,
+    Identifier,
+    PrimitiveType,
+    /// `main_token` is the `.`, followed by the name.
+    EnumLiteral,
+    /// `main_token` is the `error`, with the name two tokens after it.
+    ErrorValue,
+    /// `main_token` is the `error`. `extra_data[lhs..rhs]` are the name tokens.
+    ErrorSetDecl,
+    Unreachable,
+    /// `(lhs)`
+    Grouped,
+
+    /// `extra_data[lhs..rhs]` are the arguments.
+    BuiltinCall,
+    /// `main_token` is the operator, `lhs` the operand.
+    Unary(UnaryOp),
+    /// `main_token` is the operator.
+    Binary(BinaryOp),
+    /// `lhs catch rhs`. The capture comes right after `main_token`, if there is one.
+    Catch,
+    /// `main_token` is the operator, `lhs` the target and `rhs` the value.
+    Assign(AssignOp),
+    /// `lhs..rhs` or `lhs...rhs`, where `rhs` can be missing.
+    Range,
+
+    /// `main_token` is the `(`. `lhs` is the callee, `rhs` is extra: `SubRange`.
+    Call,
+    /// `async lhs`
+    Async,
+    /// `lhs.name`, where `main_token` is the `.` and `rhs` the name token.
+    FieldAccess,
+    /// `lhs[rhs]`
+    Index,
+    /// `lhs[start..end :sentinel]`, with `rhs` being extra: `Slice`.
+    Slice,
+    /// `lhs.*`
+    Deref,
+    /// `lhs.?`
+    Unwrap,
+
+    /// `main_token` is the `{`. `lhs` is the type, missing for `.{}`,
+    /// and `rhs` is extra: `SubRange` of `FieldInit`s.
+    StructInit,
+    /// `.name = lhs`, with `main_token` being the name.
+    FieldInit,
+    /// `main_token` is the `{`. `lhs` is the type, missing for `.{}`,
+    /// and `rhs` is extra: `SubRange` of the elements.
+    ArrayInit,
+
+    /// `?lhs`
+    Optional,
+    /// `lhs!rhs`
+    ErrorUnion,
+    /// `main_token` is the first token of the pointer type. `lhs` is
+    /// extra: `PtrType`, `rhs` is the child type.
+    PtrType,
+    /// `[lhs]child`, with `rhs` being extra: `ArrayType`.
+    ArrayType,
+    /// `anyframe` or `anyframe->lhs`
+    AnyFrameType,
+    /// `main_token` is the `struct`, `enum`, `union` or `opaque`. `lhs` is
+    /// the argument in parentheses, `rhs` is extra: `SubRange` of the members.
+    ContainerDecl,
+    /// `main_token` is the `asm`. `lhs` is the template, `rhs` is extra: `Asm`.
+    Asm,
+    /// `main_token` is the name, followed by `]`, the constraint and `(`.
+    /// `lhs` is the return type, missing when writing to a variable.
+    AsmOutput,
+    /// `main_token` is the name, followed by `]` and the constraint.
+    /// `lhs` is the expression.
+    AsmInput,
+
+    /// `if (lhs)`, with `rhs` being extra: `If`.
+    If,
+    /// `while (lhs)`, with `rhs` being extra: `While`.
+    While,
+    /// `lhs` is extra: `For`, `rhs` is the body.
+    For,
+    /// `switch (lhs)`, with `rhs` being extra: `SubRange` of `SwitchProng`s.
+    Switch,
+    /// `main_token` is the `=>`. `lhs` is extra: `SwitchProng`, `rhs` is the body.
+    SwitchProng,
+    /// `break :lhs rhs`, where `lhs` is the label token.
+    Break,
+    /// `continue :lhs`, where `lhs` is the label token.
+    Continue,
+    /// `return lhs`
+    Return,
+    /// `resume lhs`
+    Resume,
+    /// `nosuspend lhs`
+    NoSuspend,
+    /// `suspend lhs`
+    Suspend,
+}
+
+/// Bits of the `flags` of the nodes and extra data that have them.
+pub mod flags {
+    pub const PUB: u32 = 1 << 0;
+    pub const EXPORT: u32 = 1 << 1;
+    pub const EXTERN: u32 = 1 << 2;
+    pub const INLINE: u32 = 1 << 3;
+    pub const COMPTIME: u32 = 1 << 4;
+    pub const THREADLOCAL: u32 = 1 << 5;
+    pub const NOALIAS: u32 = 1 << 6;
+    /// A parameter or field with a name.
+    pub const NAMED: u32 = 1 << 7;
+    pub const ANYTYPE: u32 = 1 << 8;
+    /// The `...` parameter of C variadic functions.
+    pub const VARARGS: u32 = 1 << 9;
+    /// A return type like `!void`.
+    pub const INFERRED_ERROR: u32 = 1 << 10;
+    pub const CONST: u32 = 1 << 11;
+    pub const VOLATILE: u32 = 1 << 12;
+    pub const ALLOWZERO: u32 = 1 << 13;
+    /// The `else` prong of a switch.
+    pub const ELSE: u32 = 1 << 14;
+}
+
+/// The structs that live in `extra_data`, which are all just a fixed
+/// number of `u32`s.
+pub trait ExtraData: Sized {
+    /// Reads the struct from the start of `extra`.
+    fn read(extra: &[u32]) -> Self;
+    fn write(&self, extra: &mut Vec<u32>);
+}
+
+macro_rules! extra_data {
+    ($($(#[$attr:meta])* pub struct $name:ident { $($(#[$field_attr:meta])* pub $field:ident,)* })*) => {$(
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+        pub struct $name {
+            $($(#[$field_attr])* pub $field: u32,)*
+        }
+
+        impl ExtraData for $name {
+            fn read(extra: &[u32]) -> Self {
+                let mut fields = extra.iter().copied();
+                Self {
+                    $($field: fields.next().expect(concat!("truncated ", stringify!($name))),)*
+                }
+            }
+
+            fn write(&self, extra: &mut Vec<u32>) {
+                $(extra.push(self.$field);)*
+            }
+        }
+    )*};
+}
+
+// Lists are stored as `start` and `end` indices into `extra_data`,
+// and optional nodes and tokens are `0` when they are missing.
+extra_data! {
+    pub struct SubRange {
+        pub start,
+        pub end,
+    }
+
+    pub struct VarDecl {
+        pub flags,
+        /// The library of `extern "c"`, as a token.
+        pub lib,
+        pub ty,
+        pub align,
+        pub addrspace,
+        pub linksection,
+    }
+
+    pub struct FnDecl {
+        pub flags,
+        /// The library of `extern "c"`, as a token.
+        pub lib,
+        pub proto,
+    }
+
+    pub struct FnProto {
+        pub params_start,
+        pub params_end,
+        pub align,
+        pub addrspace,
+        pub linksection,
+        pub callconv,
+        pub flags,
+    }
+
+    pub struct ContainerField {
+        pub flags,
+        pub ty,
+        pub align,
+    }
+
+    pub struct Slice {
+        pub start,
+        pub end,
+        pub sentinel,
+    }
+
+    pub struct PtrType {
+        /// A `PointerSize`, see `CompactAst::pointer_size`.
+        pub size,
+        pub flags,
+        pub sentinel,
+        pub align,
+        pub bit_start,
+        pub bit_end,
+        pub addrspace,
+    }
+
+    pub struct ArrayType {
+        pub sentinel,
+        pub child,
+    }
+
+    pub struct Asm {
+        pub outputs_start,
+        pub outputs_end,
+        pub inputs_start,
+        pub inputs_end,
+        /// The clobbers are string literal tokens.
+        pub clobbers_start,
+        pub clobbers_end,
+    }
+
+    /// The captures are name tokens.
+    pub struct If {
+        pub capture,
+        pub then,
+        pub else_capture,
+        pub else_branch,
+    }
+
+    pub struct While {
+        pub capture,
+        pub cont,
+        pub body,
+        pub else_capture,
+        pub else_branch,
+    }
+
+    pub struct For {
+        pub inputs_start,
+        pub inputs_end,
+        /// The captures are name tokens.
+        pub captures_start,
+        pub captures_end,
+        pub else_branch,
+    }
+
+    pub struct SwitchProng {
+        pub items_start,
+        pub items_end,
+        /// The captures are name tokens.
+        pub captures_start,
+        pub captures_end,
+        pub flags,
+    }
+}
+
+impl<'src> CompactAst<'src> {
+    /// Parses `src` and converts it into the compact form. This goes
+    /// through the tree of `ast::Ast`, as there's only the one parser,
+    /// so it's the result that is small rather than the parsing. Each
+    /// declaration is dropped once it has been converted, so memory use
+    /// peaks at about the size of the tree, plus the compact tokens.
+    pub fn parse(src: &'src str) -> Self {
+        let tree = parser::parse(src);
+        Self::lower(src, tree.errors, tree.decls)
+    }
+
+    /// Converts a tree parsed from `src` into the compact form.
+    pub fn from_tree(src: &'src str, tree: &Ast) -> Self {
+        Self::lower(src, tree.errors.clone(), &tree.decls)
+    }
+
+    /// The tree doesn't keep its tokens, so they're lexed again here.
+    fn lower<'a, D: Borrow<Decl<'a>>>(
+        src: &'src str,
+        errors: Vec<ParseError>,
+        decls: impl IntoIterator<Item = D>,
+    ) -> Self {
+        let mut tokens = TokenList::default();
+        for token in Tokenizer::new(src).results().flatten() {
+            tokens.tags.push(token.1.tag());
+            tokens.starts.push(token.0.start as u32);
+        }
+        tokens.tags.push(TokenTag::Eof);
+        tokens.starts.push(src.len() as u32);
+
+        let mut lower = Lower {
+            ast: CompactAst {
+                src,
+                tokens,
+                nodes: NodeList::default(),
+                extra_data: Vec::new(),
+                errors,
+            },
+        };
+        lower.add(NodeTag::Root, 0, 0, 0);
+        let members: Vec<_> = decls
+            .into_iter()
+            .map(|decl| lower.decl(decl.borrow()))
+            .collect();
+        let members = lower.list(members);
+        lower.ast.nodes.data[0] = NodeData {
+            lhs: members.start,
+            rhs: members.end,
+        };
+        lower.ast
+    }
+
+    pub fn src(&self) -> &'src str {
+        self.src
+    }
+
+    pub fn tokens(&self) -> &TokenList {
+        &self.tokens
+    }
+
+    pub fn nodes(&self) -> &NodeList {
+        &self.nodes
+    }
+
+    pub fn extra_data(&self) -> &[u32] {
+        &self.extra_data
+    }
+
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    /// The members of the file.
+    pub fn root_decls(&self) -> &[NodeIndex] {
+        let data = self.nodes.data[0];
+        self.extra_list(data.lhs, data.rhs)
+    }
+
+    pub fn token_tag(&self, token: TokenIndex) -> TokenTag {
+        self.tokens.tags[token as usize]
+    }
+
+    pub fn token_start(&self, token: TokenIndex) -> usize {
+        self.tokens.starts[token as usize] as usize
+    }
+
+    /// Lexes a token again to get its span and value.
+    pub fn token(&self, token: TokenIndex) -> Token<'src> {
+        let start = self.token_start(token);
+        match Tokenizer::new(&self.src[start..])
+            .keep_comments(true)
+            .next_token()
+        {
+            Some(Token(span, token_type)) => {
+                Token::new(Span::new(start + span.start, start + span.end), token_type)
+            }
+            None => Token::new(Span::new(start, start), TokenType::Eof),
+        }
+    }
+
+    pub fn token_slice(&self, token: TokenIndex) -> &'src str {
+        self.token(token).0.slice(self.src)
+    }
+
+    pub fn node_tag(&self, node: NodeIndex) -> NodeTag {
+        self.nodes.tags[node as usize]
+    }
+
+    pub fn main_token(&self, node: NodeIndex) -> TokenIndex {
+        self.nodes.main_tokens[node as usize]
+    }
+
+    pub fn node_data(&self, node: NodeIndex) -> NodeData {
+        self.nodes.data[node as usize]
+    }
+
+    pub fn extra<T: ExtraData>(&self, index: u32) -> T {
+        T::read(&self.extra_data[index as usize..])
+    }
+
+    pub fn extra_list(&self, start: u32, end: u32) -> &[u32] {
+        &self.extra_data[start as usize..end as usize]
+    }
+
+    pub fn sub_range(&self, range: SubRange) -> &[u32] {
+        self.extra_list(range.start, range.end)
+    }
+
+    /// The label token of a block or loop, as in `blk: {`.
+    pub fn label(&self, node: NodeIndex) -> Option<TokenIndex> {
+        let mut token = self.main_token(node);
+        if self.is_inline(node) {
+            token -= 1;
+        }
+        let is_label = token >= 2
+            && self.token_tag(token - 1) == TokenTag::Colon
+            && self.token_tag(token - 2) == TokenTag::Identifier;
+        is_label.then(|| token - 2)
+    }
+
+    /// Whether a loop is an `inline while` or `inline for`.
+    pub fn is_inline(&self, node: NodeIndex) -> bool {
+        let token = self.main_token(node);
+        matches!(self.node_tag(node), NodeTag::While | NodeTag::For)
+            && self.token_tag(token - 1) == TokenTag::Keyword(KeywordType::Inline)
+    }
+
+    /// The name token of the capture right after `token`, as after a `catch`.
+    pub fn capture_after(&self, token: TokenIndex) -> Option<TokenIndex> {
+        if self.token_tag(token + 1) != TokenTag::Pipe {
+            return None;
+        }
+        match self.token_tag(token + 2) {
+            TokenTag::Asterisk => Some(token + 3),
+            _ => Some(token + 2),
+        }
+    }
+
+    /// Whether the capture with the given name token is a `|*x|` capture.
+    pub fn is_pointer_capture(&self, name: TokenIndex) -> bool {
+        self.token_tag(name - 1) == TokenTag::Asterisk
+    }
+
+    pub fn container_kind(&self, node: NodeIndex) -> ContainerKind {
+        match self.token_tag(self.main_token(node)) {
+            TokenTag::Keyword(KeywordType::Enum) => ContainerKind::Enum,
+            TokenTag::Keyword(KeywordType::Union) => ContainerKind::Union,
+            TokenTag::Keyword(KeywordType::Opaque) => ContainerKind::Opaque,
+            _ => ContainerKind::Struct,
+        }
+    }
+
+    pub fn container_layout(&self, node: NodeIndex) -> ContainerLayout {
+        match self.token_tag(self.main_token(node) - 1) {
+            TokenTag::Keyword(KeywordType::Packed) => ContainerLayout::Packed,
+            TokenTag::Keyword(KeywordType::Extern) => ContainerLayout::Extern,
+            _ => ContainerLayout::Auto,
+        }
+    }
+
+    /// Whether a container is a `union(enum)`, in which case its `lhs`
+    /// is the tag type of the generated enum, if it has one.
+    pub fn is_tagged_union(&self, node: NodeIndex) -> bool {
+        let token = self.main_token(node);
+        self.token_tag(token + 1) == TokenTag::LParen
+            && self.token_tag(token + 2) == TokenTag::Keyword(KeywordType::Enum)
+    }
+
+    pub fn pointer_size(&self, node: NodeIndex) -> PointerSize {
+        let data = self.node_data(node);
+        match self.extra::<PtrType>(data.lhs).size {
+            0 => PointerSize::One,
+            1 => PointerSize::Many,
+            2 => PointerSize::Slice,
+            _ => PointerSize::C,
+        }
+    }
+
+    /// The index of the first token starting at or after `offset`.
+    fn token_at(&self, offset: usize) -> TokenIndex {
+        self.tokens
+            .starts
+            .partition_point(|&start| (start as usize) < offset) as TokenIndex
+    }
+
+    /// The index of the first token from `token` on with the given tag.
+    fn find(&self, token: TokenIndex, tag: TokenTag) -> TokenIndex {
+        let rest = &self.tokens.tags[token as usize..];
+        token + rest.iter().position(|&t| t == tag).unwrap_or(0) as TokenIndex
+    }
+}
+
+/// Converts a tree into a `CompactAst`, adding children before their parents.
+struct Lower<'src> {
+    ast: CompactAst<'src>,
+}
+
+impl Lower<'_> {
+    fn add(&mut self, tag: NodeTag, main_token: TokenIndex, lhs: u32, rhs: u32) -> NodeIndex {
+        let nodes = &mut self.ast.nodes;
+        nodes.tags.push(tag);
+        nodes.main_tokens.push(main_token);
+        nodes.data.push(NodeData { lhs, rhs });
+        nodes.tags.len() as NodeIndex - 1
+    }
+
+    fn extra(&mut self, extra: impl ExtraData) -> u32 {
+        let index = self.ast.extra_data.len() as u32;
+        extra.write(&mut self.ast.extra_data);
+        index
+    }
+
+    fn list(&mut self, items: Vec<u32>) -> SubRange {
+        let start = self.ast.extra_data.len() as u32;
+        self.ast.extra_data.extend(items);
+        SubRange {
+            start,
+            end: self.ast.extra_data.len() as u32,
+        }
+    }
+
+    fn token_at(&self, offset: usize) -> TokenIndex {
+        self.ast.token_at(offset)
+    }
+
+    /// The first token after a node, such as the operator after the lhs of a binary expression.
+    fn token_after(&self, expr: &Expr) -> TokenIndex {
+        self.ast.token_at(expr.span.end)
+    }
+
+    fn find_keyword(&self, offset: usize, keyword: KeywordType) -> TokenIndex {
+        self.ast
+            .find(self.token_at(offset), TokenTag::Keyword(keyword))
+    }
+
+    fn captures(&self, captures: &[Capture]) -> Vec<u32> {
+        captures
+            .iter()
+            .map(|capture| self.token_at(capture.name.span.start))
+            .collect()
+    }
+
+    fn capture(&self, capture: Option<&Capture>) -> TokenIndex {
+        capture.map_or(0, |capture| self.token_at(capture.name.span.start))
+    }
+
+    /// The flags and library token of `export` and `extern` declarations.
+    fn linkage(&self, linkage: Option<&Linkage>, start: usize) -> (u32, TokenIndex) {
+        match linkage {
+            None => (0, 0),
+            Some(Linkage::Export) => (flags::EXPORT, 0),
+            Some(Linkage::Extern(None)) => (flags::EXTERN, 0),
+            Some(Linkage::Extern(Some(_))) => (
+                flags::EXTERN,
+                self.find_keyword(start, KeywordType::Extern) + 1,
+            ),
+        }
+    }
+
+    fn decl(&mut self, decl: &Decl) -> NodeIndex {
+        let is_pub = if decl.is_pub { flags::PUB } else { 0 };
+        let start = decl.span.start;
+        match &decl.kind {
+            DeclKind::Var(var) => self.var_decl(var, is_pub, start),
+            DeclKind::Fn(decl) => self.fn_decl(decl, is_pub, start),
+            DeclKind::Test { name, body } => {
+                let main_token = self.find_keyword(start, KeywordType::Test);
+                let name = self.opt_expr(name.as_ref());
+                let body = self.block(body);
+                self.add(NodeTag::TestDecl, main_token, name, body)
+            }
+            DeclKind::Comptime(body) => {
+                let main_token = self.find_keyword(start, KeywordType::Comptime);
+                let body = self.block(body);
+                self.add(NodeTag::Comptime, main_token, body, 0)
+            }
+            DeclKind::UsingNamespace(expr) => {
+                let main_token = self.find_keyword(start, KeywordType::UsingNamespace);
+                let expr = self.expr(expr);
+                self.add(NodeTag::UsingNamespace, main_token, expr, is_pub)
+            }
+            DeclKind::Field(field) => self.field(field),
+            DeclKind::Error => {
+                let main_token = self.token_at(start);
+                self.add(NodeTag::Error, main_token, 0, 0)
+            }
+        }
+    }
+
+    fn var_decl(&mut self, var: &VarDeclNode, is_pub: u32, start: usize) -> NodeIndex {
+        let (linkage, lib) = self.linkage(var.linkage.as_ref(), start);
+        let mut flags = is_pub | linkage;
+        if var.is_comptime {
+            flags |= flags::COMPTIME;
+        }
+        if var.is_threadlocal {
+            flags |= flags::THREADLOCAL;
+        }
+        let extra = VarDecl {
+            flags,
+            lib,
+            ty: self.opt_expr(var.ty.as_ref()),
+            align: self.opt_expr(var.align.as_ref()),
+            addrspace: self.opt_expr(var.addrspace.as_ref()),
+            linksection: self.opt_expr(var.linksection.as_ref()),
+        };
+        let value = self.opt_expr(var.value.as_ref());
+        let main_token = self.token_at(var.span.start);
+        let extra = self.extra(extra);
+        self.add(NodeTag::VarDecl, main_token, extra, value)
+    }
+
+    fn fn_decl(&mut self, decl: &FnDeclNode, is_pub: u32, start: usize) -> NodeIndex {
+        let (linkage, lib) = self.linkage(decl.linkage.as_ref(), start);
+        let mut flags = is_pub | linkage;
+        if decl.is_inline {
+            flags |= flags::INLINE;
+        }
+        let proto = self.fn_proto(&decl.proto);
+        let body = decl.body.as_ref().map_or(0, |body| self.block(body));
+        let extra = self.extra(FnDecl { flags, lib, proto });
+        let main_token = self.main_token(proto);
+        self.add(NodeTag::FnDecl, main_token, extra, body)
+    }
+
+    fn main_token(&self, node: NodeIndex) -> TokenIndex {
+        self.ast.main_token(node)
+    }
+
+    fn fn_proto(&mut self, proto: &FnProtoNode) -> NodeIndex {
+        let params: Vec<_> = proto.params.iter().map(|param| self.param(param)).collect();
+        let params = self.list(params);
+        let extra = FnProto {
+            params_start: params.start,
+            params_end: params.end,
+            align: self.opt_expr(proto.align.as_ref()),
+            addrspace: self.opt_expr(proto.addrspace.as_ref()),
+            linksection: self.opt_expr(proto.linksection.as_ref()),
+            callconv: self.opt_expr(proto.callconv.as_ref()),
+            flags: if proto.inferred_error {
+                flags::INFERRED_ERROR
+            } else {
+                0
+            },
+        };
+        let return_type = self.expr(&proto.return_type);
+        let main_token = self.token_at(proto.span.start);
+        let extra = self.extra(extra);
+        self.add(NodeTag::FnProto, main_token, extra, return_type)
+    }
+
+    fn param(&mut self, param: &Param) -> NodeIndex {
+        let mut flags = 0;
+        if param.is_comptime {
+            flags |= flags::COMPTIME;
+        }
+        if param.is_noalias {
+            flags |= flags::NOALIAS;
+        }
+        let main_token = match &param.name {
+            Some(name) => {
+                flags |= flags::NAMED;
+                self.token_at(name.span.start)
+            }
+            None => self.token_at(param.span.start),
+        };
+        let ty = match &param.ty {
+            ParamType::Expr(ty) => self.expr(ty),
+            ParamType::AnyType => {
+                flags |= flags::ANYTYPE;
+                0
+            }
+            ParamType::VarArgs => {
+                flags |= flags::VARARGS;
+                0
+            }
+        };
+        self.add(NodeTag::Param, main_token, ty, flags)
+    }
+
+    fn field(&mut self, field: &Field) -> NodeIndex {
+        let mut flags = 0;
+        if field.is_comptime {
+            flags |= flags::COMPTIME;
+        }
+        let main_token = match &field.name {
+            Some(name) => {
+                flags |= flags::NAMED;
+                self.token_at(name.span.start)
+            }
+            None => self.token_at(field.span.start),
+        };
+        let extra = ContainerField {
+            flags,
+            ty: self.opt_expr(field.ty.as_ref()),
+            align: self.opt_expr(field.align.as_ref()),
+        };
+        let value = self.opt_expr(field.value.as_ref());
+        let extra = self.extra(extra);
+        self.add(NodeTag::ContainerField, main_token, extra, value)
+    }
+
+    fn block(&mut self, block: &Block) -> NodeIndex {
+        let stmts: Vec<_> = block.stmts.iter().map(|stmt| self.stmt(stmt)).collect();
+        let stmts = self.list(stmts);
+        let main_token = self.token_at(block.span.start);
+        self.add(NodeTag::Block, main_token, stmts.start, stmts.end)
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> NodeIndex {
+        let main_token = self.token_at(stmt.span.start);
+        match &stmt.kind {
+            StmtKind::Var(var) => self.var_decl(var, 0, stmt.span.start),
+            StmtKind::Defer(expr) => {
+                let expr = self.expr(expr);
+                self.add(NodeTag::Defer, main_token, expr, 0)
+            }
+            StmtKind::Errdefer { body, .. } => {
+                let body = self.expr(body);
+                self.add(NodeTag::Errdefer, main_token, body, 0)
+            }
+            StmtKind::Expr(expr) => self.expr(expr),
+        }
+    }
+
+    fn opt_expr(&mut self, expr: Option<&Expr>) -> NodeIndex {
+        expr.map_or(0, |expr| self.expr(expr))
+    }
+
+    fn exprs(&mut self, exprs: &[Expr]) -> SubRange {
+        let exprs: Vec<_> = exprs.iter().map(|expr| self.expr(expr)).collect();
+        self.list(exprs)
+    }
+
+    fn expr(&mut self, expr: &Expr) -> NodeIndex {
+        let start = self.token_at(expr.span.start);
+        let (tag, main_token, lhs, rhs) = match &expr.kind {
+            ExprKind::Integer(_) => (NodeTag::IntegerLiteral, start, 0, 0),
+            ExprKind::Float(_) => (NodeTag::FloatLiteral, start, 0, 0),
+            ExprKind::Char(_) => (NodeTag::CharLiteral, start, 0, 0),
+            ExprKind::String(_) => (NodeTag::StringLiteral, start, 0, 0),
+            ExprKind::MultilineString(_) => (NodeTag::MultilineStringLiteral, start, 0, 0),
+            ExprKind::Identifier(_) => (NodeTag::Identifier, start, 0, 0),
+            ExprKind::PrimitiveType(_) => (NodeTag::PrimitiveType, start, 0, 0),
+            ExprKind::EnumLiteral(_) => (NodeTag::EnumLiteral, start, 0, 0),
+            ExprKind::ErrorValue(_) => (NodeTag::ErrorValue, start, 0, 0),
+            ExprKind::ErrorSet(names) => {
+                let names = names
+                    .iter()
+                    .map(|name| self.token_at(name.span.start))
+                    .collect();
+                let names = self.list(names);
+                (NodeTag::ErrorSetDecl, start, names.start, names.end)
+            }
+            ExprKind::Unreachable => (NodeTag::Unreachable, start, 0, 0),
+            ExprKind::Error => (NodeTag::Error, start, 0, 0),
+            ExprKind::Grouped(inner) => (NodeTag::Grouped, start, self.expr(inner), 0),
+
+            ExprKind::Builtin { args, .. } => {
+                let args = self.exprs(args);
+                (NodeTag::BuiltinCall, start, args.start, args.end)
+            }
+            ExprKind::Unary { op, expr } => (NodeTag::Unary(*op), start, self.expr(expr), 0),
+            ExprKind::Binary { op, lhs, rhs } => {
+                let main_token = self.token_after(lhs);
+                (
+                    NodeTag::Binary(*op),
+                    main_token,
+                    self.expr(lhs),
+                    self.expr(rhs),
+                )
+            }
+            ExprKind::Catch { lhs, rhs, .. } => {
+                let main_token = self.token_after(lhs);
+                (NodeTag::Catch, main_token, self.expr(lhs), self.expr(rhs))
+            }
+            ExprKind::Assign { op, target, value } => {
+                let main_token = self.token_after(target);
+                (
+                    NodeTag::Assign(*op),
+                    main_token,
+                    self.expr(target),
+                    self.expr(value),
+                )
+            }
+            ExprKind::Range { start: from, end } => {
+                let main_token = self.token_after(from);
+                let from = self.expr(from);
+                (
+                    NodeTag::Range,
+                    main_token,
+                    from,
+                    self.opt_expr(end.as_deref()),
+                )
+            }
+
+            ExprKind::Call { callee, args } => {
+                let main_token = self.token_after(callee);
+                let callee = self.expr(callee);
+                let args = self.exprs(args);
+                (NodeTag::Call, main_token, callee, self.extra(args))
+            }
+            ExprKind::Async(call) => (NodeTag::Async, start, self.expr(call), 0),
+            ExprKind::Field { expr, name } => {
+                let main_token = self.token_after(expr);
+                let name = self.token_at(name.span.start);
+                (NodeTag::FieldAccess, main_token, self.expr(expr), name)
+            }
+            ExprKind::Index { expr, index } => {
+                let main_token = self.token_after(expr);
+                (
+                    NodeTag::Index,
+                    main_token,
+                    self.expr(expr),
+                    self.expr(index),
+                )
+            }
+            ExprKind::Slice {
+                expr,
+                start: from,
+                end,
+                sentinel,
+            } => {
+                let main_token = self.token_after(expr);
+                let expr = self.expr(expr);
+                let slice = Slice {
+                    start: self.expr(from),
+                    end: self.opt_expr(end.as_deref()),
+                    sentinel: self.opt_expr(sentinel.as_deref()),
+                };
+                (NodeTag::Slice, main_token, expr, self.extra(slice))
+            }
+            ExprKind::Deref(inner) => {
+                (NodeTag::Deref, self.token_after(inner), self.expr(inner), 0)
+            }
+            ExprKind::Unwrap(inner) => (
+                NodeTag::Unwrap,
+                self.token_after(inner),
+                self.expr(inner),
+                0,
+            ),
+
+            ExprKind::StructInit { ty, fields } => {
+                let main_token = match ty {
+                    Some(ty) => self.token_after(ty),
+                    None => start + 1,
+                };
+                let ty = self.opt_expr(ty.as_deref());
+                let fields = fields
+                    .iter()
+                    .map(|field| {
+                        let name = self.token_at(field.name.span.start);
+                        let value = self.expr(&field.value);
+                        self.add(NodeTag::FieldInit, name, value, 0)
+                    })
+                    .collect();
+                let fields = self.list(fields);
+                (NodeTag::StructInit, main_token, ty, self.extra(fields))
+            }
+            ExprKind::ArrayInit { ty, elems } => {
+                let main_token = match ty {
+                    Some(ty) => self.token_after(ty),
+                    None => start + 1,
+                };
+                let ty = self.opt_expr(ty.as_deref());
+                let elems = self.exprs(elems);
+                (NodeTag::ArrayInit, main_token, ty, self.extra(elems))
+            }
+
+            ExprKind::Optional(child) => (NodeTag::Optional, start, self.expr(child), 0),
+            ExprKind::ErrorUnion { error, payload } => {
+                let main_token = self.token_after(error);
+                (
+                    NodeTag::ErrorUnion,
+                    main_token,
+                    self.expr(error),
+                    self.expr(payload),
+                )
+            }
+            ExprKind::Pointer(pointer) => {
+                let mut flags = 0;
+                if pointer.is_const {
+                    flags |= flags::CONST;
+                }
+                if pointer.is_volatile {
+                    flags |= flags::VOLATILE;
+                }
+                if pointer.is_allowzero {
+                    flags |= flags::ALLOWZERO;
+                }
+                let (bit_start, bit_end) = match &pointer.bit_range {
+                    Some((bit_start, bit_end)) => (self.expr(bit_start), self.expr(bit_end)),
+                    None => (0, 0),
+                };
+                let extra = PtrType {
+                    size: match pointer.size {
+                        PointerSize::One => 0,
+                        PointerSize::Many => 1,
+                        PointerSize::Slice => 2,
+                        PointerSize::C => 3,
+                    },
+                    flags,
+                    sentinel: self.opt_expr(pointer.sentinel.as_deref()),
+                    align: self.opt_expr(pointer.align.as_deref()),
+                    bit_start,
+                    bit_end,
+                    addrspace: self.opt_expr(pointer.addrspace.as_deref()),
+                };
+                let child = self.expr(&pointer.child);
+                (NodeTag::PtrType, start, self.extra(extra), child)
+            }
+            ExprKind::Array {
+                len,
+                sentinel,
+                child,
+            } => {
+                let len = self.expr(len);
+                let extra = ArrayType {
+                    sentinel: self.opt_expr(sentinel.as_deref()),
+                    child: self.expr(child),
+                };
+                (NodeTag::ArrayType, start, len, self.extra(extra))
+            }
+            ExprKind::AnyFrame(child) => (
+                NodeTag::AnyFrameType,
+                start,
+                self.opt_expr(child.as_deref()),
+                0,
+            ),
+            ExprKind::FnProto(proto) => return self.fn_proto(proto),
+            ExprKind::Container(container) => return self.container(container, expr.span.start),
+            ExprKind::Asm(asm) => return self.asm(asm, expr.span.start, expr.span.end),
+
+            ExprKind::Block(block) => return self.block(block),
+            ExprKind::If(if_expr) => {
+                let cond = self.expr(&if_expr.cond);
+                let extra = If {
+                    capture: self.capture(if_expr.capture.as_ref()),
+                    then: self.expr(&if_expr.then),
+                    else_capture: self.capture(if_expr.else_capture.as_ref()),
+                    else_branch: self.opt_expr(if_expr.else_branch.as_deref()),
+                };
+                (NodeTag::If, start, cond, self.extra(extra))
+            }
+            ExprKind::While(while_expr) => {
+                let main_token = self.find_keyword(expr.span.start, KeywordType::While);
+                let cond = self.expr(&while_expr.cond);
+                let extra = While {
+                    capture: self.capture(while_expr.capture.as_ref()),
+                    cont: self.opt_expr(while_expr.cont.as_deref()),
+                    body: self.expr(&while_expr.body),
+                    else_capture: self.capture(while_expr.else_capture.as_ref()),
+                    else_branch: self.opt_expr(while_expr.else_branch.as_deref()),
+                };
+                (NodeTag::While, main_token, cond, self.extra(extra))
+            }
+            ExprKind::For(for_expr) => {
+                let main_token = self.find_keyword(expr.span.start, KeywordType::For);
+                let inputs = self.exprs(&for_expr.inputs);
+                let captures = self.captures(&for_expr.captures);
+                let captures = self.list(captures);
+                let else_branch = self.opt_expr(for_expr.else_branch.as_deref());
+                let body = self.expr(&for_expr.body);
+                let extra = For {
+                    inputs_start: inputs.start,
+                    inputs_end: inputs.end,
+                    captures_start: captures.start,
+                    captures_end: captures.end,
+                    else_branch,
+                };
+                (NodeTag::For, main_token, self.extra(extra), body)
+            }
+            ExprKind::Switch(switch) => {
+                let cond = self.expr(&switch.cond);
+                let prongs = switch
+                    .prongs
+                    .iter()
+                    .map(|prong| self.switch_prong(prong))
+                    .collect();
+                let prongs = self.list(prongs);
+                (NodeTag::Switch, start, cond, self.extra(prongs))
+            }
+            ExprKind::Break { label, value } => {
+                let label = self.capture_label(label.as_ref());
+                (
+                    NodeTag::Break,
+                    start,
+                    label,
+                    self.opt_expr(value.as_deref()),
+                )
+            }
+            ExprKind::Continue { label } => (
+                NodeTag::Continue,
+                start,
+                self.capture_label(label.as_ref()),
+                0,
+            ),
+            ExprKind::Return(value) => (NodeTag::Return, start, self.opt_expr(value.as_deref()), 0),
+            ExprKind::Resume(inner) => (NodeTag::Resume, start, self.expr(inner), 0),
+            ExprKind::Comptime(inner) => (NodeTag::Comptime, start, self.expr(inner), 0),
+            ExprKind::NoSuspend(inner) => (NodeTag::NoSuspend, start, self.expr(inner), 0),
+            ExprKind::Suspend(inner) => (NodeTag::Suspend, start, self.expr(inner), 0),
+        };
+        self.add(tag, main_token, lhs, rhs)
+    }
+
+    fn capture_label(&self, label: Option<&Ident>) -> TokenIndex {
+        label.map_or(0, |label| self.token_at(label.span.start))
+    }
+
+    fn switch_prong(&mut self, prong: &Prong) -> NodeIndex {
+        let mut flags = 0;
+        if prong.is_inline {
+            flags |= flags::INLINE;
+        }
+        let main_token = match prong.items.last() {
+            _ if prong.is_else => {
+                flags |= flags::ELSE;
+                self.find_keyword(prong.span.start, KeywordType::Else) + 1
+            }
+            Some(item) => self.ast.find(self.token_after(item), TokenTag::EqualArrow),
+            None => self
+                .ast
+                .find(self.token_at(prong.span.start), TokenTag::EqualArrow),
+        };
+        let items = self.exprs(&prong.items);
+        let captures = self.captures(&prong.captures);
+        let captures = self.list(captures);
+        let extra = SwitchProng {
+            items_start: items.start,
+            items_end: items.end,
+            captures_start: captures.start,
+            captures_end: captures.end,
+            flags,
+        };
+        let body = self.expr(&prong.body);
+        let extra = self.extra(extra);
+        self.add(NodeTag::SwitchProng, main_token, extra, body)
+    }
+
+    fn container(&mut self, container: &ContainerDecl, start: usize) -> NodeIndex {
+        let keyword = match container.kind {
+            ContainerKind::Struct => KeywordType::Struct,
+            ContainerKind::Enum => KeywordType::Enum,
+            ContainerKind::Union => KeywordType::Union,
+            ContainerKind::Opaque => KeywordType::Opaque,
+        };
+        let main_token = self.find_keyword(start, keyword);
+        let arg = match &container.arg {
+            Some(ContainerArg::Type(ty)) => self.expr(ty),
+            Some(ContainerArg::TaggedUnion(ty)) => self.opt_expr(ty.as_deref()),
+            None => 0,
+        };
+        let members: Vec<_> = container.decls.iter().map(|decl| self.decl(decl)).collect();
+        let members = self.list(members);
+        let members = self.extra(members);
+        self.add(NodeTag::ContainerDecl, main_token, arg, members)
+    }
+
+    fn asm(&mut self, asm: &AsmExpr, start: usize, end: usize) -> NodeIndex {
+        let main_token = self.token_at(start);
+        let template = self.expr(&asm.template);
+        let outputs = asm
+            .outputs
+            .iter()
+            .map(|output| {
+                let name = self.token_at(output.name.span.start);
+                let ty = match &output.kind {
+                    AsmOutputKind::Var(_) => 0,
+                    AsmOutputKind::Return(ty) => self.expr(ty),
+                };
+                self.add(NodeTag::AsmOutput, name, ty, 0)
+            })
+            .collect();
+        let outputs = self.list(outputs);
+        let inputs = asm
+            .inputs
+            .iter()
+            .map(|input| {
+                let name = self.token_at(input.name.span.start);
+                let expr = self.expr(&input.expr);
+                self.add(NodeTag::AsmInput, name, expr, 0)
+            })
+            .collect();
+        let inputs = self.list(inputs);
+        // The clobbers are the last string literals before the closing `)`.
+        let mut clobbers: Vec<_> = (0..self.token_at(end))
+            .rev()
+            .filter(|&token| self.ast.token_tag(token) == TokenTag::StringLiteral)
+            .take(asm.clobbers.len())
+            .collect();
+        clobbers.reverse();
+        let clobbers = self.list(clobbers);
+        let extra = self.extra(Asm {
+            outputs_start: outputs.start,
+            outputs_end: outputs.end,
+            inputs_start: inputs.start,
+            inputs_end: inputs.end,
+            clobbers_start: clobbers.start,
+            clobbers_end: clobbers.end,
+        });
+        self.add(NodeTag::Asm, main_token, template, extra)
+    }
+}
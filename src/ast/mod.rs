@@ -0,0 +1,5 @@
+pub mod compact;
+mod tree;
+
+pub use compact::CompactAst;
+pub use tree::*;
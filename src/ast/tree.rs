@@ -0,0 +1,568 @@
+use std::borrow::Cow;
+
+use crate::{
+    parser::ParseError,
+    tokenizer::{BigUint, PrimitiveType, Span, StringLiteral},
+};
+
+/// A parsed zig source file. Every zig file is implicitly a struct,
+/// so the root of the tree is just the members of that struct.
+///
+/// Every node in the tree carries the span of source it was parsed from,
+/// either directly or through the `Expr` or `Decl` wrapping it, as is the
+/// case for things like `If` and `FnDecl`.
+///
+/// Like zig's own `std.zig.Ast`, a tree is produced even for source with
+/// errors in it. The errors are collected in `errors`, and the parts that
+/// couldn't be parsed are left as error nodes in the tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ast<'src> {
+    /// Covers the whole source.
+    pub span: Span,
+    /// The `//!` doc comments at the top of the file.
+    pub doc: Vec<Cow<'src, str>>,
+    pub decls: Vec<Decl<'src>>,
+    /// Every error found in the source, in the order they were found.
+    pub errors: Vec<ParseError>,
+}
+
+impl Ast<'_> {
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty()
+    }
+}
+
+/// A member of a container: a declaration, a field, a test or a comptime block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decl<'src> {
+    pub span: Span,
+    /// The `///` doc comments right before the member.
+    pub doc: Vec<Cow<'src, str>>,
+    pub is_pub: bool,
+    pub kind: DeclKind<'src>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeclKind<'src> {
+    Var(VarDecl<'src>),
+    Fn(FnDecl<'src>),
+    /// A `test "name" { ... }` block. The name is a string literal,
+    /// an identifier for doctests, or missing altogether.
+    Test {
+        name: Option<Expr<'src>>,
+        body: Block<'src>,
+    },
+    Comptime(Block<'src>),
+    UsingNamespace(Expr<'src>),
+    Field(ContainerField<'src>),
+    /// A member that couldn't be parsed.
+    Error,
+}
+
+/// A name along with where it was written, such as the name of
+/// a declaration, parameter or field, or the label of a block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ident<'src> {
+    pub span: Span,
+    pub name: Cow<'src, str>,
+}
+
+/// Whether a declaration is exported or comes from somewhere else.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Linkage<'src> {
+    Export,
+    /// `extern`, optionally with the library it comes from as in `extern "c"`.
+    Extern(Option<StringLiteral<'src>>),
+}
+
+/// A `const` or `var` declaration, either in a container or in a block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarDecl<'src> {
+    /// From the `const` or `var` up to the `;`, leaving out
+    /// anything like `pub` or `export` coming before it.
+    pub span: Span,
+    pub is_const: bool,
+    pub is_comptime: bool,
+    pub is_threadlocal: bool,
+    pub linkage: Option<Linkage<'src>>,
+    pub name: Ident<'src>,
+    pub ty: Option<Expr<'src>>,
+    pub align: Option<Expr<'src>>,
+    pub addrspace: Option<Expr<'src>>,
+    pub linksection: Option<Expr<'src>>,
+    pub value: Option<Expr<'src>>,
+}
+
+/// A function declaration. Extern functions don't have a body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FnDecl<'src> {
+    pub linkage: Option<Linkage<'src>>,
+    pub is_inline: bool,
+    pub proto: FnProto<'src>,
+    pub body: Option<Block<'src>>,
+}
+
+/// The signature of a function. Function types are
+/// prototypes without a name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FnProto<'src> {
+    /// From the `fn` up to the end of the return type.
+    pub span: Span,
+    pub name: Option<Ident<'src>>,
+    pub params: Vec<Param<'src>>,
+    pub align: Option<Expr<'src>>,
+    pub addrspace: Option<Expr<'src>>,
+    pub linksection: Option<Expr<'src>>,
+    pub callconv: Option<Expr<'src>>,
+    /// Set for return types like `!void`, where the error set is inferred.
+    pub inferred_error: bool,
+    pub return_type: Box<Expr<'src>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param<'src> {
+    pub span: Span,
+    pub doc: Vec<Cow<'src, str>>,
+    pub is_comptime: bool,
+    pub is_noalias: bool,
+    pub name: Option<Ident<'src>>,
+    pub ty: ParamType<'src>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamType<'src> {
+    Expr(Expr<'src>),
+    AnyType,
+    /// The `...` of C variadic functions.
+    VarArgs,
+}
+
+/// A field of a struct, enum or union. Enum fields and untyped union
+/// fields only have a name, while tuple fields only have a type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerField<'src> {
+    /// Doesn't include the `,` after the field.
+    pub span: Span,
+    pub is_comptime: bool,
+    pub name: Option<Ident<'src>>,
+    pub ty: Option<Expr<'src>>,
+    pub align: Option<Expr<'src>>,
+    /// The default value of a struct field or the value of an enum field.
+    pub value: Option<Expr<'src>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block<'src> {
+    pub span: Span,
+    pub label: Option<Ident<'src>>,
+    pub stmts: Vec<Stmt<'src>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stmt<'src> {
+    pub span: Span,
+    pub kind: StmtKind<'src>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StmtKind<'src> {
+    Var(Box<VarDecl<'src>>),
+    Defer(Expr<'src>),
+    Errdefer {
+        capture: Option<Capture<'src>>,
+        body: Expr<'src>,
+    },
+    /// An expression statement, which includes assignments
+    /// and blocks, loops, `if` and `switch` statements.
+    Expr(Expr<'src>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr<'src> {
+    pub span: Span,
+    pub kind: ExprKind<'src>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind<'src> {
+    Integer(BigUint),
+    Float(f64),
+    Char(char),
+    String(StringLiteral<'src>),
+    MultilineString(StringLiteral<'src>),
+    /// Includes the values `true`, `false`, `null` and `undefined`,
+    /// which are plain identifiers as far as the grammar is concerned.
+    Identifier(Cow<'src, str>),
+    PrimitiveType(PrimitiveType),
+    /// `.foo`
+    EnumLiteral(Cow<'src, str>),
+    /// `error.Foo`
+    ErrorValue(Cow<'src, str>),
+    /// `error{ Foo, Bar }`
+    ErrorSet(Vec<Ident<'src>>),
+    Unreachable,
+    /// A statement or part of an expression that couldn't be parsed.
+    Error,
+    /// `(expr)`, kept so that tools can tell the parentheses were there.
+    Grouped(Box<Expr<'src>>),
+
+    Builtin {
+        name: Cow<'src, str>,
+        args: Vec<Expr<'src>>,
+    },
+    Unary {
+        op: UnaryOp,
+        expr: Box<Expr<'src>>,
+    },
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expr<'src>>,
+        rhs: Box<Expr<'src>>,
+    },
+    /// `lhs catch |err| rhs`. Kept apart from the other binary
+    /// operators because of the optional capture.
+    Catch {
+        lhs: Box<Expr<'src>>,
+        capture: Option<Capture<'src>>,
+        rhs: Box<Expr<'src>>,
+    },
+    /// Only found in statements and the continue expression of `while` loops.
+    Assign {
+        op: AssignOp,
+        target: Box<Expr<'src>>,
+        value: Box<Expr<'src>>,
+    },
+    /// `start..end` in `for` loop inputs, where the end is optional,
+    /// and `start...end` in switch prongs.
+    Range {
+        start: Box<Expr<'src>>,
+        end: Option<Box<Expr<'src>>>,
+    },
+
+    Call {
+        callee: Box<Expr<'src>>,
+        args: Vec<Expr<'src>>,
+    },
+    /// `async f()`
+    Async(Box<Expr<'src>>),
+    Field {
+        expr: Box<Expr<'src>>,
+        name: Ident<'src>,
+    },
+    Index {
+        expr: Box<Expr<'src>>,
+        index: Box<Expr<'src>>,
+    },
+    Slice {
+        expr: Box<Expr<'src>>,
+        start: Box<Expr<'src>>,
+        end: Option<Box<Expr<'src>>>,
+        sentinel: Option<Box<Expr<'src>>>,
+    },
+    /// `ptr.*`
+    Deref(Box<Expr<'src>>),
+    /// `optional.?`
+    Unwrap(Box<Expr<'src>>),
+
+    /// `T{ .a = 1 }` or `.{ .a = 1 }`. Empty initializers are struct initializers.
+    StructInit {
+        ty: Option<Box<Expr<'src>>>,
+        fields: Vec<FieldInit<'src>>,
+    },
+    /// `T{ 1, 2 }` or `.{ 1, 2 }`
+    ArrayInit {
+        ty: Option<Box<Expr<'src>>>,
+        elems: Vec<Expr<'src>>,
+    },
+
+    /// `?T`
+    Optional(Box<Expr<'src>>),
+    /// `E!T`
+    ErrorUnion {
+        error: Box<Expr<'src>>,
+        payload: Box<Expr<'src>>,
+    },
+    Pointer(PointerType<'src>),
+    /// `[len]T` or `[len:sentinel]T`. Inferred lengths are the identifier `_`.
+    Array {
+        len: Box<Expr<'src>>,
+        sentinel: Option<Box<Expr<'src>>>,
+        child: Box<Expr<'src>>,
+    },
+    /// `anyframe` or `anyframe->T`
+    AnyFrame(Option<Box<Expr<'src>>>),
+    FnProto(Box<FnProto<'src>>),
+    Container(ContainerDecl<'src>),
+    Asm(Asm<'src>),
+
+    Block(Block<'src>),
+    If(Box<If<'src>>),
+    While(Box<While<'src>>),
+    For(Box<For<'src>>),
+    Switch(Switch<'src>),
+    Break {
+        label: Option<Ident<'src>>,
+        value: Option<Box<Expr<'src>>>,
+    },
+    Continue {
+        label: Option<Ident<'src>>,
+    },
+    Return(Option<Box<Expr<'src>>>),
+    Resume(Box<Expr<'src>>),
+    Comptime(Box<Expr<'src>>),
+    NoSuspend(Box<Expr<'src>>),
+    Suspend(Box<Expr<'src>>),
+}
+
+/// A `.name = value` in a struct initializer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldInit<'src> {
+    /// From the `.` up to the end of the value.
+    pub span: Span,
+    pub name: Ident<'src>,
+    pub value: Expr<'src>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    /// `!`
+    Not,
+    /// `-`
+    Negate,
+    /// `-%`
+    NegateWrap,
+    /// `~`
+    BitNot,
+    /// `&`
+    AddressOf,
+    Try,
+    Await,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Or,
+    And,
+
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+    LessEqual,
+    GreaterEqual,
+
+    BitAnd,
+    BitXor,
+    BitOr,
+    OrElse,
+
+    Shl,
+    Shr,
+    ShlSat,
+
+    Add,
+    Sub,
+    /// `++`
+    Concat,
+    AddWrap,
+    SubWrap,
+    AddSat,
+    SubSat,
+
+    Mul,
+    Div,
+    Mod,
+    /// `**`
+    Repeat,
+    MulWrap,
+    MulSat,
+    /// `||`
+    MergeErrorSets,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignOp {
+    Assign,
+    Mul,
+    MulWrap,
+    MulSat,
+    Div,
+    Mod,
+    Add,
+    AddWrap,
+    AddSat,
+    Sub,
+    SubWrap,
+    SubSat,
+    Shl,
+    ShlSat,
+    Shr,
+    BitAnd,
+    BitXor,
+    BitOr,
+}
+
+/// A name bound by `|x|` after an `if`, `while`, `for`, `switch`,
+/// `catch` or `errdefer`. `|*x|` captures a pointer to the payload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capture<'src> {
+    /// Covers the `*` of pointer captures, but not the `|`s.
+    pub span: Span,
+    pub is_pointer: bool,
+    pub name: Ident<'src>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct If<'src> {
+    pub cond: Box<Expr<'src>>,
+    pub capture: Option<Capture<'src>>,
+    pub then: Box<Expr<'src>>,
+    /// The `|err|` of an `else` branch when the condition is an error union.
+    pub else_capture: Option<Capture<'src>>,
+    pub else_branch: Option<Box<Expr<'src>>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct While<'src> {
+    pub label: Option<Ident<'src>>,
+    pub is_inline: bool,
+    pub cond: Box<Expr<'src>>,
+    pub capture: Option<Capture<'src>>,
+    /// The `: (i += 1)` part, run after every iteration.
+    pub cont: Option<Box<Expr<'src>>>,
+    pub body: Box<Expr<'src>>,
+    pub else_capture: Option<Capture<'src>>,
+    pub else_branch: Option<Box<Expr<'src>>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct For<'src> {
+    pub label: Option<Ident<'src>>,
+    pub is_inline: bool,
+    /// The things iterated over, which are either
+    /// expressions or `Range`s such as `0..`.
+    pub inputs: Vec<Expr<'src>>,
+    pub captures: Vec<Capture<'src>>,
+    pub body: Box<Expr<'src>>,
+    pub else_branch: Option<Box<Expr<'src>>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Switch<'src> {
+    pub cond: Box<Expr<'src>>,
+    pub prongs: Vec<SwitchProng<'src>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwitchProng<'src> {
+    pub span: Span,
+    pub is_inline: bool,
+    /// The values matched by the prong, which can include `Range`s.
+    /// Empty for the `else` prong.
+    pub items: Vec<Expr<'src>>,
+    pub is_else: bool,
+    /// The payload and optionally the tag, as in `|payload, tag|`.
+    pub captures: Vec<Capture<'src>>,
+    pub body: Expr<'src>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerSize {
+    /// `*T`
+    One,
+    /// `[*]T`
+    Many,
+    /// `[]T`
+    Slice,
+    /// `[*c]T`
+    C,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointerType<'src> {
+    pub size: PointerSize,
+    pub sentinel: Option<Box<Expr<'src>>>,
+    pub align: Option<Box<Expr<'src>>>,
+    /// The `a:b` bit range of `align(n:a:b)`, for pointers into packed structs.
+    pub bit_range: Option<(Box<Expr<'src>>, Box<Expr<'src>>)>,
+    pub addrspace: Option<Box<Expr<'src>>>,
+    pub is_const: bool,
+    pub is_volatile: bool,
+    pub is_allowzero: bool,
+    pub child: Box<Expr<'src>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerKind {
+    Struct,
+    Enum,
+    Union,
+    Opaque,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerLayout {
+    Auto,
+    Packed,
+    Extern,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContainerArg<'src> {
+    /// The tag type of `enum(u8)`, or the backing integer of `packed struct(u32)`.
+    Type(Box<Expr<'src>>),
+    /// `union(enum)`, optionally with the tag type of the
+    /// generated enum as in `union(enum(u8))`.
+    TaggedUnion(Option<Box<Expr<'src>>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerDecl<'src> {
+    pub kind: ContainerKind,
+    pub layout: ContainerLayout,
+    pub arg: Option<ContainerArg<'src>>,
+    pub doc: Vec<Cow<'src, str>>,
+    pub decls: Vec<Decl<'src>>,
+}
+
+/// An inline assembly expression.
+///
+/// ```zig
+/// asm volatile ("syscall"
+///     : [ret] "={rax}" (-> usize),
+///     : [number] "{rax}" (number),
+///     : "rcx", "r11"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Asm<'src> {
+    pub is_volatile: bool,
+    pub template: Box<Expr<'src>>,
+    pub outputs: Vec<AsmOutput<'src>>,
+    pub inputs: Vec<AsmInput<'src>>,
+    pub clobbers: Vec<StringLiteral<'src>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsmOutput<'src> {
+    pub span: Span,
+    pub name: Ident<'src>,
+    pub constraint: StringLiteral<'src>,
+    pub kind: AsmOutputKind<'src>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AsmOutputKind<'src> {
+    /// `(x)`, writing the output to the variable `x`.
+    Var(Ident<'src>),
+    /// `(-> T)`, making the output the value of the asm expression.
+    Return(Box<Expr<'src>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsmInput<'src> {
+    pub span: Span,
+    pub name: Ident<'src>,
+    pub constraint: StringLiteral<'src>,
+    pub expr: Expr<'src>,
+}
@@ -0,0 +1,303 @@
+use std::fmt::Write;
+
+use crate::{
+    interp::EvalError,
+    parser::ParseError,
+    source::{LineIndex, SourceFile},
+    tokenizer::{LexError, Position, Span},
+};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const CYAN: &str = "\x1b[1;36m";
+const BLUE: &str = "\x1b[1;34m";
+const GREEN: &str = "\x1b[1;32m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Note => "note",
+        }
+    }
+
+    fn color(&self) -> &'static str {
+        match self {
+            Self::Error => RED,
+            Self::Warning => YELLOW,
+            Self::Note => CYAN,
+        }
+    }
+}
+
+/// A span of source with an optional message explaining
+/// what it has to do with the diagnostic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub(crate) span: Span,
+    pub(crate) message: Option<String>,
+}
+
+impl Label {
+    pub fn new(span: Span) -> Self {
+        Self {
+            span,
+            message: None,
+        }
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+}
+
+/// A report about a problem in some source, rendered in the style of
+/// rustc and zig: a `file:line:col` header, the offending line with the
+/// primary span underlined, any secondary spans, and trailing notes and help.
+///
+/// ```text
+/// main.zig:1:15: error: invalid digit '2' for binary base
+///   |
+/// 1 | const x = 0b102;
+///   |               ^ not a binary digit
+///   |
+///   = help: binary literals only use the digits 0 and 1
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub(crate) severity: Severity,
+    pub(crate) message: String,
+    pub(crate) primary: Label,
+    pub(crate) secondary: Vec<Label>,
+    pub(crate) notes: Vec<String>,
+    pub(crate) help: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>, span: Span) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            primary: Label::new(span),
+            secondary: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>, span: Span) -> Self {
+        Self::new(Severity::Error, message, span)
+    }
+
+    pub fn warning(message: impl Into<String>, span: Span) -> Self {
+        Self::new(Severity::Warning, message, span)
+    }
+
+    /// Sets the message shown next to the underline of the primary span.
+    pub fn with_label(mut self, message: impl Into<String>) -> Self {
+        self.primary.message = Some(message.into());
+        self
+    }
+
+    /// Adds another span relevant to the diagnostic, such as a previous declaration.
+    pub fn with_secondary(mut self, label: Label) -> Self {
+        self.secondary.push(label);
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help.push(help.into());
+        self
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn span(&self) -> Span {
+        self.primary.span
+    }
+
+    /// Renders the diagnostic for a terminal. `src` has to be the source
+    /// the spans point into, and `file_name` is only used for the header.
+    /// With `color` set the output contains ANSI escape codes.
+    pub fn render(&self, file_name: &str, src: &str, color: bool) -> String {
+        self.render_with(file_name, src, &LineIndex::new(src), color)
+    }
+
+    /// Renders the diagnostic against a `SourceFile`, reusing its line index.
+    pub fn render_file(&self, file: &SourceFile, color: bool) -> String {
+        self.render_with(file.name(), file.src(), file.line_index(), color)
+    }
+
+    fn render_with(&self, file_name: &str, src: &str, lines: &LineIndex, color: bool) -> String {
+        let paint = |code: &'static str| if color { code } else { "" };
+        let reset = paint(RESET);
+        let line_text = |line: usize| {
+            let text = lines.line_span(line).map_or("", |span| span.slice(src));
+            let text = text.strip_suffix('\n').unwrap_or(text);
+            text.strip_suffix('\r').unwrap_or(text)
+        };
+
+        // Terminals advance one cell per character, not per byte, so the
+        // column in the header is counted in unicode scalar values.
+        let start = lines.line_col(self.primary.span.start);
+        let start = Position::in_line(start.line, line_text(start.line), start.col);
+        let mut labels: Vec<(&Label, bool)> = vec![(&self.primary, true)];
+        labels.extend(self.secondary.iter().map(|label| (label, false)));
+        let gutter = labels
+            .iter()
+            .map(|(label, _)| lines.line_col(label.span.start).line + 1)
+            .max()
+            .unwrap_or(1)
+            .to_string()
+            .len();
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{bold}{file_name}:{}:{}: {}{}:{reset}{bold} {}{reset}",
+            start.line + 1,
+            start.char_col + 1,
+            paint(self.severity.color()),
+            self.severity.name(),
+            self.message,
+            bold = paint(BOLD),
+        );
+
+        let blue = paint(BLUE);
+        let _ = writeln!(out, "{blue}{:gutter$} |{reset}", "");
+        // Labels are shown in source order, with all labels on the
+        // same line sharing a single copy of that line.
+        labels.sort_by_key(|(label, primary)| (label.span.start, !primary));
+        let mut previous_line = None;
+        for (label, primary) in labels {
+            let position = lines.line_col(label.span.start);
+            let line = line_text(position.line);
+            if previous_line != Some(position.line) {
+                let _ = writeln!(out, "{blue}{:>gutter$} |{reset} {line}", position.line + 1);
+                previous_line = Some(position.line);
+            }
+
+            // Spans running over multiple lines are underlined up to the
+            // end of their first line. Empty spans still get a single marker.
+            let col = position.col.min(line.len());
+            let mut end = (col + label.span.len()).min(line.len());
+            while !line.is_char_boundary(end) {
+                end += 1;
+            }
+            let width = line[col..end].chars().count().max(1);
+            let (marker, marker_color) = if primary {
+                ('^', self.severity.color())
+            } else {
+                ('-', BLUE)
+            };
+            let _ = write!(
+                out,
+                "{blue}{:gutter$} |{reset} {}{}{}",
+                "",
+                indentation(&line[..col]),
+                paint(marker_color),
+                marker.to_string().repeat(width),
+            );
+            if let Some(message) = &label.message {
+                let _ = write!(out, " {message}");
+            }
+            let _ = writeln!(out, "{reset}");
+        }
+
+        if !self.notes.is_empty() || !self.help.is_empty() {
+            let _ = writeln!(out, "{blue}{:gutter$} |{reset}", "");
+        }
+        for note in &self.notes {
+            let _ = writeln!(
+                out,
+                "{blue}{:gutter$} ={reset} {}note:{reset} {note}",
+                "",
+                paint(BOLD)
+            );
+        }
+        for help in &self.help {
+            let _ = writeln!(
+                out,
+                "{blue}{:gutter$} ={reset} {}help:{reset} {help}",
+                "",
+                paint(GREEN)
+            );
+        }
+        out
+    }
+}
+
+/// Builds the whitespace that lines a marker up under the character
+/// right after `prefix`, keeping tabs so they line up the same way.
+fn indentation(prefix: &str) -> String {
+    prefix
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect()
+}
+
+impl From<&LexError> for Diagnostic {
+    fn from(error: &LexError) -> Self {
+        Diagnostic::error(error.kind().to_string(), error.span())
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(error: &ParseError) -> Self {
+        Diagnostic::error(error.kind().to_string(), error.span())
+    }
+}
+
+/// The calls that led to the error become secondary labels, innermost
+/// first, with runs of recursive calls from the same place shown once.
+impl From<&EvalError> for Diagnostic {
+    fn from(error: &EvalError) -> Self {
+        let mut diagnostic = Diagnostic::error(error.kind().to_string(), error.span());
+        let trace = error.trace();
+        let mut i = 0;
+        while i < trace.len() {
+            let frame = &trace[i];
+            let repeats = trace[i..]
+                .iter()
+                .take_while(|other| *other == frame)
+                .count();
+            i += repeats;
+            // `main` itself isn't called from anywhere in the source.
+            if frame.span.end() == frame.span.start() {
+                continue;
+            }
+            let call = match frame.comptime {
+                true => "comptime call",
+                false => "call",
+            };
+            let message = match repeats {
+                1 => format!("in {call} to '{}'", frame.name),
+                _ => format!("in {call} to '{}', {repeats} times", frame.name),
+            };
+            diagnostic = diagnostic.with_secondary(Label::new(frame.span).with_message(message));
+        }
+        diagnostic
+    }
+}
@@ -0,0 +1,823 @@
+use crate::{
+    parser::{self, ParseError},
+    tokenizer::{ConcreteToken, KeywordType, TokenTag, Tokenizer, Trivia, TriviaKind},
+};
+
+use super::spacing::{
+    continues_after_block, ends_operand, hugs_paren, is_ambiguous_operator, is_binary_operator,
+    is_prefix_operator, Last,
+};
+
+const INDENT: &str = "    ";
+
+/// Formats zig source the way `zig fmt` does. Source with syntax errors
+/// isn't touched, as there is no telling what its layout should be.
+pub fn format(src: &str) -> Result<String, Vec<ParseError>> {
+    let ast = parser::parse(src);
+    if ast.has_errors() {
+        return Err(ast.errors);
+    }
+    Ok(Formatter::new(src).format())
+}
+
+/// Checks if `src` is formatted already, as `zig fmt --check` does.
+pub fn is_formatted(src: &str) -> Result<bool, Vec<ParseError>> {
+    Ok(format(src)? == src)
+}
+
+/// What a pair of brackets is for, which decides
+/// how their contents are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GroupKind {
+    /// The whole file, which is a container without the braces.
+    Root,
+    Block,
+    /// The members of a `struct`, `enum`, `union` or `opaque`.
+    Container,
+    Switch,
+    /// `.{ ... }` or `T{ ... }`.
+    InitList,
+    ErrorSet,
+    Call,
+    FnParams,
+    /// The parentheses after `if`, `while`, `for` and `switch`,
+    /// and around the continue expression of a `while`.
+    Control,
+    /// The parentheses after keywords like `align` and `callconv`.
+    KeywordArgs,
+    Parens,
+    /// The brackets of types like `[]T` and `[*:0]T`.
+    TypePrefix,
+    Index,
+}
+
+/// Something that was seen before the `{` it applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pending {
+    FnBody,
+    Container,
+    Switch,
+    Block,
+}
+
+#[derive(Debug)]
+struct Group {
+    kind: GroupKind,
+    /// Whether every element goes on its own line.
+    multiline: bool,
+    open: usize,
+    close: usize,
+    /// The indentation of the lines inside the group.
+    indent: usize,
+    /// Set while the current statement or element spans more than one line.
+    continuation: bool,
+    /// Set after the `=>` of a switch prong, whose items are also separated by commas.
+    in_prong: bool,
+    pending: Option<Pending>,
+}
+
+/// A formatter working on the tokens and comments of the source rather
+/// than on the syntax tree. Brackets are matched up into groups, which are
+/// laid out one element per line when they end in a trailing comma, the
+/// same way `zig fmt` decides it, and on a single line otherwise. Blocks
+/// and switches always get one statement or prong per line.
+struct Formatter<'src> {
+    src: &'src str,
+    tokens: Vec<ConcreteToken<'src>>,
+    tags: Vec<TokenTag>,
+    /// For every opening bracket, the index of the matching closing one.
+    closers: Vec<usize>,
+    groups: Vec<Group>,
+    out: String,
+    at_line_start: bool,
+    /// The indentation of the line being written.
+    line_indent: usize,
+    last: Last,
+    in_capture: bool,
+    /// The kind of the group the last token closed, if it was a closing bracket.
+    closed: Option<GroupKind>,
+    /// Set after a comment, which can't have anything after it on the same line.
+    force_newline: bool,
+    /// The indentation of the line after a multiline string, right after one.
+    string_end: Option<usize>,
+}
+
+impl<'src> Formatter<'src> {
+    fn new(src: &'src str) -> Self {
+        let tokens: Vec<_> = Tokenizer::new(src).lossless().collect();
+        let tags: Vec<_> = tokens
+            .iter()
+            .map(|token| token.token().token_type().tag())
+            .collect();
+        let mut closers = vec![usize::MAX; tags.len()];
+        let mut open = Vec::new();
+        for (i, tag) in tags.iter().enumerate() {
+            match tag {
+                TokenTag::LParen | TokenTag::LBracket | TokenTag::LBrace => open.push(i),
+                TokenTag::RParen | TokenTag::RBracket | TokenTag::RBrace => {
+                    if let Some(start) = open.pop() {
+                        closers[start] = i;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let root = Group {
+            kind: GroupKind::Root,
+            multiline: true,
+            open: 0,
+            close: tags.len(),
+            indent: 0,
+            continuation: false,
+            in_prong: false,
+            pending: None,
+        };
+        Self {
+            src,
+            tokens,
+            tags,
+            closers,
+            groups: vec![root],
+            out: String::new(),
+            at_line_start: true,
+            line_indent: 0,
+            last: Last::Other,
+            in_capture: false,
+            closed: None,
+            force_newline: false,
+            string_end: None,
+        }
+    }
+
+    fn format(mut self) -> String {
+        let mut i = 0;
+        while i < self.tokens.len() {
+            i = self.token(i) + 1;
+        }
+        let len = self.out.trim_end().len();
+        self.out.truncate(len);
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        self.out
+    }
+
+    fn group(&self) -> &Group {
+        self.groups.last().expect("the root group is never closed")
+    }
+
+    fn group_mut(&mut self) -> &mut Group {
+        self.groups
+            .last_mut()
+            .expect("the root group is never closed")
+    }
+
+    fn tag(&self, i: usize) -> TokenTag {
+        self.tags.get(i).copied().unwrap_or(TokenTag::Eof)
+    }
+
+    /// Writes token `i` along with the comments around it, and returns
+    /// the index of the last token written.
+    fn token(&mut self, i: usize) -> usize {
+        let tag = self.tags[i];
+        self.leading_comments(i);
+
+        let closing = matches!(
+            tag,
+            TokenTag::RParen | TokenTag::RBracket | TokenTag::RBrace
+        ) && self.groups.len() > 1
+            && self.group().close == i;
+        let closed = closing.then(|| self.groups.pop().expect("checked above"));
+        self.separate(i, closed.as_ref());
+        if tag == TokenTag::Eof {
+            return i;
+        }
+
+        let binary = self.is_binary(i);
+        let mut last = i;
+        match tag {
+            TokenTag::LParen | TokenTag::LBracket | TokenTag::LBrace => {
+                if tag == TokenTag::LParen && self.is_asm(i) {
+                    last = self.asm(i);
+                    self.last = Last::Operand;
+                    self.closed = None;
+                    self.trailing_comments(last);
+                    return last;
+                }
+                self.open(i);
+                self.write_token(i);
+            }
+            TokenTag::MultilineStringLiteral => {
+                // A string starting an element of a list laid out one per
+                // line is indented like the other elements. Otherwise its
+                // lines go one level deeper than the statement or list it's
+                // in, and whatever comes after them goes back to that.
+                let (indent, end_indent) = match self.at_line_start {
+                    true => (self.line_indent, self.line_indent),
+                    false => (self.group().indent + 1, self.group().indent),
+                };
+                for (n, line) in self.tokens[i].text().split('\n').enumerate() {
+                    if n > 0 || !self.at_line_start {
+                        self.newline(indent, false);
+                    }
+                    self.write(line.trim_start().trim_end_matches('\r'));
+                }
+                self.string_end = Some(end_indent);
+            }
+            _ => self.write_token(i),
+        }
+
+        self.last = match tag {
+            _ if binary => Last::Binary,
+            TokenTag::Pipe if self.in_capture => Last::CaptureClose,
+            TokenTag::Pipe if self.opens_capture(i) => Last::CaptureOpen,
+            TokenTag::RBracket
+                if closed.as_ref().map(|g| g.kind) == Some(GroupKind::TypePrefix) =>
+            {
+                Last::Prefix
+            }
+            TokenTag::RParen | TokenTag::RBracket | TokenTag::RBrace => {
+                match closed.as_ref().map(|g| g.kind) {
+                    Some(GroupKind::Control | GroupKind::FnParams | GroupKind::KeywordArgs) => {
+                        Last::Other
+                    }
+                    _ => Last::Operand,
+                }
+            }
+            TokenTag::MinusArrow => Last::Prefix,
+            _ if is_ambiguous_operator(tag) || is_prefix_operator(tag) => Last::Prefix,
+            _ if ends_operand(tag) => Last::Operand,
+            _ => Last::Other,
+        };
+        match self.last {
+            Last::CaptureOpen => self.in_capture = true,
+            Last::CaptureClose => self.in_capture = false,
+            _ => {}
+        }
+        self.closed = closed.map(|group| group.kind);
+
+        match tag {
+            TokenTag::Keyword(KeywordType::Fn) => self.group_mut().pending = Some(Pending::FnBody),
+            TokenTag::Keyword(
+                KeywordType::Struct | KeywordType::Enum | KeywordType::Union | KeywordType::Opaque,
+            ) => self.group_mut().pending = Some(Pending::Container),
+            TokenTag::Keyword(KeywordType::Switch) => {
+                self.group_mut().pending = Some(Pending::Switch)
+            }
+            TokenTag::Keyword(KeywordType::Test) => self.group_mut().pending = Some(Pending::Block),
+            TokenTag::Semicolon | TokenTag::Comma | TokenTag::Equal => {
+                self.group_mut().pending = None
+            }
+            TokenTag::EqualArrow => self.group_mut().in_prong = true,
+            TokenTag::DocComment | TokenTag::ContainerDocComment => self.force_newline = true,
+            _ => {}
+        }
+
+        // Lists laid out one element per line always end in a comma.
+        let group = self.group();
+        let needs_comma = group.multiline
+            && group.close == i + 1
+            && group.open != i
+            && tag != TokenTag::Comma
+            && !matches!(
+                group.kind,
+                GroupKind::Root
+                    | GroupKind::Block
+                    | GroupKind::Container
+                    | GroupKind::Control
+                    | GroupKind::KeywordArgs
+            );
+        if needs_comma {
+            self.write(",");
+        }
+        self.trailing_comments(i);
+        last
+    }
+
+    /// Writes the comments on the lines before token `i`, each on a line of its own.
+    fn leading_comments(&mut self, i: usize) {
+        let after_open = i > 0 && self.group().open == i - 1 && self.group().multiline;
+        let mut newlines = 0;
+        for n in 0..self.tokens[i].leading().len() {
+            let trivia = &self.tokens[i].leading()[n];
+            match trivia.kind() {
+                TriviaKind::Newline => newlines += 1,
+                TriviaKind::Whitespace => {}
+                TriviaKind::Comment => {
+                    let text = trivia.text().trim_end();
+                    let group = self.group();
+                    let indent = group.indent + group.continuation as usize;
+                    self.newline(indent, newlines >= 2 && !after_open);
+                    self.write(text);
+                    self.force_newline = true;
+                    newlines = 0;
+                }
+            }
+        }
+    }
+
+    /// Writes the comment after token `i` on the same line, if there is one.
+    fn trailing_comments(&mut self, i: usize) {
+        for n in 0..self.tokens[i].trailing().len() {
+            let trivia = &self.tokens[i].trailing()[n];
+            if trivia.kind() == TriviaKind::Comment {
+                let text = trivia.text().trim_end();
+                self.space();
+                self.write(text);
+                self.force_newline = true;
+            }
+        }
+    }
+
+    /// The number of line breaks in the source right before token `i`,
+    /// after any comments.
+    fn newlines_before(&self, i: usize) -> usize {
+        self.tokens[i]
+            .leading()
+            .iter()
+            .rev()
+            .take_while(|trivia| trivia.kind() != TriviaKind::Comment)
+            .filter(|trivia| trivia.kind() == TriviaKind::Newline)
+            .count()
+    }
+
+    /// Writes whatever goes between the last token and token `i`:
+    /// nothing, a space or a line break.
+    fn separate(&mut self, i: usize, closed: Option<&Group>) {
+        if i == 0 {
+            // Only comments at the top of the file can come before it.
+            if self.force_newline {
+                self.newline(0, self.newlines_before(0) >= 2);
+            }
+            return;
+        }
+        let tag = self.tags[i];
+        let prev = self.tags[i - 1];
+        let blank = self.newlines_before(i) >= 2;
+        let group = self.group();
+        let statements = matches!(
+            group.kind,
+            GroupKind::Root | GroupKind::Block | GroupKind::Container | GroupKind::Switch
+        );
+
+        if let Some(closed) = closed.filter(|closed| closed.multiline) {
+            return self.newline(closed.indent.saturating_sub(1), false);
+        }
+        if group.multiline && group.open == i - 1 && group.kind != GroupKind::Root {
+            return self.newline(group.indent, false);
+        }
+        let ends_statement = match prev {
+            TokenTag::Semicolon => statements,
+            TokenTag::Comma if group.kind == GroupKind::Switch => group.in_prong,
+            TokenTag::Comma => group.multiline && group.kind != GroupKind::Block,
+            TokenTag::DocComment | TokenTag::ContainerDocComment => true,
+            TokenTag::RBrace => {
+                statements
+                    && matches!(
+                        self.closed,
+                        Some(GroupKind::Block | GroupKind::Switch | GroupKind::Container)
+                    )
+                    && !continues_after_block(self.tokens[i].token())
+            }
+            _ => false,
+        };
+        let is_doc = matches!(tag, TokenTag::DocComment | TokenTag::ContainerDocComment);
+        if ends_statement || is_doc {
+            let indent = group.indent;
+            let group = self.group_mut();
+            group.continuation = false;
+            group.in_prong = false;
+            return self.newline(indent, blank);
+        }
+        // Multiline strings always start on a new line, which isn't a
+        // break in the expression, so they're left to `token`.
+        if matches!(tag, TokenTag::Eof | TokenTag::MultilineStringLiteral) {
+            return;
+        }
+
+        // Long expressions can be broken after a binary operator,
+        // with the rest indented one level deeper.
+        let broken = self.newlines_before(i) > 0 && self.last == Last::Binary;
+        if broken && (statements || group.multiline) {
+            let indent = group.indent + 1;
+            self.group_mut().continuation = true;
+            return self.newline(indent, false);
+        }
+        if let Some(indent) = self.string_end {
+            return self.newline(indent, false);
+        }
+        if self.force_newline {
+            let indent = group.indent + group.continuation as usize;
+            return self.newline(indent, false);
+        }
+
+        if self.space_before(i, closed) {
+            self.space();
+        }
+    }
+
+    fn space_before(&self, i: usize, closed: Option<&Group>) -> bool {
+        use TokenTag::*;
+        let tag = self.tags[i];
+        let prev = self.tags[i - 1];
+        let group = self.group();
+
+        if tag == Pipe && self.in_capture {
+            return false;
+        }
+        match self.last {
+            Last::CaptureOpen | Last::Prefix => return false,
+            Last::CaptureClose => return true,
+            _ => {}
+        }
+        match tag {
+            Comma | Semicolon | RParen | RBracket | DotAsterisk | DotQuestionMark | MinusArrow => {
+                return false
+            }
+            RBrace => return closed.is_some_and(|closed| self.pads_braces(closed)),
+            _ => {}
+        }
+        match prev {
+            LParen | LBracket => return false,
+            LBrace => return self.pads_braces(group),
+            Comma | Semicolon => return true,
+            Dot => return false,
+            _ => {}
+        }
+
+        let binary = self.is_binary(i);
+        // The `!` of error unions and ranges don't get spaces, unlike other operators.
+        if (tag == ExclamationMark && binary) || prev == ExclamationMark {
+            return false;
+        }
+        if matches!(tag, Dot2 | Dot3) || matches!(prev, Dot2 | Dot3) {
+            return false;
+        }
+        if self.last == Last::Binary || binary {
+            return true;
+        }
+
+        let after_control = self.closed == Some(GroupKind::Control);
+        match (prev, tag) {
+            (_, Colon) => match group.kind {
+                GroupKind::Index => true,
+                GroupKind::TypePrefix | GroupKind::KeywordArgs => false,
+                _ => {
+                    after_control
+                        || matches!(prev, Keyword(KeywordType::Break | KeywordType::Continue))
+                }
+            },
+            (Colon, _) => match group.kind {
+                GroupKind::Index | GroupKind::TypePrefix | GroupKind::KeywordArgs => false,
+                _ => !matches!(
+                    self.tag(i.wrapping_sub(2)),
+                    Keyword(KeywordType::Break | KeywordType::Continue)
+                ),
+            },
+            (Keyword(_), LParen) => !hugs_paren(prev),
+            (_, LParen | LBracket | Dot) => self.last != Last::Operand,
+            (_, LBrace) => !matches!(
+                self.brace_kind(i),
+                GroupKind::InitList | GroupKind::ErrorSet
+            ),
+            _ => true,
+        }
+    }
+
+    /// Whether the insides of single line braces get a space
+    /// on each side, as in `.{ .x = 1 }`.
+    fn pads_braces(&self, group: &Group) -> bool {
+        if group.close == group.open + 1 {
+            return false;
+        }
+        // Error sets with a single name are kept tight, like `error{Bad}`.
+        if group.kind == GroupKind::ErrorSet {
+            return group.close != group.open + 2;
+        }
+        if group.kind != GroupKind::InitList {
+            return true;
+        }
+        // Array initializers with a single element are kept tight, like `.{x}`.
+        let is_struct = self.tags[group.open + 1] == TokenTag::Dot
+            && self.tag(group.open + 3) == TokenTag::Equal;
+        is_struct
+            || self
+                .top_level(group.open)
+                .any(|i| self.tags[i] == TokenTag::Comma)
+    }
+
+    /// The indices of the tokens directly inside the brackets opened at
+    /// `open`, leaving out the contents of nested brackets.
+    fn top_level(&self, open: usize) -> impl Iterator<Item = usize> + '_ {
+        let close = self.closers[open];
+        let mut i = open + 1;
+        std::iter::from_fn(move || {
+            let current = i;
+            if current >= close {
+                return None;
+            }
+            i = match self.tags[current] {
+                TokenTag::LParen | TokenTag::LBracket | TokenTag::LBrace => self.closers[current],
+                _ => current + 1,
+            };
+            Some(current)
+        })
+    }
+
+    /// Whether token `i` is a binary operator, which depends on what
+    /// came before it for operators like `*` that are also prefix operators.
+    fn is_binary(&self, i: usize) -> bool {
+        let tag = self.tags[i];
+        if tag == TokenTag::Pipe && (self.in_capture || self.opens_capture(i)) {
+            return false;
+        }
+        match tag {
+            _ if is_ambiguous_operator(tag) => self.last == Last::Operand,
+            _ => is_binary_operator(self.tokens[i].token()),
+        }
+    }
+
+    /// Whether the `|` at `i` starts a capture like the one in `if (x) |y|`.
+    fn opens_capture(&self, i: usize) -> bool {
+        use KeywordType::*;
+        i > 0
+            && (self.closed == Some(GroupKind::Control)
+                || matches!(
+                    self.tags[i - 1],
+                    TokenTag::Keyword(Else | Catch | Errdefer) | TokenTag::EqualArrow
+                ))
+    }
+
+    fn is_asm(&self, i: usize) -> bool {
+        let asm = TokenTag::Keyword(KeywordType::Asm);
+        match self.tag(i.wrapping_sub(1)) {
+            TokenTag::Keyword(KeywordType::Volatile) => self.tag(i.wrapping_sub(2)) == asm,
+            prev => prev == asm,
+        }
+    }
+
+    /// Writes the parentheses of an assembly expression opened at `open`,
+    /// and returns the index of the closing one. With only a template and
+    /// clobbers it goes on one line, as in `asm volatile ("nop" ::: "memory")`,
+    /// otherwise every output and input gets a line of its own.
+    fn asm(&mut self, open: usize) -> usize {
+        let close = self.closers[open];
+        let has_comments = (open..=close).any(|i| {
+            let token = &self.tokens[i];
+            let comment = |trivia: &Trivia| trivia.kind() == TriviaKind::Comment;
+            (i > open && token.leading().iter().any(comment))
+                || token.trailing().iter().any(comment)
+        });
+        // The template, outputs, inputs and clobbers, split at the `:`s.
+        let mut sections = vec![Vec::new()];
+        for i in self.top_level(open) {
+            match self.tags[i] {
+                TokenTag::Colon => sections.push(Vec::new()),
+                _ => sections
+                    .last_mut()
+                    .expect("starts with the template")
+                    .push(i),
+            }
+        }
+        if has_comments || sections.len() > 4 || sections[0].len() != 1 {
+            // Nothing sensible can be done, so it's kept as written.
+            let start = self.tokens[open].token().span().start();
+            let end = self.tokens[close].token().span().end();
+            let text = &self.src[start..end];
+            self.write(text);
+            return close;
+        }
+
+        let template = self.tokens[sections[0][0]].text();
+        sections.resize(4, Vec::new());
+        let items = |section: &[usize]| -> Vec<String> {
+            section
+                .split(|&i| self.tags[i] == TokenTag::Comma)
+                .filter(|item| !item.is_empty())
+                .map(|item| self.asm_operand(item))
+                .collect()
+        };
+        let outputs = items(&sections[1]);
+        let inputs = items(&sections[2]);
+        let clobbers = items(&sections[3]).join(", ");
+        self.write("(");
+        self.write(template);
+        if outputs.is_empty() && inputs.is_empty() {
+            if !clobbers.is_empty() {
+                self.write(" ::: ");
+                self.write(&clobbers);
+            }
+            self.write(")");
+            return close;
+        }
+
+        let indent = self.line_indent;
+        let last_section = match (clobbers.is_empty(), inputs.is_empty()) {
+            (false, _) => 3,
+            (true, false) => 2,
+            (true, true) => 1,
+        };
+        for (section, operands) in [(1, &outputs), (2, &inputs)] {
+            if section > last_section {
+                break;
+            }
+            self.newline(indent + 1, false);
+            self.write(":");
+            for (n, operand) in operands.iter().enumerate() {
+                if n > 0 {
+                    self.newline(indent + 1, false);
+                    self.write(" ");
+                }
+                self.write(" ");
+                self.write(operand);
+                if section < last_section || n + 1 < operands.len() {
+                    self.write(",");
+                }
+            }
+        }
+        if !clobbers.is_empty() {
+            self.newline(indent + 1, false);
+            self.write(": ");
+            self.write(&clobbers);
+        }
+        self.newline(indent, false);
+        self.write(")");
+        close
+    }
+
+    /// Writes an operand of an assembly expression, such as `[ret] "={rax}" (-> usize)`,
+    /// or a clobber. What's in the brackets is kept as written, give or take
+    /// whitespace.
+    fn asm_operand(&self, tokens: &[usize]) -> String {
+        let mut out = String::new();
+        for &token in tokens {
+            let (open, close) = match self.tags[token] {
+                TokenTag::LParen => ("(", ")"),
+                TokenTag::LBracket => ("[", "]"),
+                TokenTag::RParen | TokenTag::RBracket => continue,
+                _ => {
+                    if !out.is_empty() {
+                        out.push(' ');
+                    }
+                    out.push_str(self.tokens[token].text());
+                    continue;
+                }
+            };
+            let start = self.tokens[token].token().span().end();
+            let end = self.tokens[self.closers[token]].token().span().start();
+            let inner = self.src[start..end].split_whitespace().collect::<Vec<_>>();
+            if !out.is_empty() {
+                out.push(' ');
+            }
+            out.push_str(open);
+            out.push_str(&inner.join(" "));
+            out.push_str(close);
+        }
+        out
+    }
+
+    /// Works out what the brackets opened at `i` are for.
+    fn kind_of(&self, i: usize) -> GroupKind {
+        use KeywordType::*;
+        let prev = self.tag(i.wrapping_sub(1));
+        let operand = self.last == Last::Operand;
+        match self.tags[i] {
+            TokenTag::LBrace => self.brace_kind(i),
+            TokenTag::LBracket if operand => GroupKind::Index,
+            TokenTag::LBracket => GroupKind::TypePrefix,
+            _ => match prev {
+                TokenTag::Keyword(If | While | For | Switch) => GroupKind::Control,
+                TokenTag::Colon if self.tag(i.wrapping_sub(2)) == TokenTag::RParen => {
+                    GroupKind::Control
+                }
+                TokenTag::Keyword(Fn) => GroupKind::FnParams,
+                TokenTag::Identifier if self.tag(i.wrapping_sub(2)) == TokenTag::Keyword(Fn) => {
+                    GroupKind::FnParams
+                }
+                _ if hugs_paren(prev) => GroupKind::KeywordArgs,
+                _ if operand => GroupKind::Call,
+                _ => GroupKind::Parens,
+            },
+        }
+    }
+
+    fn brace_kind(&self, i: usize) -> GroupKind {
+        let prev = self.tag(i.wrapping_sub(1));
+        match self.group().pending {
+            _ if prev == TokenTag::Keyword(KeywordType::Error) => GroupKind::ErrorSet,
+            Some(Pending::Container) => GroupKind::Container,
+            Some(Pending::Switch) => GroupKind::Switch,
+            Some(Pending::FnBody | Pending::Block) => GroupKind::Block,
+            None if prev == TokenTag::Dot || self.last == Last::Operand => GroupKind::InitList,
+            None => GroupKind::Block,
+        }
+    }
+
+    fn open(&mut self, i: usize) {
+        let kind = self.kind_of(i);
+        if self.tags[i] == TokenTag::LBrace && kind != GroupKind::ErrorSet {
+            self.group_mut().pending = None;
+        }
+        let close = self.closers[i];
+        let multiline = self.is_multiline(kind, i, close);
+        let indent = self.line_indent + multiline as usize;
+        self.groups.push(Group {
+            kind,
+            multiline,
+            open: i,
+            close,
+            indent,
+            continuation: false,
+            in_prong: false,
+            pending: None,
+        });
+    }
+
+    /// Decides if a group is laid out one element per line. Like `zig fmt`,
+    /// this is up to whether the last element is followed by a comma, except
+    /// for blocks and switches. Comments inside a group also force it onto
+    /// multiple lines.
+    fn is_multiline(&self, kind: GroupKind, open: usize, close: usize) -> bool {
+        use KeywordType::*;
+        let has_comment = |trivia: &[Trivia]| {
+            trivia
+                .iter()
+                .any(|trivia| trivia.kind() == TriviaKind::Comment)
+        };
+        if has_comment(self.tokens[open].trailing()) || has_comment(self.tokens[close].leading()) {
+            return true;
+        }
+        if close == open + 1 {
+            return false;
+        }
+        if matches!(kind, GroupKind::Block | GroupKind::Switch)
+            || self.tags[close - 1] == TokenTag::Comma
+        {
+            return true;
+        }
+        self.top_level(open).any(|i| {
+            let token = &self.tokens[i];
+            let tag = self.tags[i];
+            // The trailing comments of a nested opening bracket are inside it.
+            let nested = matches!(
+                tag,
+                TokenTag::LParen | TokenTag::LBracket | TokenTag::LBrace
+            );
+            let is_decl = matches!(
+                tag,
+                TokenTag::Semicolon
+                    | TokenTag::DocComment
+                    | TokenTag::Keyword(Fn | Pub | Const | Var | Test | Comptime | UsingNamespace)
+            );
+            has_comment(token.leading())
+                || (!nested && has_comment(token.trailing()))
+                || (kind == GroupKind::Container && is_decl)
+        })
+    }
+
+    fn write_token(&mut self, i: usize) {
+        let text = match self.tags[i] {
+            TokenTag::DocComment | TokenTag::ContainerDocComment => {
+                self.tokens[i].text().trim_end()
+            }
+            _ => self.tokens[i].text(),
+        };
+        self.write(text);
+    }
+
+    fn write(&mut self, text: &str) {
+        if self.at_line_start {
+            for _ in 0..self.line_indent {
+                self.out.push_str(INDENT);
+            }
+            self.at_line_start = false;
+        }
+        self.out.push_str(text);
+    }
+
+    fn space(&mut self) {
+        if !self.at_line_start && !self.out.ends_with(' ') {
+            self.out.push(' ');
+        }
+    }
+
+    /// Starts a new line with the given indentation, with an empty line
+    /// before it if `blank` is set. Nothing is written at the very start.
+    fn newline(&mut self, indent: usize, blank: bool) {
+        self.line_indent = indent;
+        self.force_newline = false;
+        self.string_end = None;
+        if self.out.is_empty() {
+            return;
+        }
+        if !self.at_line_start {
+            let len = self.out.trim_end_matches(' ').len();
+            self.out.truncate(len);
+            self.out.push('\n');
+            self.at_line_start = true;
+        }
+        if blank && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+}
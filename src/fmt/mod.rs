@@ -0,0 +1,4 @@
+mod formatter;
+mod spacing;
+
+pub use formatter::*;
@@ -0,0 +1,96 @@
+use crate::{
+    parser::binary_precedence,
+    tokenizer::{KeywordType, Token, TokenTag},
+};
+
+/// What the last token written was, as far as the space after it goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Last {
+    /// Something that ends an operand, such as an identifier or a `)`.
+    /// An ambiguous operator like `*` or `-` after it is a binary operator.
+    Operand,
+    /// A binary operator, always followed by a space.
+    Binary,
+    /// A prefix operator, never followed by a space.
+    Prefix,
+    /// The opening `|` of a capture.
+    CaptureOpen,
+    /// The closing `|` of a capture.
+    CaptureClose,
+    Other,
+}
+
+/// Operators that are binary after an operand and prefix operators otherwise.
+pub(super) fn is_ambiguous_operator(tag: TokenTag) -> bool {
+    use TokenTag::*;
+    matches!(
+        tag,
+        Minus | MinusPercent | Asterisk | Asterisk2 | Ampersand | ExclamationMark
+    )
+}
+
+/// Operators that are only ever prefix operators.
+pub(super) fn is_prefix_operator(tag: TokenTag) -> bool {
+    matches!(tag, TokenTag::Tilde | TokenTag::QuestionMark)
+}
+
+/// Binary operators and assignments, going by the precedence table of the
+/// parser, which get a space on both sides. This doesn't handle the
+/// ambiguous operators, which depend on what comes before them.
+pub(super) fn is_binary_operator(token: &Token) -> bool {
+    binary_precedence(token.token_type()).is_some()
+        || token.is_assign_op()
+        || token.token_type().tag() == TokenTag::EqualArrow
+}
+
+/// Tokens that end an operand by themselves.
+pub(super) fn ends_operand(tag: TokenTag) -> bool {
+    use KeywordType::*;
+    use TokenTag::*;
+    matches!(
+        tag,
+        Identifier
+            | PrimitiveType
+            | Integer
+            | Float
+            | Char
+            | StringLiteral
+            | MultilineStringLiteral
+            | Builtin
+            | DotAsterisk
+            | DotQuestionMark
+            | Keyword(Unreachable | AnyType | AnyFrame | Error)
+    )
+}
+
+/// Keywords that are written right before their `(`, like `align(4)`.
+pub(super) fn hugs_paren(tag: TokenTag) -> bool {
+    use KeywordType::*;
+    matches!(
+        tag,
+        TokenTag::Keyword(
+            Align | CallConv | AddrSpace | LinkSection | Struct | Enum | Union | Opaque
+        )
+    )
+}
+
+/// Tokens that can come right after the `}` of a block without starting a
+/// new statement, as in `} else {` or `};`.
+pub(super) fn continues_after_block(token: &Token) -> bool {
+    use KeywordType::*;
+    use TokenTag::*;
+    let tag = token.token_type().tag();
+    matches!(
+        tag,
+        Keyword(Else | Catch | OrElse | And | Or)
+            | Semicolon
+            | Comma
+            | RParen
+            | RBracket
+            | RBrace
+            | Dot
+            | DotAsterisk
+            | DotQuestionMark
+            | Eof
+    ) || is_binary_operator(token)
+}
@@ -0,0 +1,384 @@
+use std::borrow::Cow;
+
+use crate::{
+    ast::{BinaryOp, ContainerKind, Expr, Ident},
+    tokenizer::Span,
+};
+
+use super::{
+    error::EvalErrorKind,
+    format::format,
+    int::Int,
+    interpreter::{Exec, Interpreter},
+    ops,
+    value::{IntType, Namespace, NativeFn, Type, Value},
+};
+
+impl<'src> Interpreter<'src> {
+    /// Calls a builtin function. `ty` is the result type, which the
+    /// casting builtins need.
+    pub(super) fn builtin(
+        &mut self,
+        name: &str,
+        args: &[Expr<'src>],
+        span: Span,
+        ty: Option<&Type<'src>>,
+    ) -> Exec<'src, Value<'src>> {
+        let expected = match name {
+            "This" | "inComptime" => 0,
+            "import" | "intCast" | "truncate" | "floatFromInt" | "intFromFloat" | "floatCast"
+            | "enumFromInt" | "intFromBool" | "intFromEnum" | "tagName" | "errorName" | "fabs"
+            | "sqrt" | "floor" | "ceil" | "round" | "trunc" | "panic" | "TypeOf" | "sizeOf"
+            | "alignOf" | "bitSizeOf" | "typeName" | "typeInfo" | "compileError" => 1,
+            "as" | "divTrunc" | "divFloor" | "divExact" | "mod" | "rem" | "field" | "hasDecl"
+            | "hasField" => 2,
+            "min" | "max" if args.len() >= 2 => args.len(),
+            "min" | "max" => 2,
+            _ => return self.fail(span, EvalErrorKind::Unsupported(format!("@{name}"))),
+        };
+        if args.len() != expected {
+            let found = args.len();
+            return self.fail(span, EvalErrorKind::ArgCount { expected, found });
+        }
+
+        match name {
+            "import" => match self.eval(&args[0])?.bytes() {
+                Some(path) if path == b"std" => Ok(Type::Namespace(Namespace::Std).into()),
+                _ => {
+                    let kind = EvalErrorKind::Unsupported("importing files".to_string());
+                    self.fail(span, kind)
+                }
+            },
+            "This" => match self.scope.container() {
+                Some(container) => Ok(Type::Container(container).into()),
+                None => {
+                    let kind =
+                        EvalErrorKind::Unsupported("@This() outside of a container".to_string());
+                    self.fail(span, kind)
+                }
+            },
+            "as" => {
+                let ty = self.eval_type(&args[0])?;
+                self.eval_as(&args[1], &ty)
+            }
+            "TypeOf" => self.type_of_expr(&args[0]),
+            "sizeOf" | "alignOf" | "bitSizeOf" | "typeName" | "typeInfo" => {
+                let ty = self.eval_type(&args[0])?;
+                let size = match name {
+                    "sizeOf" => self.layout(&ty, span)?.0,
+                    "alignOf" => self.layout(&ty, span)?.1,
+                    "bitSizeOf" => self.bit_size(&ty, span)?,
+                    "typeName" => return Ok(self.string(ty.to_string().as_bytes())),
+                    _ => return self.type_info(&ty, span),
+                };
+                Ok(Value::Int(Int::from(size), None))
+            }
+            "compileError" => {
+                let message = self.string_arg(&args[0])?;
+                self.fail(span, EvalErrorKind::CompileError(message))
+            }
+            "inComptime" => Ok(Value::Bool(self.comptime > 0)),
+            "intCast" | "truncate" | "floatFromInt" | "intFromFloat" | "floatCast"
+            | "enumFromInt" => {
+                let Some(ty) = ty else {
+                    return self.fail(span, EvalErrorKind::NoResultType(name.to_string()));
+                };
+                let ty = ty.payload().clone();
+                let value = self.eval(&args[0])?;
+                self.cast(name, value, &ty, span)
+            }
+            "intFromBool" => match self.eval(&args[0])? {
+                Value::Bool(b) => Ok(Value::Int(Int::from(b), Some(IntType::new(false, 1)))),
+                value => self.mismatch("bool", &value, args[0].span),
+            },
+            "intFromEnum" => match self.eval(&args[0])? {
+                Value::Enum(container, index) => Ok(container.fields()[index]
+                    .default
+                    .clone()
+                    .unwrap_or(Value::Int(Int::from(index), None))),
+                Value::Union(_, index, _) => Ok(Value::Int(Int::from(index), None)),
+                value => self.mismatch("enum", &value, args[0].span),
+            },
+            "tagName" => match self.eval(&args[0])? {
+                Value::Enum(container, index) | Value::Union(container, index, _) => {
+                    Ok(self.string(container.fields()[index].name.as_bytes()))
+                }
+                Value::EnumLiteral(name) => Ok(self.string(name.as_bytes())),
+                value => self.mismatch("enum", &value, args[0].span),
+            },
+            "errorName" => match self.eval(&args[0])? {
+                Value::Error(name) => Ok(self.string(name.as_bytes())),
+                value => self.mismatch("error", &value, args[0].span),
+            },
+            "divTrunc" | "divFloor" | "divExact" | "mod" | "rem" => {
+                let lhs = self.eval(&args[0])?;
+                let rhs = self.eval(&args[1])?;
+                self.at(span, divide(name, &lhs, &rhs))
+            }
+            "min" | "max" => {
+                let mut result = self.eval(&args[0])?;
+                for arg in &args[1..] {
+                    let value = self.eval(arg)?;
+                    let op = match name {
+                        "min" => BinaryOp::Less,
+                        _ => BinaryOp::Greater,
+                    };
+                    let better = self.arith(op, &value, &result, span)?;
+                    let (keep, other) = match better {
+                        Value::Bool(true) => (value, result),
+                        _ => (result, value),
+                    };
+                    // Like peer type resolution, a comptime_int takes the other's type.
+                    result = match (keep, other) {
+                        (Value::Int(x, None), Value::Int(_, ty)) => Value::Int(x, ty),
+                        (Value::Float(x, None), Value::Float(_, bits)) => Value::Float(x, bits),
+                        (keep, _) => keep,
+                    };
+                }
+                Ok(result)
+            }
+            "fabs" => match self.eval(&args[0])? {
+                Value::Float(x, bits) => Ok(Value::Float(x.abs(), bits)),
+                Value::Int(x, None) => Ok(Value::Float(x.magnitude() as f64, None)),
+                value => self.mismatch("float", &value, args[0].span),
+            },
+            "sqrt" | "floor" | "ceil" | "round" | "trunc" => {
+                let (x, bits) = match self.eval(&args[0])? {
+                    Value::Float(x, bits) => (x, bits),
+                    Value::Int(x, None) => (x.to_f64(), None),
+                    value => return self.mismatch("float", &value, args[0].span),
+                };
+                let result = match name {
+                    "sqrt" => x.sqrt(),
+                    "floor" => x.floor(),
+                    "ceil" => x.ceil(),
+                    "round" => x.round(),
+                    _ => x.trunc(),
+                };
+                Ok(Value::Float(ops::round_float(result, bits), bits))
+            }
+            "panic" => {
+                let value = self.eval(&args[0])?;
+                match value.bytes() {
+                    Some(message) => {
+                        let message = String::from_utf8_lossy(&message).into_owned();
+                        self.fail(span, EvalErrorKind::Panic(message))
+                    }
+                    None => self.mismatch("[]const u8", &value, args[0].span),
+                }
+            }
+            "field" => {
+                let value = self.eval(&args[0])?;
+                let name = self.string_arg(&args[1])?;
+                let name = Ident {
+                    span: args[1].span,
+                    name: Cow::Owned(name),
+                };
+                self.field(value, &name)
+            }
+            "hasDecl" | "hasField" => {
+                let ty = self.eval_type(&args[0])?;
+                let member = self.string_arg(&args[1])?;
+                let Type::Container(container) = ty else {
+                    return self.mismatch("container type", &ty.into(), args[0].span);
+                };
+                let found = match name {
+                    "hasDecl" => container.scope.contains(&member),
+                    _ => {
+                        self.resolve_fields(&container)?;
+                        container.field_index(&member).is_some()
+                    }
+                };
+                Ok(Value::Bool(found))
+            }
+            _ => unreachable!("unknown builtins are rejected above"),
+        }
+    }
+
+    fn cast(
+        &mut self,
+        name: &str,
+        value: Value<'src>,
+        ty: &Type<'src>,
+        span: Span,
+    ) -> Exec<'src, Value<'src>> {
+        let result = match name {
+            "intCast" | "truncate" => ops::int_type(ty).and_then(|int| match (int, name) {
+                (Some(int), "intCast") => ops::int_cast(&value, int),
+                (Some(int), _) => ops::truncate(&value, int),
+                (None, _) => match value {
+                    Value::Int(x, _) => Ok(Value::Int(x, None)),
+                    value => Err(ops::expected_int(&value)),
+                },
+            }),
+            "floatFromInt" => {
+                ops::float_type(ty).and_then(|bits| ops::float_from_int(&value, bits))
+            }
+            "intFromFloat" => ops::int_type(ty).and_then(|int| ops::int_from_float(&value, int)),
+            "floatCast" => ops::float_type(ty).and_then(|bits| match value {
+                Value::Float(x, _) => Ok(Value::Float(ops::round_float(x, bits), bits)),
+                value => Err(EvalErrorKind::TypeMismatch {
+                    expected: "float".to_string(),
+                    found: value.type_of().to_string(),
+                }),
+            }),
+            _ => return self.enum_from_int(value, ty, span),
+        };
+        self.at(span, result)
+    }
+
+    fn enum_from_int(
+        &mut self,
+        value: Value<'src>,
+        ty: &Type<'src>,
+        span: Span,
+    ) -> Exec<'src, Value<'src>> {
+        let container = match ty {
+            Type::Container(container) if container.kind == ContainerKind::Enum => container,
+            _ => {
+                let kind = EvalErrorKind::TypeMismatch {
+                    expected: "enum".to_string(),
+                    found: ty.to_string(),
+                };
+                return self.fail(span, kind);
+            }
+        };
+        let Value::Int(tag, _) = value else {
+            return self.fail(span, ops::expected_int(&value));
+        };
+        self.resolve_fields(container)?;
+        let index = container.fields().iter().position(
+            |field| matches!(field.default, Some(Value::Int(field_tag, _)) if field_tag == tag),
+        );
+        match index {
+            Some(index) => Ok(Value::Enum(container.clone(), index)),
+            None => self.fail(span, EvalErrorKind::InvalidEnumValue),
+        }
+    }
+
+    fn string_arg(&mut self, arg: &Expr<'src>) -> Exec<'src, String> {
+        let value = self.eval(arg)?;
+        match value.bytes() {
+            Some(bytes) => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+            None => self.mismatch("[]const u8", &value, arg.span),
+        }
+    }
+
+    pub(super) fn mismatch<T>(
+        &self,
+        expected: &str,
+        value: &Value<'src>,
+        span: Span,
+    ) -> Exec<'src, T> {
+        let kind = EvalErrorKind::TypeMismatch {
+            expected: expected.to_string(),
+            found: value.type_of().to_string(),
+        };
+        self.fail(span, kind)
+    }
+
+    /// Calls one of the functions of the standard library.
+    pub(super) fn native(
+        &mut self,
+        native: NativeFn,
+        args: &[Expr<'src>],
+        span: Span,
+    ) -> Exec<'src, Value<'src>> {
+        let expected = match native {
+            NativeFn::Print => 2,
+            NativeFn::Assert => 1,
+        };
+        if args.len() != expected {
+            let found = args.len();
+            return self.fail(span, EvalErrorKind::ArgCount { expected, found });
+        }
+        match native {
+            NativeFn::Print => {
+                let fmt = self.string_arg(&args[0])?;
+                let values = match self.eval(&args[1])? {
+                    Value::Tuple(values) => values,
+                    Value::Struct(s) if s.ty.is_none() && s.fields.is_empty() => Vec::new(),
+                    value => return self.mismatch("tuple", &value, args[1].span),
+                };
+                let text = match format(&fmt, &values) {
+                    Ok(text) => text,
+                    Err(reason) => {
+                        return self.fail(args[0].span, EvalErrorKind::InvalidFormat(reason))
+                    }
+                };
+                match &mut self.output {
+                    Some(output) => output.push_str(&text),
+                    None => eprint!("{text}"),
+                }
+                Ok(Value::Void)
+            }
+            NativeFn::Assert => match self.eval_bool(&args[0])? {
+                true => Ok(Value::Void),
+                false => self.fail(span, EvalErrorKind::Unreachable),
+            },
+        }
+    }
+}
+
+/// `@divTrunc`, `@divFloor`, `@divExact`, `@mod` and `@rem`.
+fn divide<'src>(
+    name: &str,
+    lhs: &Value<'src>,
+    rhs: &Value<'src>,
+) -> Result<Value<'src>, EvalErrorKind> {
+    match (lhs, rhs) {
+        (Value::Int(x, a), Value::Int(y, b)) => {
+            let ty = match (a, b) {
+                (Some(a), Some(b)) if a != b => {
+                    return Err(EvalErrorKind::IncompatibleTypes {
+                        lhs: a.to_string(),
+                        rhs: b.to_string(),
+                    })
+                }
+                _ => a.or(*b),
+            };
+            let (x, y) = (*x, *y);
+            if y.is_zero() {
+                return Err(EvalErrorKind::DivisionByZero);
+            }
+            if y.is_negative() && matches!(name, "mod" | "rem") {
+                let message = "remainder division by zero or negative value".to_string();
+                return Err(EvalErrorKind::Panic(message));
+            }
+            if name == "divExact" && x.checked_rem(y).is_some_and(|rem| !rem.is_zero()) {
+                let message = "exact division produced remainder".to_string();
+                return Err(EvalErrorKind::Panic(message));
+            }
+            let result = match name {
+                "divTrunc" | "divExact" => x.checked_div(y),
+                "divFloor" => x.checked_div_floor(y),
+                "mod" => x.checked_mod_floor(y),
+                _ => x.checked_rem(y),
+            };
+            match result.filter(|&result| ty.is_none_or(|ty| ty.contains(result))) {
+                Some(result) => Ok(Value::Int(result, ty)),
+                None => Err(EvalErrorKind::Overflow),
+            }
+        }
+        (Value::Float(..) | Value::Int(..), Value::Float(..) | Value::Int(..)) => {
+            let float = |value: &Value| match value {
+                Value::Float(x, bits) => (*x, *bits),
+                Value::Int(x, _) => (x.to_f64(), None),
+                _ => unreachable!(),
+            };
+            let ((x, a), (y, b)) = (float(lhs), float(rhs));
+            let result = match name {
+                "divTrunc" | "divExact" => (x / y).trunc(),
+                "divFloor" => (x / y).floor(),
+                "mod" => x - y * (x / y).floor(),
+                _ => x % y,
+            };
+            let bits = a.or(b);
+            Ok(Value::Float(ops::round_float(result, bits), bits))
+        }
+        _ => Err(EvalErrorKind::TypeMismatch {
+            expected: "number".to_string(),
+            found: lhs.type_of().to_string(),
+        }),
+    }
+}
@@ -0,0 +1,228 @@
+use std::rc::Rc;
+
+use crate::{
+    ast::{ContainerKind, PointerSize},
+    tokenizer::Span,
+};
+
+use super::{
+    error::EvalErrorKind,
+    interpreter::{Exec, Interpreter},
+    ops,
+    value::{undefined_of, Array, Container, Pointer, Slice, Struct, Type, Value},
+};
+
+impl<'src> Interpreter<'src> {
+    /// Converts `value` to `ty` the way zig does without a cast, such as
+    /// a `comptime_int` to a `u8` or a `*[4]u8` to a `[]const u8`.
+    pub(super) fn coerce(
+        &mut self,
+        value: Value<'src>,
+        ty: &Type<'src>,
+        span: Span,
+    ) -> Exec<'src, Value<'src>> {
+        if let Type::Container(container) = ty {
+            self.resolve_fields(container)?;
+        }
+        let mismatch = |this: &Self, value: &Value<'src>| {
+            let kind = EvalErrorKind::TypeMismatch {
+                expected: ty.to_string(),
+                found: value.type_of().to_string(),
+            };
+            this.fail(span, kind)
+        };
+        let value = match (value, ty) {
+            // Arrays and structs get undefined elements and fields, which
+            // can then be assigned one by one.
+            (Value::Undefined, ty) => undefined_of(ty),
+            (Value::Null, Type::Optional(_)) => Value::Null,
+            (value, Type::Optional(child)) => return self.coerce(value, child, span),
+            (Value::Error(name), Type::ErrorUnion { error, .. }) => {
+                return self.coerce(Value::Error(name), error, span)
+            }
+            (value, Type::ErrorUnion { payload, .. }) => return self.coerce(value, payload, span),
+            (Value::Error(name), Type::ErrorSet(names)) => match names {
+                Some(names) if !names.contains(&name) => {
+                    return mismatch(self, &Value::Error(name))
+                }
+                _ => Value::Error(name),
+            },
+
+            (Value::Int(x, None), Type::Int(int)) => {
+                Value::Int(self.at(span, ops::fit(x, *int))?, Some(*int))
+            }
+            // Integers only widen to types that can hold all of their values.
+            (Value::Int(x, Some(from)), Type::Int(int))
+                if int.min() <= from.min() && from.max() <= int.max() =>
+            {
+                Value::Int(x, Some(*int))
+            }
+            (Value::Int(x, None), Type::Float(bits)) => {
+                Value::Float(ops::round_float(x.to_f64(), Some(*bits)), Some(*bits))
+            }
+            (Value::Int(x, None), Type::ComptimeFloat) => Value::Float(x.to_f64(), None),
+            (Value::Float(x, None), Type::Float(bits)) => {
+                Value::Float(ops::round_float(x, Some(*bits)), Some(*bits))
+            }
+            (Value::Float(x, Some(from)), Type::Float(bits)) if from <= *bits => {
+                Value::Float(x, Some(*bits))
+            }
+
+            (Value::EnumLiteral(name), Type::Container(container)) => {
+                let index = container.field_index(&name);
+                match (container.kind, index) {
+                    (ContainerKind::Enum, Some(index)) => Value::Enum(container.clone(), index),
+                    (ContainerKind::Union, Some(index))
+                        if container.fields()[index].ty == Type::Void =>
+                    {
+                        Value::Union(container.clone(), index, Box::new(Value::Void))
+                    }
+                    (_, None) => {
+                        let kind = EvalErrorKind::NoMember {
+                            ty: container.name(),
+                            name,
+                        };
+                        return self.fail(span, kind);
+                    }
+                    _ => return mismatch(self, &Value::EnumLiteral(name)),
+                }
+            }
+            (Value::Struct(Struct { ty: None, fields }), Type::Container(container))
+                if container.kind != ContainerKind::Enum =>
+            {
+                return self.struct_from(container, fields, span)
+            }
+            (
+                Value::Tuple(elems),
+                Type::Array {
+                    len,
+                    sentinel,
+                    child,
+                },
+            ) => {
+                if elems.len() != *len {
+                    let found = elems.len();
+                    return self.fail(
+                        span,
+                        EvalErrorKind::ArrayLength {
+                            expected: *len,
+                            found,
+                        },
+                    );
+                }
+                let mut coerced = Vec::new();
+                for elem in elems {
+                    coerced.push(self.coerce(elem, child, span)?);
+                }
+                Value::Array(Box::new(Array {
+                    child: (**child).clone(),
+                    sentinel: *sentinel,
+                    elems: coerced,
+                }))
+            }
+
+            (
+                Value::Pointer(pointer),
+                Type::Pointer {
+                    size,
+                    is_const,
+                    child,
+                    ..
+                },
+            ) if *is_const || !pointer.place.is_const => {
+                let mut place = pointer.place.clone();
+                place.is_const |= *is_const;
+                let array = match &pointer.child {
+                    Type::Array { len, child, .. } => Some((*len, &**child)),
+                    _ => None,
+                };
+                match (size, array) {
+                    (PointerSize::One, _) if pointer.child == **child => {
+                        Value::Pointer(Box::new(Pointer {
+                            place,
+                            child: pointer.child.clone(),
+                        }))
+                    }
+                    (PointerSize::Slice, Some((len, elem))) if elem == &**child => {
+                        Value::Slice(Box::new(Slice {
+                            place,
+                            start: 0,
+                            len,
+                            child: elem.clone(),
+                        }))
+                    }
+                    _ => return mismatch(self, &Value::Pointer(pointer)),
+                }
+            }
+            (
+                Value::Slice(slice),
+                Type::Pointer {
+                    size: PointerSize::Slice,
+                    is_const,
+                    child,
+                    ..
+                },
+            ) if slice.child == **child && (*is_const || !slice.place.is_const) => {
+                let mut slice = slice;
+                slice.place.is_const |= *is_const;
+                Value::Slice(slice)
+            }
+
+            (value, ty) if value.type_of() == *ty => value,
+            (value, _) => return mismatch(self, &value),
+        };
+        Ok(value)
+    }
+
+    /// Turns the fields of an anonymous struct literal into a value of
+    /// a struct or union type.
+    fn struct_from(
+        &mut self,
+        container: &Rc<Container<'src>>,
+        fields: Vec<(String, Value<'src>)>,
+        span: Span,
+    ) -> Exec<'src, Value<'src>> {
+        let declared = container.fields();
+        for (name, _) in &fields {
+            if container.field_index(name).is_none() {
+                let kind = EvalErrorKind::NoMember {
+                    ty: container.name(),
+                    name: name.clone(),
+                };
+                return self.fail(span, kind);
+            }
+        }
+
+        if container.kind == ContainerKind::Union {
+            let Ok::<[_; 1], _>([(name, value)]) = fields.try_into() else {
+                return self.fail(span, EvalErrorKind::UnionInit);
+            };
+            let index = container.field_index(&name).expect("checked above");
+            let value = self.coerce(value, &declared[index].ty, span)?;
+            return Ok(Value::Union(container.clone(), index, Box::new(value)));
+        }
+
+        let mut fields = fields;
+        let mut values = Vec::new();
+        for declared in declared.iter() {
+            let value = match fields.iter().position(|(name, _)| *name == declared.name) {
+                Some(index) => {
+                    let (_, value) = fields.swap_remove(index);
+                    self.coerce(value, &declared.ty, span)?
+                }
+                None => match &declared.default {
+                    Some(default) => default.clone(),
+                    None => {
+                        let kind = EvalErrorKind::MissingField(declared.name.clone());
+                        return self.fail(span, kind);
+                    }
+                },
+            };
+            values.push((declared.name.clone(), value));
+        }
+        Ok(Value::Struct(Struct {
+            ty: Some(container.clone()),
+            fields: values,
+        }))
+    }
+}
@@ -0,0 +1,728 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    ast::{
+        ContainerArg, ContainerDecl, ContainerKind, ContainerLayout, DeclKind, Expr, Param,
+        ParamType, PointerSize,
+    },
+    tokenizer::Span,
+};
+
+use super::{
+    error::EvalErrorKind,
+    int::Int,
+    interpreter::{Exec, Interpreter},
+    ops,
+    scope::Scope,
+    value::{Array, Container, Field, Function, IntType, Struct, Type, Value, Variable},
+};
+
+/// A type returned by a generic function, such as `List(i32)`, along
+/// with the arguments it was called with.
+pub(super) struct Instance<'src> {
+    function: Rc<Function<'src>>,
+    args: Vec<Value<'src>>,
+    ty: Type<'src>,
+}
+
+/// The fields of `std.builtin.Type`, in order.
+const TYPE_INFO_TAGS: [&str; 24] = [
+    "Type",
+    "Void",
+    "Bool",
+    "NoReturn",
+    "Int",
+    "Float",
+    "Pointer",
+    "Array",
+    "Struct",
+    "ComptimeFloat",
+    "ComptimeInt",
+    "Undefined",
+    "Null",
+    "Optional",
+    "ErrorUnion",
+    "ErrorSet",
+    "Enum",
+    "Union",
+    "Fn",
+    "Opaque",
+    "Frame",
+    "AnyFrame",
+    "Vector",
+    "EnumLiteral",
+];
+
+impl<'src> Interpreter<'src> {
+    /// Runs `f` at comptime, where reading a runtime variable is an error.
+    pub(super) fn comptime<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.comptime += 1;
+        let result = f(self);
+        self.comptime -= 1;
+        result
+    }
+
+    pub(super) fn eval_comptime(&mut self, expr: &Expr<'src>) -> Exec<'src, Value<'src>> {
+        self.comptime(|this| this.eval(expr))
+    }
+
+    /// Checks that a variable can be used here, and keeps count of the
+    /// runtime ones.
+    pub(super) fn read(&mut self, var: &Variable<'src>, name: &str, span: Span) -> Exec<'src, ()> {
+        match (var.is_comptime, self.comptime) {
+            (true, _) => Ok(()),
+            (false, 0) => {
+                self.runtime_reads += 1;
+                Ok(())
+            }
+            (false, _) => self.fail(span, EvalErrorKind::NotComptime(name.to_string())),
+        }
+    }
+
+    /// `@TypeOf`. The operand is only there for its type, so it can use
+    /// runtime variables even at comptime, but unlike in zig it is still
+    /// evaluated, side effects and all.
+    pub(super) fn type_of_expr(&mut self, expr: &Expr<'src>) -> Exec<'src, Value<'src>> {
+        let comptime = std::mem::take(&mut self.comptime);
+        let reads = self.runtime_reads;
+        // Variables have the type they were declared with, such as
+        // `?u8` for one that is `null` at the moment.
+        let result = self.place_of(expr).and_then(|place| match place {
+            Some(place) => Ok(self.place_type(&place)),
+            None => Ok(self.eval(expr)?.type_of()),
+        });
+        self.comptime = comptime;
+        self.runtime_reads = reads;
+        Ok(result?.into())
+    }
+
+    /// The type a generic function returned before for the same arguments.
+    pub(super) fn instance(
+        &self,
+        function: &Rc<Function<'src>>,
+        args: &[Value<'src>],
+    ) -> Option<Type<'src>> {
+        self.instances
+            .iter()
+            .find(|instance| {
+                Rc::ptr_eq(&instance.function, function)
+                    && instance.args.len() == args.len()
+                    && instance.args.iter().zip(args).all(|(a, b)| same(a, b))
+            })
+            .map(|instance| instance.ty.clone())
+    }
+
+    /// Remembers the type a generic function returned, and names it
+    /// after the call the way zig does, as in `List(i32)`.
+    pub(super) fn add_instance(
+        &mut self,
+        function: &Rc<Function<'src>>,
+        args: Vec<Value<'src>>,
+        ty: Type<'src>,
+    ) {
+        if let Type::Container(container) = &ty {
+            let args: Vec<_> = args.iter().map(describe).collect();
+            container
+                .name
+                .borrow_mut()
+                .get_or_insert_with(|| format!("{}({})", function.name, args.join(", ")));
+        }
+        self.instances.push(Instance {
+            function: function.clone(),
+            args,
+            ty,
+        });
+    }
+
+    /// The size and alignment of a type in bytes, as on a 64-bit target.
+    /// Structs are laid out the way zig does it, with the fields ordered
+    /// by alignment so that there's no padding between them.
+    pub(super) fn layout(&mut self, ty: &Type<'src>, span: Span) -> Exec<'src, (u64, u64)> {
+        let layout = match ty {
+            Type::AnyOpaque => return self.fail(span, EvalErrorKind::NoSize(ty.to_string())),
+            _ if ty.is_comptime_only() => (0, 1),
+            Type::Void | Type::NoReturn => (0, 1),
+            Type::Bool => (1, 1),
+            Type::Int(int) => int_layout(int.bits),
+            Type::Float(80) => (16, 16),
+            Type::Float(bits) => (*bits as u64 / 8, *bits as u64 / 8),
+            Type::ErrorSet(_) => (2, 2),
+            Type::Pointer {
+                size: PointerSize::Slice,
+                ..
+            } => (16, 8),
+            Type::Pointer { .. } => (8, 8),
+            // Optional pointers use the null address for `null`, and
+            // everything else gets a flag after the payload.
+            Type::Optional(child) => match &**child {
+                Type::Pointer { size, .. } if *size != PointerSize::C => {
+                    self.layout(child, span)?
+                }
+                child => {
+                    let (size, align) = self.layout(child, span)?;
+                    (round_up(size + 1, align), align)
+                }
+            },
+            Type::ErrorUnion { payload, .. } => {
+                let (size, align) = self.layout(payload, span)?;
+                let align = align.max(2);
+                (round_up(round_up(size, 2) + 2, align), align)
+            }
+            Type::Array {
+                len,
+                sentinel,
+                child,
+            } => {
+                let (size, align) = self.layout(child, span)?;
+                let len = *len as u64 + sentinel.is_some() as u64;
+                (size * len, align)
+            }
+            Type::Tuple(types) => self.struct_layout(types, ContainerLayout::Auto, span)?,
+            Type::AnonStruct(fields) => {
+                let types: Vec<_> = fields.iter().map(|(_, ty)| ty.clone()).collect();
+                self.struct_layout(&types, ContainerLayout::Auto, span)?
+            }
+            Type::Container(container) => {
+                self.resolve_fields(container)?;
+                let types: Vec<_> = container.fields().iter().map(|f| f.ty.clone()).collect();
+                match container.kind {
+                    ContainerKind::Struct => {
+                        self.struct_layout(&types, container.decl.layout, span)?
+                    }
+                    ContainerKind::Enum => int_layout(enum_tag(container).bits),
+                    ContainerKind::Union => {
+                        let (mut size, mut align) = (0, 1);
+                        for ty in &types {
+                            let (field_size, field_align) = self.layout(ty, span)?;
+                            size = size.max(field_size);
+                            align = align.max(field_align);
+                        }
+                        match self.union_tag(container)? {
+                            Some(tag) => {
+                                let (tag_size, tag_align) = int_layout(enum_tag(&tag).bits);
+                                let align = align.max(tag_align);
+                                (round_up(round_up(size, tag_align) + tag_size, align), align)
+                            }
+                            None => (round_up(size, align), align),
+                        }
+                    }
+                    ContainerKind::Opaque => {
+                        return self.fail(span, EvalErrorKind::NoSize(ty.to_string()))
+                    }
+                }
+            }
+            _ => return self.fail(span, EvalErrorKind::NoSize(ty.to_string())),
+        };
+        Ok(layout)
+    }
+
+    fn struct_layout(
+        &mut self,
+        types: &[Type<'src>],
+        layout: ContainerLayout,
+        span: Span,
+    ) -> Exec<'src, (u64, u64)> {
+        if layout == ContainerLayout::Packed {
+            let mut bits = 0;
+            for ty in types {
+                bits += self.bit_size(ty, span)?;
+            }
+            return Ok(int_layout(bits as u16));
+        }
+        let (mut size, mut align) = (0, 1);
+        for ty in types {
+            let (field_size, field_align) = self.layout(ty, span)?;
+            // Extern structs keep the fields in order, padding between them.
+            if layout == ContainerLayout::Extern {
+                size = round_up(size, field_align);
+            }
+            size += field_size;
+            align = align.max(field_align);
+        }
+        Ok((round_up(size, align), align))
+    }
+
+    /// `@bitSizeOf`, which is the size in bits, except for the types that
+    /// don't need whole bytes.
+    pub(super) fn bit_size(&mut self, ty: &Type<'src>, span: Span) -> Exec<'src, u64> {
+        let bits = match ty {
+            Type::Int(int) => int.bits as u64,
+            Type::Bool => 1,
+            Type::Float(bits) => *bits as u64,
+            Type::Container(container) if container.kind == ContainerKind::Enum => {
+                self.resolve_fields(container)?;
+                enum_tag(container).bits as u64
+            }
+            Type::Container(container) if container.decl.layout == ContainerLayout::Packed => {
+                self.resolve_fields(container)?;
+                let mut bits = 0;
+                for field in container.fields().iter() {
+                    bits += self.bit_size(&field.ty, span)?;
+                }
+                bits
+            }
+            ty => self.layout(ty, span)?.0 * 8,
+        };
+        Ok(bits)
+    }
+
+    /// The enum that holds the tags of a tagged union. A `union(enum)`
+    /// gets one made up for it.
+    fn union_tag(
+        &mut self,
+        container: &Rc<Container<'src>>,
+    ) -> Exec<'src, Option<Rc<Container<'src>>>> {
+        let scope = container.scope.clone();
+        let tag_type = match &container.decl.arg {
+            None => return Ok(None),
+            Some(ContainerArg::Type(expr)) => {
+                return match self.in_scope(scope, |this| this.eval_type(expr))? {
+                    Type::Container(tag) if tag.kind == ContainerKind::Enum => {
+                        self.resolve_fields(&tag)?;
+                        Ok(Some(tag))
+                    }
+                    ty => self.mismatch("enum type", &ty.into(), expr.span),
+                };
+            }
+            Some(ContainerArg::TaggedUnion(Some(expr))) => {
+                match self.in_scope(scope, |this| this.eval_type(expr))? {
+                    Type::Int(int) => int,
+                    ty => return self.mismatch("integer type", &ty.into(), expr.span),
+                }
+            }
+            Some(ContainerArg::TaggedUnion(None)) => {
+                let max = container.fields().len().saturating_sub(1);
+                IntType::new(false, (usize::BITS - max.leading_zeros()) as u16)
+            }
+        };
+        let fields = container
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(index, field)| Field {
+                name: field.name.clone(),
+                ty: Type::Void,
+                default: Some(Value::Int(Int::from(index), Some(tag_type))),
+            })
+            .collect();
+        let name = format!("@typeInfo({}).Union.tag_type.?", container.name());
+        Ok(Some(self.synthetic(
+            ContainerKind::Enum,
+            name,
+            None,
+            fields,
+        )))
+    }
+
+    /// `@typeInfo`, which describes a type with a `std.builtin.Type`.
+    /// Only the more useful parts of it are filled in.
+    pub(super) fn type_info(&mut self, ty: &Type<'src>, span: Span) -> Exec<'src, Value<'src>> {
+        let literal = |name: &str| Value::EnumLiteral(name.to_string());
+        let u16_value = |bits: u16| Value::Int(Int::from(bits), Some(IntType::new(false, 16)));
+        let (tag, payload) = match ty {
+            Type::Type => ("Type", Value::Void),
+            Type::Void => ("Void", Value::Void),
+            Type::Bool => ("Bool", Value::Void),
+            Type::NoReturn => ("NoReturn", Value::Void),
+            Type::ComptimeInt => ("ComptimeInt", Value::Void),
+            Type::ComptimeFloat => ("ComptimeFloat", Value::Void),
+            Type::Undefined => ("Undefined", Value::Void),
+            Type::Null => ("Null", Value::Void),
+            Type::EnumLiteral => ("EnumLiteral", Value::Void),
+            Type::Int(int) => {
+                let signedness = match int.signed {
+                    true => "signed",
+                    false => "unsigned",
+                };
+                let payload = info(vec![
+                    ("signedness", literal(signedness)),
+                    ("bits", u16_value(int.bits)),
+                ]);
+                ("Int", payload)
+            }
+            Type::Float(bits) => ("Float", info(vec![("bits", u16_value(*bits))])),
+            Type::Pointer {
+                size,
+                is_const,
+                sentinel,
+                child,
+            } => {
+                let size = match size {
+                    PointerSize::One => "One",
+                    PointerSize::Many => "Many",
+                    PointerSize::Slice => "Slice",
+                    PointerSize::C => "C",
+                };
+                let payload = info(vec![
+                    ("size", literal(size)),
+                    ("is_const", Value::Bool(*is_const)),
+                    ("is_volatile", Value::Bool(false)),
+                    ("child", (**child).clone().into()),
+                    ("is_allowzero", Value::Bool(false)),
+                    ("sentinel", sentinel_value(*sentinel)),
+                ]);
+                ("Pointer", payload)
+            }
+            Type::Array {
+                len,
+                sentinel,
+                child,
+            } => {
+                let payload = info(vec![
+                    ("len", Value::Int(Int::from(*len), None)),
+                    ("child", (**child).clone().into()),
+                    ("sentinel", sentinel_value(*sentinel)),
+                ]);
+                ("Array", payload)
+            }
+            Type::Optional(child) => ("Optional", info(vec![("child", (**child).clone().into())])),
+            Type::ErrorUnion { error, payload } => {
+                let payload = info(vec![
+                    ("error_set", (**error).clone().into()),
+                    ("payload", (**payload).clone().into()),
+                ]);
+                ("ErrorUnion", payload)
+            }
+            Type::ErrorSet(None) => ("ErrorSet", Value::Null),
+            Type::ErrorSet(Some(names)) => {
+                let errors = names
+                    .iter()
+                    .map(|name| info(vec![("name", self.string(name.as_bytes()))]))
+                    .collect();
+                ("ErrorSet", list(errors))
+            }
+            Type::Tuple(types) => {
+                let fields = types
+                    .iter()
+                    .enumerate()
+                    .map(|(i, ty)| self.field_info(&i.to_string(), ty, None))
+                    .collect();
+                (
+                    "Struct",
+                    struct_info("Auto", fields, list(Vec::new()), true),
+                )
+            }
+            Type::AnonStruct(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(name, ty)| self.field_info(name, ty, None))
+                    .collect();
+                (
+                    "Struct",
+                    struct_info("Auto", fields, list(Vec::new()), false),
+                )
+            }
+            Type::Container(container) => {
+                self.resolve_fields(container)?;
+                let decls = self.decls_info(container);
+                let layout = match container.decl.layout {
+                    ContainerLayout::Auto => "Auto",
+                    ContainerLayout::Packed => "Packed",
+                    ContainerLayout::Extern => "Extern",
+                };
+                let fields = container.fields();
+                match container.kind {
+                    ContainerKind::Struct => {
+                        let fields = fields
+                            .iter()
+                            .map(|field| {
+                                self.field_info(&field.name, &field.ty, field.default.clone())
+                            })
+                            .collect();
+                        ("Struct", struct_info(layout, fields, decls, false))
+                    }
+                    ContainerKind::Enum => {
+                        let tag_type = Type::Int(enum_tag(container));
+                        let fields = fields
+                            .iter()
+                            .map(|field| {
+                                let value = match &field.default {
+                                    Some(Value::Int(tag, _)) => Value::Int(*tag, None),
+                                    _ => Value::Undefined,
+                                };
+                                info(vec![
+                                    ("name", self.string(field.name.as_bytes())),
+                                    ("value", value),
+                                ])
+                            })
+                            .collect();
+                        let payload = info(vec![
+                            ("tag_type", tag_type.into()),
+                            ("fields", list(fields)),
+                            ("decls", decls),
+                            ("is_exhaustive", Value::Bool(true)),
+                        ]);
+                        ("Enum", payload)
+                    }
+                    ContainerKind::Union => {
+                        let tag_type = match self.union_tag(container)? {
+                            Some(tag) => Type::Container(tag).into(),
+                            None => Value::Null,
+                        };
+                        let fields = fields
+                            .iter()
+                            .map(|field| {
+                                info(vec![
+                                    ("name", self.string(field.name.as_bytes())),
+                                    ("type", field.ty.clone().into()),
+                                ])
+                            })
+                            .collect();
+                        let payload = info(vec![
+                            ("layout", literal(layout)),
+                            ("tag_type", tag_type),
+                            ("fields", list(fields)),
+                            ("decls", decls),
+                        ]);
+                        ("Union", payload)
+                    }
+                    ContainerKind::Opaque => ("Opaque", info(vec![("decls", decls)])),
+                }
+            }
+            Type::Fn(function) => ("Fn", self.fn_info(function)?),
+            Type::AnyOpaque | Type::Namespace(_) | Type::NativeFn(_) => {
+                let kind = EvalErrorKind::Unsupported(format!("@typeInfo of '{ty}'"));
+                return self.fail(span, kind);
+            }
+        };
+        let container = self.type_info_container();
+        let index = container.field_index(tag).expect("every tag is a field");
+        Ok(Value::Union(container, index, Box::new(payload)))
+    }
+
+    fn field_info(&self, name: &str, ty: &Type<'src>, default: Option<Value<'src>>) -> Value<'src> {
+        info(vec![
+            ("name", self.string(name.as_bytes())),
+            ("type", ty.clone().into()),
+            ("default_value", default.unwrap_or(Value::Null)),
+            ("is_comptime", Value::Bool(false)),
+        ])
+    }
+
+    /// The public declarations of a container, by name.
+    fn decls_info(&self, container: &Container<'src>) -> Value<'src> {
+        let decls = container
+            .decl
+            .decls
+            .iter()
+            .filter(|decl| decl.is_pub)
+            .filter_map(|decl| match &decl.kind {
+                DeclKind::Var(var) => Some(&var.name),
+                DeclKind::Fn(function) => function.proto.name.as_ref(),
+                _ => None,
+            })
+            .map(|name| info(vec![("name", self.string(name.name.as_bytes()))]))
+            .collect();
+        list(decls)
+    }
+
+    /// The parameters of a generic function have no type to speak of,
+    /// and neither does its return type.
+    fn fn_info(&mut self, function: &Rc<Function<'src>>) -> Exec<'src, Value<'src>> {
+        let proto = &function.decl.proto;
+        let generic = |param: &Param| param.is_comptime || param.ty == ParamType::AnyType;
+        let is_generic = proto.params.iter().any(generic);
+        let mut params = Vec::new();
+        for param in &proto.params {
+            let ty = match &param.ty {
+                ParamType::Expr(ty) if !is_generic => {
+                    let ty = self.in_scope(function.scope.clone(), |this| this.eval_type(ty))?;
+                    ty.into()
+                }
+                _ => Value::Null,
+            };
+            params.push(info(vec![
+                ("is_generic", Value::Bool(generic(param))),
+                ("is_noalias", Value::Bool(param.is_noalias)),
+                ("type", ty),
+            ]));
+        }
+        let return_type = match is_generic {
+            true => Value::Null,
+            false => {
+                let scope = function.scope.clone();
+                let ty = self.in_scope(scope, |this| this.eval_type(&proto.return_type))?;
+                match proto.inferred_error {
+                    true => Type::ErrorUnion {
+                        error: Box::new(Type::ErrorSet(None)),
+                        payload: Box::new(ty),
+                    },
+                    false => ty,
+                }
+                .into()
+            }
+        };
+        let is_var_args = proto.params.iter().any(|p| p.ty == ParamType::VarArgs);
+        Ok(info(vec![
+            ("is_generic", Value::Bool(is_generic)),
+            ("is_var_args", Value::Bool(is_var_args)),
+            ("return_type", return_type),
+            ("params", list(params)),
+        ]))
+    }
+
+    fn type_info_container(&mut self) -> Rc<Container<'src>> {
+        if let Some(container) = &self.type_info {
+            return container.clone();
+        }
+        // The payloads are anonymous structs, so the field types are
+        // only there to tell them apart from the tags without one.
+        let fields = TYPE_INFO_TAGS
+            .iter()
+            .map(|tag| Field {
+                name: tag.to_string(),
+                ty: match has_payload(tag) {
+                    true => Type::AnonStruct(Vec::new()),
+                    false => Type::Void,
+                },
+                default: None,
+            })
+            .collect();
+        let arg = Some(ContainerArg::TaggedUnion(None));
+        let container = self.synthetic(
+            ContainerKind::Union,
+            "builtin.Type".to_string(),
+            arg,
+            fields,
+        );
+        self.type_info = Some(container.clone());
+        container
+    }
+
+    /// Makes a container that isn't declared anywhere in the source.
+    fn synthetic(
+        &self,
+        kind: ContainerKind,
+        name: String,
+        arg: Option<ContainerArg<'src>>,
+        fields: Vec<Field<'src>>,
+    ) -> Rc<Container<'src>> {
+        let decl = ContainerDecl {
+            kind,
+            layout: ContainerLayout::Auto,
+            arg,
+            doc: Vec::new(),
+            decls: Vec::new(),
+        };
+        Rc::new(Container {
+            kind,
+            name: RefCell::new(Some(name)),
+            decl: Rc::new(decl),
+            scope: Scope::child(&self.root),
+            fields: RefCell::new(Some(Rc::new(fields))),
+        })
+    }
+}
+
+/// Whether two comptime arguments are the same, for telling generic
+/// instances apart. Strings are compared by their bytes.
+fn same<'src>(a: &Value<'src>, b: &Value<'src>) -> bool {
+    if let (Some(a), Some(b)) = (a.bytes(), b.bytes()) {
+        return a == b;
+    }
+    a.type_of() == b.type_of() && matches!(ops::equal(a, b), Ok(true))
+}
+
+/// Writes out an argument to a generic function, for the name of the
+/// type it returns.
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Type(ty) => ty.to_string(),
+        Value::Int(x, _) => x.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::EnumLiteral(name) => format!(".{name}"),
+        Value::Enum(container, index) => {
+            format!("{}.{}", container.name(), container.fields()[*index].name)
+        }
+        value => match value.bytes() {
+            Some(bytes) => format!("{:?}", String::from_utf8_lossy(&bytes)),
+            None => "...".to_string(),
+        },
+    }
+}
+
+/// The integer type of an enum's tags, which is the smallest one that
+/// fits them if the enum doesn't say.
+fn enum_tag(container: &Container) -> IntType {
+    let fields = container.fields();
+    let tags = fields.iter().filter_map(|field| match &field.default {
+        Some(Value::Int(tag, ty)) => Some((*tag, *ty)),
+        _ => None,
+    });
+    let mut max = Int::ZERO;
+    for (tag, ty) in tags {
+        if let Some(ty) = ty {
+            return ty;
+        }
+        max = max.max(tag);
+    }
+    IntType::new(false, max.bits() as u16)
+}
+
+/// Whether a field of `std.builtin.Type` has a payload.
+fn has_payload(tag: &str) -> bool {
+    !matches!(
+        tag,
+        "Type"
+            | "Void"
+            | "Bool"
+            | "NoReturn"
+            | "ComptimeFloat"
+            | "ComptimeInt"
+            | "Undefined"
+            | "Null"
+            | "EnumLiteral"
+    )
+}
+
+fn int_layout(bits: u16) -> (u64, u64) {
+    if bits == 0 {
+        return (0, 1);
+    }
+    let bytes = (bits as u64).div_ceil(8);
+    let align = bytes.next_power_of_two().min(16);
+    (round_up(bytes, align), align)
+}
+
+fn round_up(size: u64, align: u64) -> u64 {
+    size.div_ceil(align) * align
+}
+
+/// An anonymous struct, for the payloads of `@typeInfo`.
+fn info<'src>(fields: Vec<(&str, Value<'src>)>) -> Value<'src> {
+    let fields = fields
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect();
+    Value::Struct(Struct { ty: None, fields })
+}
+
+fn list(elems: Vec<Value>) -> Value {
+    let child = elems.first().map_or(Type::Void, Value::type_of);
+    Value::Array(Box::new(Array {
+        child,
+        sentinel: None,
+        elems,
+    }))
+}
+
+fn struct_info<'src>(
+    layout: &str,
+    fields: Vec<Value<'src>>,
+    decls: Value<'src>,
+    is_tuple: bool,
+) -> Value<'src> {
+    info(vec![
+        ("layout", Value::EnumLiteral(layout.to_string())),
+        ("fields", list(fields)),
+        ("decls", decls),
+        ("is_tuple", Value::Bool(is_tuple)),
+    ])
+}
+
+fn sentinel_value<'src>(sentinel: Option<i64>) -> Value<'src> {
+    match sentinel {
+        Some(sentinel) => Value::Int(Int::from(sentinel), None),
+        None => Value::Null,
+    }
+}
@@ -0,0 +1,229 @@
+use std::fmt;
+
+use crate::tokenizer::Span;
+
+/// An error found while running a program. Most of these are the panics
+/// a zig program built in debug mode would hit, such as integer overflow,
+/// and the rest are what the zig compiler would have rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalError {
+    pub(crate) span: Span,
+    pub(crate) kind: EvalErrorKind,
+    /// The calls that were being made when the error happened,
+    /// innermost first.
+    pub(crate) trace: Vec<Frame>,
+}
+
+/// A function call in progress.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    /// The name of the function being called.
+    pub name: String,
+    /// Where it was called from.
+    pub span: Span,
+    /// Whether the call was made at compile time.
+    pub comptime: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalErrorKind {
+    Undeclared(String),
+    Redeclared(String),
+    /// A declaration whose value depends on itself.
+    DependencyLoop(String),
+    UndeclaredLabel(String),
+    TypeMismatch {
+        expected: String,
+        found: String,
+    },
+    IncompatibleTypes {
+        lhs: String,
+        rhs: String,
+    },
+    InvalidOperator {
+        op: &'static str,
+        ty: String,
+    },
+    /// An integer that doesn't fit in the type it is coerced to.
+    IntegerTooLarge {
+        value: String,
+        ty: String,
+    },
+    /// A `var` with a type like `comptime_int` that only exists at compile time.
+    ComptimeVar(String),
+    NoMember {
+        ty: String,
+        name: String,
+    },
+    MissingField(String),
+    NotCallable(String),
+    NotIndexable(String),
+    NotIterable(String),
+    ArgCount {
+        expected: usize,
+        found: usize,
+    },
+    AssignToConst,
+    NotAssignable,
+    BreakOutsideLoop,
+    ContinueOutsideLoop,
+    ReturnOutsideFn,
+    UnboundedFor,
+    /// A switch without an `else` prong that has no prong for the value.
+    UnhandledSwitchValue,
+    UnionInit,
+    ArrayLength {
+        expected: usize,
+        found: usize,
+    },
+    /// A cast builtin like `@intCast` used where there is no type to cast to.
+    NoResultType(String),
+    /// `/` or `%` on signed integers, which zig only allows for
+    /// values that aren't negative.
+    SignedDivision(String),
+    UndefinedValue,
+    InvalidFormat(String),
+    /// A runtime variable read where the value has to be known at compile time.
+    NotComptime(String),
+    /// `@sizeOf` and friends on a type like `anyopaque`.
+    NoSize(String),
+    /// `@compileError`, with its message.
+    CompileError(String),
+    /// A feature of zig the interpreter doesn't have.
+    Unsupported(String),
+    NoMain,
+
+    // The safety checks of a debug build.
+    Overflow,
+    DivisionByZero,
+    ShiftOverflow,
+    CastTruncated,
+    FloatOutOfBounds,
+    InvalidEnumValue,
+    IndexOutOfBounds {
+        index: usize,
+        len: usize,
+    },
+    SliceStartAfterEnd {
+        start: usize,
+        end: usize,
+    },
+    NullUnwrap,
+    ErrorUnwrap(String),
+    Unreachable,
+    Panic(String),
+    StackOverflow,
+    /// An error returned from `main`.
+    ErrorReturned(String),
+}
+
+impl EvalError {
+    pub fn new(span: Span, kind: EvalErrorKind) -> Self {
+        Self {
+            span,
+            kind,
+            trace: Vec::new(),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn kind(&self) -> &EvalErrorKind {
+        &self.kind
+    }
+
+    pub fn trace(&self) -> &[Frame] {
+        &self.trace
+    }
+}
+
+impl fmt::Display for EvalErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Undeclared(name) => write!(f, "use of undeclared identifier '{name}'"),
+            Self::Redeclared(name) => write!(f, "redeclaration of '{name}'"),
+            Self::DependencyLoop(name) => write!(f, "dependency loop detected in '{name}'"),
+            Self::UndeclaredLabel(name) => write!(f, "use of undeclared label '{name}'"),
+            Self::TypeMismatch { expected, found } => {
+                write!(f, "expected type '{expected}', found '{found}'")
+            }
+            Self::IncompatibleTypes { lhs, rhs } => {
+                write!(f, "incompatible types: '{lhs}' and '{rhs}'")
+            }
+            Self::InvalidOperator { op, ty } => {
+                write!(f, "operator {op} not allowed for type '{ty}'")
+            }
+            Self::IntegerTooLarge { value, ty } => {
+                write!(f, "type '{ty}' cannot represent integer value '{value}'")
+            }
+            Self::ComptimeVar(ty) => {
+                write!(f, "variable of type '{ty}' must be const or comptime")
+            }
+            Self::NoMember { ty, name } => write!(f, "'{ty}' has no member named '{name}'"),
+            Self::MissingField(name) => write!(f, "missing struct field: {name}"),
+            Self::NotCallable(ty) => write!(f, "type '{ty}' not a function"),
+            Self::NotIndexable(ty) => write!(f, "type '{ty}' does not support indexing"),
+            Self::NotIterable(ty) => write!(f, "type '{ty}' is not indexable and not a range"),
+            Self::ArgCount { expected, found } => {
+                let s = if *expected == 1 { "" } else { "s" };
+                write!(f, "expected {expected} argument{s}, found {found}")
+            }
+            Self::AssignToConst => write!(f, "cannot assign to constant"),
+            Self::NotAssignable => write!(f, "cannot assign to this expression"),
+            Self::BreakOutsideLoop => write!(f, "break expression outside loop"),
+            Self::ContinueOutsideLoop => write!(f, "continue expression outside loop"),
+            Self::ReturnOutsideFn => write!(f, "return expression outside function"),
+            Self::UnboundedFor => write!(f, "unbounded for loop"),
+            Self::UnhandledSwitchValue => write!(f, "switch must handle all possibilities"),
+            Self::UnionInit => write!(f, "union initializer must initialize one field"),
+            Self::ArrayLength { expected, found } => {
+                write!(f, "expected {expected} array elements; found {found}")
+            }
+            Self::NoResultType(builtin) => write!(f, "@{builtin} must have a known result type"),
+            Self::SignedDivision(ty) => write!(
+                f,
+                "division with '{ty}' and '{ty}': signed integers must use @divTrunc, @divFloor, or @divExact"
+            ),
+            Self::UndefinedValue => write!(f, "use of undefined value here causes undefined behavior"),
+            Self::InvalidFormat(reason) => write!(f, "invalid format string: {reason}"),
+            Self::NotComptime(name) => write!(
+                f,
+                "unable to evaluate comptime expression: '{name}' is only known at runtime"
+            ),
+            Self::NoSize(ty) => write!(f, "no size available for type '{ty}'"),
+            Self::CompileError(message) => write!(f, "{message}"),
+            Self::Unsupported(what) => write!(f, "{what} isn't supported by the interpreter"),
+            Self::NoMain => write!(f, "root source file has no member named 'main'"),
+            Self::Overflow => write!(f, "integer overflow"),
+            Self::DivisionByZero => write!(f, "division by zero"),
+            Self::ShiftOverflow => write!(f, "shift amount is greater than the type size"),
+            Self::CastTruncated => write!(f, "integer cast truncated bits"),
+            Self::FloatOutOfBounds => {
+                write!(f, "integer part of floating point value out of bounds")
+            }
+            Self::InvalidEnumValue => write!(f, "invalid enum value"),
+            Self::IndexOutOfBounds { index, len } => {
+                write!(f, "index out of bounds: index {index}, len {len}")
+            }
+            Self::SliceStartAfterEnd { start, end } => {
+                write!(f, "start index {start} is larger than end index {end}")
+            }
+            Self::NullUnwrap => write!(f, "attempt to use null value"),
+            Self::ErrorUnwrap(name) => write!(f, "attempt to unwrap error: {name}"),
+            Self::Unreachable => write!(f, "reached unreachable code"),
+            Self::Panic(message) => write!(f, "{message}"),
+            Self::StackOverflow => write!(f, "stack overflow"),
+            Self::ErrorReturned(name) => write!(f, "error.{name} returned from main"),
+        }
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl std::error::Error for EvalError {}
@@ -0,0 +1,1196 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    ast::{
+        AssignOp, BinaryOp, Capture, ContainerArg, ContainerDecl, ContainerKind, DeclKind, Expr,
+        ExprKind, FieldInit, Ident, ParamType, UnaryOp,
+    },
+    tokenizer::Span,
+};
+
+use super::{
+    error::EvalErrorKind,
+    int::Int,
+    interpreter::{Exec, Flow, Interpreter},
+    ops,
+    scope::Scope,
+    value::{
+        Array, Container, Field, Function, IntType, Namespace, NativeFn, Place, Pointer, Slice,
+        Step, Struct, Type, Value, Variable,
+    },
+};
+
+impl<'src> Interpreter<'src> {
+    pub(super) fn eval(&mut self, expr: &Expr<'src>) -> Exec<'src, Value<'src>> {
+        // Every arm calls out to another method, which keeps the stack
+        // frame of this one small enough for deep recursion.
+        let span = expr.span;
+        match &expr.kind {
+            ExprKind::Grouped(expr) | ExprKind::NoSuspend(expr) => self.eval(expr),
+            ExprKind::Comptime(expr) => self.eval_comptime(expr),
+            ExprKind::Builtin { name, args } => self.builtin(name, args, span, None),
+            ExprKind::Unary { op, expr } => self.unary(*op, expr, span),
+            ExprKind::Binary { op, lhs, rhs } => self.binary(*op, lhs, rhs, span),
+            ExprKind::Catch { lhs, capture, rhs } => self.catch(lhs, capture.as_ref(), rhs, None),
+            ExprKind::Assign { op, target, value } => self.assign(*op, target, value, span),
+            ExprKind::Call { callee, args } => self.call_expr(callee, args, span),
+            ExprKind::Field { .. }
+            | ExprKind::Index { .. }
+            | ExprKind::Deref(_)
+            | ExprKind::Unwrap(_) => self.access(expr),
+            ExprKind::Slice {
+                expr,
+                start,
+                end,
+                sentinel,
+            } => self.slice(expr, start, end.as_deref(), sentinel.is_some(), span),
+            ExprKind::StructInit { ty, fields } => {
+                self.struct_init(ty.as_deref(), fields, None, span)
+            }
+            ExprKind::ArrayInit { ty, elems } => self.array_init(ty.as_deref(), elems, None, span),
+            ExprKind::Container(decl) => self.container(decl),
+            ExprKind::Block(block) => self.block(block),
+            ExprKind::If(expr) => self.if_expr(expr, None),
+            ExprKind::While(expr) => self.while_loop(expr, None),
+            ExprKind::For(expr) => self.for_loop(expr, None),
+            ExprKind::Switch(expr) => self.switch(expr, None),
+            ExprKind::Break { .. } | ExprKind::Continue { .. } | ExprKind::Return(_) => {
+                Err(self.jump(expr)?)
+            }
+            ExprKind::ErrorSet(_)
+            | ExprKind::Optional(_)
+            | ExprKind::ErrorUnion { .. }
+            | ExprKind::Pointer(_)
+            | ExprKind::Array { .. } => Ok(Value::Type(self.type_expr(expr)?)),
+            _ => self.leaf(expr),
+        }
+    }
+
+    /// Literals, names and the expressions the interpreter can't evaluate.
+    fn leaf(&mut self, expr: &Expr<'src>) -> Exec<'src, Value<'src>> {
+        let unsupported = match &expr.kind {
+            ExprKind::Integer(value) => match value.to_u128() {
+                Ok(value) => return Ok(Value::Int(Int::from(value), None)),
+                Err(_) => "integers over 128 bits",
+            },
+            ExprKind::Float(value) => return Ok(Value::Float(*value, None)),
+            ExprKind::Char(c) => return Ok(Value::Int(Int::from(*c), None)),
+            ExprKind::String(string) | ExprKind::MultilineString(string) => {
+                return Ok(self.string(string.value()))
+            }
+            ExprKind::Identifier(name) => {
+                let value = match name.as_ref() {
+                    "true" => Value::Bool(true),
+                    "false" => Value::Bool(false),
+                    "null" => Value::Null,
+                    "undefined" => Value::Undefined,
+                    name => self.lookup(name, expr.span)?.value(),
+                };
+                return Ok(value);
+            }
+            ExprKind::PrimitiveType(primitive) => {
+                return Ok(Type::from_primitive(*primitive).into())
+            }
+            ExprKind::EnumLiteral(name) => return Ok(Value::EnumLiteral(name.to_string())),
+            ExprKind::ErrorValue(name) => return Ok(Value::Error(name.to_string())),
+            ExprKind::Unreachable => return self.fail(expr.span, EvalErrorKind::Unreachable),
+            ExprKind::Error => "code that failed to parse",
+            ExprKind::Range { .. } => "ranges outside of for loops and switch prongs",
+            ExprKind::FnProto(_) => "function types",
+            ExprKind::Asm(_) => "inline assembly",
+            _ => "async functions",
+        };
+        self.fail(
+            expr.span,
+            EvalErrorKind::Unsupported(unsupported.to_string()),
+        )
+    }
+
+    /// `break`, `continue` and `return`, which leave evaluation by way
+    /// of the `Flow` they produce.
+    fn jump(&mut self, expr: &Expr<'src>) -> Exec<'src, Flow<'src>> {
+        let label = |label: &Option<Ident>| label.as_ref().map(|label| label.name.to_string());
+        let flow = match &expr.kind {
+            ExprKind::Break {
+                label: target,
+                value,
+            } => {
+                let value = match value {
+                    Some(value) => self.eval(value)?,
+                    None => Value::Void,
+                };
+                Flow::Break(label(target), Box::new(value), expr.span)
+            }
+            ExprKind::Continue { label: target } => Flow::Continue(label(target), expr.span),
+            ExprKind::Return(value) => {
+                let ty = self.return_types.last().cloned();
+                let value = match (value, ty) {
+                    (Some(value), Some(ty)) => self.eval_as(value, &ty)?,
+                    (Some(value), None) => self.eval(value)?,
+                    (None, _) => Value::Void,
+                };
+                Flow::Return(Box::new(value), expr.span)
+            }
+            _ => unreachable!("not a jump"),
+        };
+        Ok(flow)
+    }
+
+    /// Expressions that make a type out of other types.
+    fn type_expr(&mut self, expr: &Expr<'src>) -> Exec<'src, Type<'src>> {
+        let ty = match &expr.kind {
+            ExprKind::ErrorSet(names) => {
+                let names = names.iter().map(|name| name.name.to_string()).collect();
+                Type::ErrorSet(Some(Rc::new(names)))
+            }
+            ExprKind::Optional(child) => Type::Optional(Box::new(self.eval_type(child)?)),
+            ExprKind::ErrorUnion { error, payload } => {
+                let error = self.eval_type(error)?;
+                if !matches!(error, Type::ErrorSet(_)) {
+                    let kind = EvalErrorKind::TypeMismatch {
+                        expected: "error set".to_string(),
+                        found: error.to_string(),
+                    };
+                    return self.fail(expr.span, kind);
+                }
+                let payload = self.eval_type(payload)?;
+                Type::ErrorUnion {
+                    error: Box::new(error),
+                    payload: Box::new(payload),
+                }
+            }
+            ExprKind::Pointer(pointer) => {
+                let sentinel = match &pointer.sentinel {
+                    Some(sentinel) => Some(self.eval_sentinel(sentinel)?),
+                    None => None,
+                };
+                Type::Pointer {
+                    size: pointer.size,
+                    is_const: pointer.is_const,
+                    sentinel,
+                    child: Box::new(self.eval_type(&pointer.child)?),
+                }
+            }
+            ExprKind::Array {
+                len,
+                sentinel,
+                child,
+            } => {
+                let len = self.eval_index(len)?;
+                let sentinel = match sentinel {
+                    Some(sentinel) => Some(self.eval_sentinel(sentinel)?),
+                    None => None,
+                };
+                Type::Array {
+                    len,
+                    sentinel,
+                    child: Box::new(self.eval_type(child)?),
+                }
+            }
+            _ => unreachable!("not a type expression"),
+        };
+        Ok(ty)
+    }
+
+    /// Evaluates an expression whose result has to be of type `ty`. The
+    /// type is what gives things like `.{ .x = 1 }`, `.red` and `@intCast(x)`
+    /// their type.
+    pub(super) fn eval_as(
+        &mut self,
+        expr: &Expr<'src>,
+        ty: &Type<'src>,
+    ) -> Exec<'src, Value<'src>> {
+        let span = expr.span;
+        let value = match &expr.kind {
+            ExprKind::Grouped(expr) => return self.eval_as(expr, ty),
+            ExprKind::StructInit { ty: None, fields } => {
+                self.struct_init(None, fields, Some(ty.payload()), span)?
+            }
+            ExprKind::ArrayInit { ty: None, elems } => {
+                self.array_init(None, elems, Some(ty.payload()), span)?
+            }
+            ExprKind::Builtin { name, args } => self.builtin(name, args, span, Some(ty))?,
+            ExprKind::Catch { lhs, capture, rhs } => {
+                self.catch(lhs, capture.as_ref(), rhs, Some(ty))?
+            }
+            ExprKind::If(expr) => self.if_expr(expr, Some(ty))?,
+            ExprKind::Switch(expr) => self.switch(expr, Some(ty))?,
+            _ => self.eval(expr)?,
+        };
+        self.coerce(value, ty, span)
+    }
+
+    /// Evaluates a type, which has to be known at comptime.
+    pub(super) fn eval_type(&mut self, expr: &Expr<'src>) -> Exec<'src, Type<'src>> {
+        match self.eval_comptime(expr)? {
+            Value::Type(ty) => Ok(ty),
+            value => {
+                let kind = EvalErrorKind::TypeMismatch {
+                    expected: "type".to_string(),
+                    found: value.type_of().to_string(),
+                };
+                self.fail(expr.span, kind)
+            }
+        }
+    }
+
+    pub(super) fn eval_bool(&mut self, expr: &Expr<'src>) -> Exec<'src, bool> {
+        match self.eval_as(expr, &Type::Bool)? {
+            Value::Bool(value) => Ok(value),
+            _ => self.fail(expr.span, EvalErrorKind::UndefinedValue),
+        }
+    }
+
+    pub(super) fn eval_int(&mut self, expr: &Expr<'src>) -> Exec<'src, Int> {
+        match self.eval(expr)? {
+            Value::Int(value, _) => Ok(value),
+            value => self.fail(expr.span, ops::expected_int(&value)),
+        }
+    }
+
+    fn eval_sentinel(&mut self, expr: &Expr<'src>) -> Exec<'src, i64> {
+        let value = self.eval_int(expr)?;
+        match value.to() {
+            Some(value) => Ok(value),
+            None => {
+                let kind =
+                    EvalErrorKind::Unsupported("sentinels that don't fit in an i64".to_string());
+                self.fail(expr.span, kind)
+            }
+        }
+    }
+
+    /// Evaluates an index or a length, which is a `usize`.
+    pub(super) fn eval_index(&mut self, expr: &Expr<'src>) -> Exec<'src, usize> {
+        match self.eval_as(expr, &Type::Int(IntType::USIZE))? {
+            Value::Int(value, _) => Ok(value.to().expect("coerced to a usize")),
+            _ => self.fail(expr.span, EvalErrorKind::UndefinedValue),
+        }
+    }
+
+    /// Makes a string literal, which is a pointer to a constant array of bytes.
+    pub(super) fn string(&self, bytes: &[u8]) -> Value<'src> {
+        let elems = bytes
+            .iter()
+            .map(|&byte| Value::Int(Int::from(byte), Some(IntType::U8)))
+            .collect();
+        Self::constant(Value::Array(Box::new(Array {
+            child: Type::Int(IntType::U8),
+            sentinel: Some(0),
+            elems,
+        })))
+    }
+
+    /// Makes a pointer to a new constant holding `value`.
+    pub(super) fn constant(value: Value<'src>) -> Value<'src> {
+        let ty = value.type_of();
+        let place = Place::new(Variable::new(value, ty.clone(), true));
+        Value::Pointer(Box::new(Pointer { place, child: ty }))
+    }
+
+    fn unary(&mut self, op: UnaryOp, operand: &Expr<'src>, span: Span) -> Exec<'src, Value<'src>> {
+        match op {
+            UnaryOp::AddressOf => return self.address_of(operand),
+            UnaryOp::Await => {
+                return self.fail(
+                    span,
+                    EvalErrorKind::Unsupported("async functions".to_string()),
+                )
+            }
+            UnaryOp::Try => {
+                return match self.eval(operand)? {
+                    Value::Error(name) => Err(Flow::Return(Box::new(Value::Error(name)), span)),
+                    value => Ok(value),
+                }
+            }
+            _ => {}
+        }
+        let value = self.eval(operand)?;
+        if let Value::Undefined = value {
+            return self.fail(operand.span, EvalErrorKind::UndefinedValue);
+        }
+        let result = match op {
+            UnaryOp::Not => match value {
+                Value::Bool(value) => Ok(Value::Bool(!value)),
+                value => Err(EvalErrorKind::InvalidOperator {
+                    op: "!",
+                    ty: value.type_of().to_string(),
+                }),
+            },
+            UnaryOp::Negate => ops::negate(&value, false),
+            UnaryOp::NegateWrap => ops::negate(&value, true),
+            UnaryOp::BitNot => ops::bit_not(&value),
+            UnaryOp::AddressOf | UnaryOp::Await | UnaryOp::Try => unreachable!("handled above"),
+        };
+        self.at(span, result)
+    }
+
+    fn address_of(&mut self, operand: &Expr<'src>) -> Exec<'src, Value<'src>> {
+        match self.place_of(operand)? {
+            Some(place) => {
+                let child = self.place_type(&place);
+                Ok(Value::Pointer(Box::new(Pointer { place, child })))
+            }
+            // A pointer to a temporary, like `&[_]u8{ 1, 2 }`, is to a constant.
+            None => Ok(Self::constant(self.eval(operand)?)),
+        }
+    }
+
+    fn binary(
+        &mut self,
+        op: BinaryOp,
+        lhs: &Expr<'src>,
+        rhs: &Expr<'src>,
+        span: Span,
+    ) -> Exec<'src, Value<'src>> {
+        match op {
+            BinaryOp::And | BinaryOp::Or => {
+                let lhs = self.eval_bool(lhs)?;
+                // `and` stops at false and `or` at true.
+                if lhs != (op == BinaryOp::And) {
+                    return Ok(Value::Bool(lhs));
+                }
+                Ok(Value::Bool(self.eval_bool(rhs)?))
+            }
+            BinaryOp::OrElse => match self.eval(lhs)? {
+                Value::Null if matches!(rhs.kind, ExprKind::Unreachable) => {
+                    self.fail(rhs.span, EvalErrorKind::NullUnwrap)
+                }
+                Value::Null => self.eval(rhs),
+                value => Ok(value),
+            },
+            BinaryOp::Concat | BinaryOp::Repeat => self.concat(op, lhs, rhs, span),
+            BinaryOp::MergeErrorSets => {
+                let lhs = self.eval_type(lhs)?;
+                let rhs = self.eval_type(rhs)?;
+                match (lhs, rhs) {
+                    (Type::ErrorSet(Some(a)), Type::ErrorSet(Some(b))) => {
+                        let mut names = (*a).clone();
+                        names.extend(b.iter().filter(|name| !a.contains(name)).cloned());
+                        Ok(Type::ErrorSet(Some(Rc::new(names))).into())
+                    }
+                    (Type::ErrorSet(_), Type::ErrorSet(_)) => Ok(Type::ErrorSet(None).into()),
+                    (lhs, _) => {
+                        let kind = EvalErrorKind::InvalidOperator {
+                            op: "||",
+                            ty: lhs.to_string(),
+                        };
+                        self.fail(span, kind)
+                    }
+                }
+            }
+            _ => {
+                let lhs = self.eval(lhs)?;
+                let rhs = self.eval(rhs)?;
+                self.arith(op, &lhs, &rhs, span)
+            }
+        }
+    }
+
+    pub(super) fn arith(
+        &self,
+        op: BinaryOp,
+        lhs: &Value<'src>,
+        rhs: &Value<'src>,
+        span: Span,
+    ) -> Exec<'src, Value<'src>> {
+        let comparison = matches!(op, BinaryOp::Equal | BinaryOp::NotEqual);
+        if !comparison && (matches!(lhs, Value::Undefined) || matches!(rhs, Value::Undefined)) {
+            return self.fail(span, EvalErrorKind::UndefinedValue);
+        }
+        self.at(span, ops::binary(op, lhs, rhs))
+    }
+
+    /// `++` and `**` on arrays and strings.
+    fn concat(
+        &mut self,
+        op: BinaryOp,
+        lhs: &Expr<'src>,
+        rhs: &Expr<'src>,
+        span: Span,
+    ) -> Exec<'src, Value<'src>> {
+        let lhs_value = self.eval(lhs)?;
+        let Some((elems, child, is_pointer)) = self.elems(&lhs_value) else {
+            let kind = EvalErrorKind::InvalidOperator {
+                op: ops::symbol(op),
+                ty: lhs_value.type_of().to_string(),
+            };
+            return self.fail(lhs.span, kind);
+        };
+        let elems = match op {
+            BinaryOp::Concat => {
+                let rhs_value = self.eval(rhs)?;
+                let Some((rhs_elems, _, _)) = self.elems(&rhs_value) else {
+                    let kind = EvalErrorKind::InvalidOperator {
+                        op: "++",
+                        ty: rhs_value.type_of().to_string(),
+                    };
+                    return self.fail(rhs.span, kind);
+                };
+                let mut elems = elems;
+                for elem in rhs_elems {
+                    elems.push(self.coerce(elem, &child, span)?);
+                }
+                elems
+            }
+            _ => {
+                let times = self.eval_index(rhs)?;
+                let len = elems.len() * times;
+                elems.into_iter().cycle().take(len).collect()
+            }
+        };
+        let sentinel = is_pointer
+            .then_some(0)
+            .filter(|_| child == Type::Int(IntType::U8));
+        let array = Value::Array(Box::new(Array {
+            child,
+            sentinel,
+            elems,
+        }));
+        match is_pointer {
+            true => Ok(Self::constant(array)),
+            false => Ok(array),
+        }
+    }
+
+    /// The elements of an array, tuple or a pointer to an array, along
+    /// with their type and whether it was a pointer.
+    fn elems(&self, value: &Value<'src>) -> Option<(Vec<Value<'src>>, Type<'src>, bool)> {
+        match value {
+            Value::Array(array) => Some((array.elems.clone(), array.child.clone(), false)),
+            Value::Tuple(elems) => {
+                let child = elems.first().map_or(Type::ComptimeInt, Value::type_of);
+                Some((elems.clone(), child, false))
+            }
+            Value::Pointer(pointer) => pointer.place.with(|value| match value {
+                Value::Array(array) => Some((array.elems.clone(), array.child.clone(), true)),
+                _ => None,
+            }),
+            Value::Slice(slice) => Some((slice.elems(), slice.child.clone(), true)),
+            _ => None,
+        }
+    }
+
+    pub(super) fn catch(
+        &mut self,
+        lhs: &Expr<'src>,
+        capture: Option<&Capture<'src>>,
+        rhs: &Expr<'src>,
+        ty: Option<&Type<'src>>,
+    ) -> Exec<'src, Value<'src>> {
+        let name = match self.eval(lhs)? {
+            Value::Error(name) => name,
+            value => return Ok(value),
+        };
+        if let ExprKind::Unreachable = rhs.kind {
+            return self.fail(rhs.span, EvalErrorKind::ErrorUnwrap(name));
+        }
+        let capture = capture.map(|capture| (capture, Value::Error(name)));
+        self.branch(rhs, capture, ty)
+    }
+
+    fn assign(
+        &mut self,
+        op: AssignOp,
+        target: &Expr<'src>,
+        value: &Expr<'src>,
+        span: Span,
+    ) -> Exec<'src, Value<'src>> {
+        if let ExprKind::Identifier(name) = &target.kind {
+            if name == "_" {
+                self.eval(value)?;
+                return Ok(Value::Void);
+            }
+        }
+        let Some(place) = self.place_of(target)? else {
+            return self.fail(target.span, EvalErrorKind::NotAssignable);
+        };
+        if place.is_const {
+            return self.fail(target.span, EvalErrorKind::AssignToConst);
+        }
+        let ty = self.place_type(&place);
+        let new = match assign_op(op) {
+            None => self.eval_as(value, &ty)?,
+            Some(op) => {
+                let current = place.get();
+                let rhs = self.eval(value)?;
+                let result = self.arith(op, &current, &rhs, span)?;
+                self.coerce(result, &ty, span)?
+            }
+        };
+        match place.set(new) {
+            true => Ok(Value::Void),
+            false => self.fail(target.span, EvalErrorKind::UndefinedValue),
+        }
+    }
+
+    /// The type of the value at `place`, going by the declared types
+    /// along the way.
+    pub(super) fn place_type(&self, place: &Place<'src>) -> Type<'src> {
+        let mut ty = place.var.ty.clone();
+        for step in &place.path {
+            let next = match (step, ty.payload()) {
+                (Step::Index(_), Type::Array { child, .. }) => Some((**child).clone()),
+                (Step::Field(i), Type::Container(container)) => {
+                    container.fields().get(*i).map(|field| field.ty.clone())
+                }
+                (Step::Field(i), Type::Tuple(types)) => types.get(*i).cloned(),
+                (Step::Field(i), Type::AnonStruct(fields)) => {
+                    fields.get(*i).map(|(_, ty)| ty.clone())
+                }
+                _ => None,
+            };
+            match next {
+                Some(next) => ty = next,
+                None => return place.get().type_of(),
+            }
+        }
+        ty
+    }
+
+    /// Finds where the value of an expression is stored, for expressions
+    /// that can be assigned to or have their address taken. Returns
+    /// `None` for expressions that produce a temporary value.
+    pub(super) fn place_of(&mut self, expr: &Expr<'src>) -> Exec<'src, Option<Place<'src>>> {
+        match &expr.kind {
+            ExprKind::Identifier(name) => match name.as_ref() {
+                "true" | "false" | "null" | "undefined" => Ok(None),
+                name => Ok(Some(Place::new(self.lookup(name, expr.span)?))),
+            },
+            ExprKind::Grouped(expr) => self.place_of(expr),
+            ExprKind::Field { expr: base, name } => match self.place_of(base)? {
+                Some(base) => self.field_place(base, name),
+                None => Ok(None),
+            },
+            ExprKind::Index { expr: base, index } => match self.place_of(base)? {
+                Some(base) => self.index_place(base, index),
+                None => Ok(None),
+            },
+            ExprKind::Deref(pointer) => match self.eval(pointer)? {
+                Value::Pointer(pointer) => Ok(Some(pointer.place)),
+                Value::Undefined => self.fail(pointer.span, EvalErrorKind::UndefinedValue),
+                value => {
+                    let kind = EvalErrorKind::TypeMismatch {
+                        expected: "pointer".to_string(),
+                        found: value.type_of().to_string(),
+                    };
+                    self.fail(pointer.span, kind)
+                }
+            },
+            ExprKind::Unwrap(optional) => {
+                let Some(place) = self.place_of(optional)? else {
+                    return Ok(None);
+                };
+                match place.with(|value| matches!(value, Value::Null)) {
+                    true => self.fail(expr.span, EvalErrorKind::NullUnwrap),
+                    false => Ok(Some(place)),
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn field_place(
+        &mut self,
+        base: Place<'src>,
+        name: &Ident<'src>,
+    ) -> Exec<'src, Option<Place<'src>>> {
+        enum Found<'src> {
+            Field(usize),
+            Payload(Rc<Container<'src>>, usize, usize),
+            Decl(Rc<Container<'src>>),
+        }
+        // Fields are reached through pointers without having to write `.*`.
+        let base = match base.with(|value| match value {
+            Value::Pointer(pointer) => Some(pointer.place.clone()),
+            _ => None,
+        }) {
+            Some(pointee) => pointee,
+            None => base,
+        };
+        let found = base.with(|value| match value {
+            Value::Struct(s) => s
+                .fields
+                .iter()
+                .position(|(field, _)| *field == name.name)
+                .map(Found::Field),
+            Value::Union(container, active, _) => container
+                .field_index(&name.name)
+                .map(|index| Found::Payload(container.clone(), *active, index)),
+            Value::Type(Type::Container(container)) => Some(Found::Decl(container.clone())),
+            _ => None,
+        });
+        match found {
+            Some(Found::Field(index)) => Ok(Some(base.child(Step::Field(index)))),
+            Some(Found::Payload(container, active, index)) => {
+                if active != index {
+                    let fields = container.fields();
+                    let message = format!(
+                        "access of union field '{}' while field '{}' is active",
+                        fields[index].name, fields[active].name
+                    );
+                    return self.fail(name.span, EvalErrorKind::Panic(message));
+                }
+                Ok(Some(base.child(Step::Payload)))
+            }
+            Some(Found::Decl(container)) => {
+                let var = self.member(&container.scope, &name.name, name.span)?;
+                Ok(var.map(Place::new))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn index_place(
+        &mut self,
+        base: Place<'src>,
+        index: &Expr<'src>,
+    ) -> Exec<'src, Option<Place<'src>>> {
+        enum Found<'src> {
+            Array(Place<'src>, usize),
+            Slice(Slice<'src>),
+            Tuple(usize),
+        }
+        let found = base.with(|value| match value {
+            Value::Array(array) => Some(Found::Array(base.clone(), array.elems.len())),
+            Value::Tuple(elems) => Some(Found::Tuple(elems.len())),
+            Value::Pointer(pointer) => pointer.place.with(|value| match value {
+                Value::Array(array) => Some(Found::Array(pointer.place.clone(), array.elems.len())),
+                _ => None,
+            }),
+            Value::Slice(slice) => Some(Found::Slice((**slice).clone())),
+            _ => None,
+        });
+        let Some(found) = found else {
+            return Ok(None);
+        };
+        let i = self.eval_index(index)?;
+        let (len, place) = match found {
+            Found::Array(place, len) => (len, place.child(Step::Index(i))),
+            Found::Slice(slice) => (slice.len, slice.elem(i)),
+            Found::Tuple(len) => (len, base.child(Step::Field(i))),
+        };
+        match i < len {
+            true => Ok(Some(place)),
+            false => self.fail(
+                index.span,
+                EvalErrorKind::IndexOutOfBounds { index: i, len },
+            ),
+        }
+    }
+
+    /// Reads a field, an element, a pointer or an optional's payload.
+    fn access(&mut self, expr: &Expr<'src>) -> Exec<'src, Value<'src>> {
+        if let Some(place) = self.place_of(expr)? {
+            return Ok(place.get());
+        }
+        match &expr.kind {
+            ExprKind::Field { expr, name } => {
+                let value = self.eval(expr)?;
+                self.field(value, name)
+            }
+            ExprKind::Index { expr: base, index } => {
+                let value = self.eval(base)?;
+                let place = Place::new(Variable::new(value, Type::Void, true));
+                match self.index_place(place, index)? {
+                    Some(place) => Ok(place.get()),
+                    None => {
+                        let ty = self.eval(base)?.type_of().to_string();
+                        self.fail(base.span, EvalErrorKind::NotIndexable(ty))
+                    }
+                }
+            }
+            ExprKind::Unwrap(optional) => match self.eval(optional)? {
+                Value::Null => self.fail(expr.span, EvalErrorKind::NullUnwrap),
+                value => Ok(value),
+            },
+            _ => unreachable!("dereferences always have a place"),
+        }
+    }
+
+    /// Reads a field of a value that isn't stored anywhere, or a member
+    /// of a type.
+    pub(super) fn field(
+        &mut self,
+        value: Value<'src>,
+        name: &Ident<'src>,
+    ) -> Exec<'src, Value<'src>> {
+        let span = name.span;
+        let len = |len: usize| Ok(Value::Int(Int::from(len), Some(IntType::USIZE)));
+        match value {
+            Value::Pointer(pointer) => match pointer.place.get() {
+                pointee @ (Value::Array(_) | Value::Struct(_) | Value::Union(..)) => {
+                    self.field(pointee, name)
+                }
+                _ => self.no_member(&Value::Pointer(pointer), name),
+            },
+            Value::Array(array) if name.name == "len" => len(array.elems.len()),
+            Value::Slice(slice) if name.name == "len" => len(slice.len),
+            Value::Tuple(elems) if name.name == "len" => len(elems.len()),
+            Value::Struct(ref s) => match s.fields.iter().find(|(field, _)| *field == name.name) {
+                Some((_, value)) => Ok(value.clone()),
+                None => self.no_member(&value, name),
+            },
+            Value::Union(ref container, active, ref payload) => {
+                match container.field_index(&name.name) {
+                    Some(index) if index == active => Ok((**payload).clone()),
+                    Some(index) => {
+                        let fields = container.fields();
+                        let message = format!(
+                            "access of union field '{}' while field '{}' is active",
+                            fields[index].name, fields[active].name
+                        );
+                        self.fail(span, EvalErrorKind::Panic(message))
+                    }
+                    None => self.no_member(&value, name),
+                }
+            }
+            Value::Type(Type::Container(ref container)) => {
+                if let Some(var) = self.member(&container.scope, &name.name, span)? {
+                    return Ok(var.value());
+                }
+                self.resolve_fields(container)?;
+                match container.field_index(&name.name) {
+                    Some(index) if container.kind == ContainerKind::Enum => {
+                        Ok(Value::Enum(container.clone(), index))
+                    }
+                    _ => self.no_member(&value, name),
+                }
+            }
+            Value::Type(Type::ErrorSet(ref names)) => match names {
+                Some(names) if !names.iter().any(|error| *error == name.name) => {
+                    self.no_member(&value, name)
+                }
+                _ => Ok(Value::Error(name.name.to_string())),
+            },
+            Value::Type(Type::Namespace(namespace)) => match (namespace, name.name.as_ref()) {
+                (Namespace::Std, "debug") => Ok(Type::Namespace(Namespace::Debug).into()),
+                (Namespace::Debug, "print") => Ok(Value::NativeFn(NativeFn::Print)),
+                (Namespace::Debug, "assert") => Ok(Value::NativeFn(NativeFn::Assert)),
+                _ => {
+                    let what = format!("'{}.{}'", Type::Namespace(namespace), name.name);
+                    self.fail(span, EvalErrorKind::Unsupported(what))
+                }
+            },
+            Value::Undefined => self.fail(span, EvalErrorKind::UndefinedValue),
+            value => self.no_member(&value, name),
+        }
+    }
+
+    fn no_member<T>(&self, value: &Value<'src>, name: &Ident<'src>) -> Exec<'src, T> {
+        let ty = match value {
+            Value::Type(ty) => ty.to_string(),
+            value => value.type_of().to_string(),
+        };
+        let kind = EvalErrorKind::NoMember {
+            ty,
+            name: name.name.to_string(),
+        };
+        self.fail(name.span, kind)
+    }
+
+    fn slice(
+        &mut self,
+        base: &Expr<'src>,
+        start: &Expr<'src>,
+        end: Option<&Expr<'src>>,
+        has_sentinel: bool,
+        span: Span,
+    ) -> Exec<'src, Value<'src>> {
+        if has_sentinel {
+            let kind = EvalErrorKind::Unsupported("sentinel terminated slices".to_string());
+            return self.fail(span, kind);
+        }
+        let place = match self.place_of(base)? {
+            Some(place) => place,
+            None => {
+                let value = self.eval(base)?;
+                let ty = value.type_of();
+                Place::new(Variable::new(value, ty, true))
+            }
+        };
+        let array = place.with(|value| match value {
+            Value::Array(array) => Ok((place.clone(), 0, array.elems.len(), array.child.clone())),
+            Value::Pointer(pointer) => pointer.place.with(|value| match value {
+                Value::Array(array) => Ok((
+                    pointer.place.clone(),
+                    0,
+                    array.elems.len(),
+                    array.child.clone(),
+                )),
+                value => Err(value.type_of()),
+            }),
+            Value::Slice(slice) => Ok((
+                slice.place.clone(),
+                slice.start,
+                slice.len,
+                slice.child.clone(),
+            )),
+            value => Err(value.type_of()),
+        });
+        let (place, offset, len, child) = match array {
+            Ok(array) => array,
+            Err(ty) => return self.fail(base.span, EvalErrorKind::NotIndexable(ty.to_string())),
+        };
+        let start = self.eval_index(start)?;
+        let end = match end {
+            Some(end) => self.eval_index(end)?,
+            None => len,
+        };
+        if end > len {
+            return self.fail(span, EvalErrorKind::IndexOutOfBounds { index: end, len });
+        }
+        if start > end {
+            return self.fail(span, EvalErrorKind::SliceStartAfterEnd { start, end });
+        }
+        Ok(Value::Slice(Box::new(Slice {
+            place,
+            start: offset + start,
+            len: end - start,
+            child,
+        })))
+    }
+
+    fn call_expr(
+        &mut self,
+        callee: &Expr<'src>,
+        args: &[Expr<'src>],
+        span: Span,
+    ) -> Exec<'src, Value<'src>> {
+        if let ExprKind::Field { expr, name } = &callee.kind {
+            if let Some((function, receiver)) = self.method(expr, name)? {
+                return self.call(&function, Some(receiver), args, span);
+            }
+        }
+        match self.eval(callee)? {
+            Value::Fn(function) => self.call(&function, None, args, span),
+            Value::NativeFn(native) => self.native(native, args, span),
+            value => {
+                let kind = EvalErrorKind::NotCallable(value.type_of().to_string());
+                self.fail(callee.span, kind)
+            }
+        }
+    }
+
+    /// Looks up `base.name(...)` as a method call, where `base` is passed
+    /// as the first argument. Methods that take a pointer get a pointer
+    /// to `base`.
+    fn method(
+        &mut self,
+        base: &Expr<'src>,
+        name: &Ident<'src>,
+    ) -> Exec<'src, Option<(Rc<Function<'src>>, Value<'src>)>> {
+        let place = self.place_of(base)?;
+        let value = match &place {
+            Some(place) => place.get(),
+            None => self.eval(base)?,
+        };
+        let container = |value: &Value<'src>| match value {
+            Value::Struct(Struct { ty: Some(c), .. }) | Value::Enum(c, _) | Value::Union(c, ..) => {
+                Some(c.clone())
+            }
+            _ => None,
+        };
+        let (container, pointee) = match &value {
+            Value::Pointer(pointer) => {
+                let pointee = pointer.place.get();
+                (container(&pointee), Some(pointee))
+            }
+            value => (container(value), None),
+        };
+        let Some(container) = container else {
+            return Ok(None);
+        };
+        // Fields can hold functions too, which aren't methods.
+        if container.kind != ContainerKind::Enum && container.field_index(&name.name).is_some() {
+            return Ok(None);
+        }
+        let Some(var) = self.member(&container.scope, &name.name, name.span)? else {
+            return Ok(None);
+        };
+        let Value::Fn(function) = var.value() else {
+            return Ok(None);
+        };
+        let takes_pointer = function.decl.proto.params.first().is_some_and(|param| {
+            matches!(
+                &param.ty,
+                ParamType::Expr(Expr {
+                    kind: ExprKind::Pointer(_),
+                    ..
+                })
+            )
+        });
+        let receiver = match (takes_pointer, pointee) {
+            (true, None) => {
+                let place = place.unwrap_or_else(|| {
+                    let ty = value.type_of();
+                    Place::new(Variable::new(value, ty, true))
+                });
+                let child = self.place_type(&place);
+                Value::Pointer(Box::new(Pointer { place, child }))
+            }
+            (false, Some(pointee)) => pointee,
+            (_, _) => value,
+        };
+        Ok(Some((function, receiver)))
+    }
+
+    fn container(&mut self, decl: &ContainerDecl<'src>) -> Exec<'src, Value<'src>> {
+        let scope = Scope::child(&self.scope);
+        let decl = Rc::new(decl.clone());
+        let container = Rc::new(Container {
+            kind: decl.kind,
+            name: RefCell::new(None),
+            decl: decl.clone(),
+            scope: scope.clone(),
+            fields: RefCell::new(None),
+        });
+        scope.set_container(&container);
+        let comptime = self.declare_members(&scope, &decl.decls)?;
+        for block in comptime {
+            self.in_scope(scope.clone(), |this| {
+                this.comptime(|this| this.block(block))
+            })?;
+        }
+        Ok(Type::Container(container).into())
+    }
+
+    /// Evaluates the field types of a container, which is put off until
+    /// they're needed so that they can refer to the container itself.
+    pub(super) fn resolve_fields(&mut self, container: &Rc<Container<'src>>) -> Exec<'src, ()> {
+        if container.fields.borrow().is_some() {
+            return Ok(());
+        }
+        let decl = container.decl.clone();
+        let scope = container.scope.clone();
+        let tag_type = match &decl.arg {
+            Some(ContainerArg::Type(ty)) if decl.kind == ContainerKind::Enum => {
+                Some(self.in_scope(scope.clone(), |this| this.eval_type(ty))?)
+            }
+            _ => None,
+        };
+        let mut fields = Vec::new();
+        let mut next_tag = Some(Int::ZERO);
+        for member in &decl.decls {
+            let DeclKind::Field(field) = &member.kind else {
+                continue;
+            };
+            let name = match &field.name {
+                Some(name) => name.name.to_string(),
+                None => fields.len().to_string(),
+            };
+            let field = self.in_scope(scope.clone(), |this| {
+                if decl.kind == ContainerKind::Enum {
+                    let tag = match &field.value {
+                        Some(value) => this.eval_int(value)?,
+                        None => match next_tag {
+                            Some(tag) => tag,
+                            None => return this.fail(member.span, EvalErrorKind::Overflow),
+                        },
+                    };
+                    next_tag = tag.checked_add(Int::ONE);
+                    let ty = match &tag_type {
+                        Some(Type::Int(ty)) => Some(*ty),
+                        _ => None,
+                    };
+                    return Ok(Field {
+                        name,
+                        ty: Type::Void,
+                        default: Some(Value::Int(tag, ty)),
+                    });
+                }
+                let ty = match &field.ty {
+                    Some(ty) => this.eval_type(ty)?,
+                    None => Type::Void,
+                };
+                let default = match &field.value {
+                    Some(value) => Some(this.eval_as(value, &ty)?),
+                    None => None,
+                };
+                Ok(Field { name, ty, default })
+            });
+            fields.push(field?);
+        }
+        if container.fields.borrow().is_none() {
+            *container.fields.borrow_mut() = Some(Rc::new(fields));
+        }
+        Ok(())
+    }
+
+    pub(super) fn struct_init(
+        &mut self,
+        ty: Option<&Expr<'src>>,
+        fields: &[FieldInit<'src>],
+        hint: Option<&Type<'src>>,
+        span: Span,
+    ) -> Exec<'src, Value<'src>> {
+        let ty = match ty {
+            Some(ty) => Some(self.eval_type(ty)?),
+            None => hint.cloned(),
+        };
+        let container = match &ty {
+            Some(Type::Container(container)) if container.kind != ContainerKind::Enum => {
+                container.clone()
+            }
+            Some(Type::Array { len: 0, .. }) if fields.is_empty() => {
+                return Ok(Value::Tuple(Vec::new()));
+            }
+            // Without a type to go by it's an anonymous struct, which
+            // can still be coerced to a struct later.
+            _ => {
+                let mut values = Vec::new();
+                for field in fields {
+                    values.push((field.name.name.to_string(), self.eval(&field.value)?));
+                }
+                return Ok(Value::Struct(Struct {
+                    ty: None,
+                    fields: values,
+                }));
+            }
+        };
+        self.resolve_fields(&container)?;
+        let declared = container.fields();
+        for field in fields {
+            if container.field_index(&field.name.name).is_none() {
+                let kind = EvalErrorKind::NoMember {
+                    ty: container.name(),
+                    name: field.name.name.to_string(),
+                };
+                return self.fail(field.name.span, kind);
+            }
+        }
+
+        if container.kind == ContainerKind::Union {
+            let [field] = fields else {
+                return self.fail(span, EvalErrorKind::UnionInit);
+            };
+            let index = container
+                .field_index(&field.name.name)
+                .expect("checked above");
+            let value = self.eval_as(&field.value, &declared[index].ty)?;
+            return Ok(Value::Union(container, index, Box::new(value)));
+        }
+
+        let mut values = Vec::new();
+        for declared in declared.iter() {
+            let value = match fields.iter().find(|field| field.name.name == declared.name) {
+                Some(field) => self.eval_as(&field.value, &declared.ty)?,
+                None => match &declared.default {
+                    Some(default) => default.clone(),
+                    None => {
+                        let kind = EvalErrorKind::MissingField(declared.name.clone());
+                        return self.fail(span, kind);
+                    }
+                },
+            };
+            values.push((declared.name.clone(), value));
+        }
+        Ok(Value::Struct(Struct {
+            ty: Some(container),
+            fields: values,
+        }))
+    }
+
+    pub(super) fn array_init(
+        &mut self,
+        ty: Option<&Expr<'src>>,
+        elems: &[Expr<'src>],
+        hint: Option<&Type<'src>>,
+        span: Span,
+    ) -> Exec<'src, Value<'src>> {
+        let (len, sentinel, child) = match ty {
+            // `[_]T{ ... }` takes its length from the elements.
+            Some(Expr {
+                kind:
+                    ExprKind::Array {
+                        len,
+                        sentinel,
+                        child,
+                    },
+                ..
+            }) => {
+                let inferred = matches!(&len.kind, ExprKind::Identifier(name) if name == "_");
+                let len = match inferred {
+                    true => elems.len(),
+                    false => self.eval_index(len)?,
+                };
+                let sentinel = match sentinel {
+                    Some(sentinel) => Some(self.eval_sentinel(sentinel)?),
+                    None => None,
+                };
+                (len, sentinel, self.eval_type(child)?)
+            }
+            Some(ty) => match self.eval_type(ty)? {
+                Type::Array {
+                    len,
+                    sentinel,
+                    child,
+                } => (len, sentinel, *child),
+                ty => {
+                    let kind = EvalErrorKind::TypeMismatch {
+                        expected: "array type".to_string(),
+                        found: ty.to_string(),
+                    };
+                    return self.fail(span, kind);
+                }
+            },
+            None => match hint {
+                Some(Type::Array {
+                    len,
+                    sentinel,
+                    child,
+                }) => (*len, *sentinel, (**child).clone()),
+                _ => {
+                    let mut values = Vec::new();
+                    for elem in elems {
+                        values.push(self.eval(elem)?);
+                    }
+                    return Ok(Value::Tuple(values));
+                }
+            },
+        };
+        if len != elems.len() {
+            let found = elems.len();
+            return self.fail(
+                span,
+                EvalErrorKind::ArrayLength {
+                    expected: len,
+                    found,
+                },
+            );
+        }
+        let mut values = Vec::new();
+        for elem in elems {
+            values.push(self.eval_as(elem, &child)?);
+        }
+        Ok(Value::Array(Box::new(Array {
+            child,
+            sentinel,
+            elems: values,
+        })))
+    }
+}
+
+fn assign_op(op: AssignOp) -> Option<BinaryOp> {
+    let op = match op {
+        AssignOp::Assign => return None,
+        AssignOp::Mul => BinaryOp::Mul,
+        AssignOp::MulWrap => BinaryOp::MulWrap,
+        AssignOp::MulSat => BinaryOp::MulSat,
+        AssignOp::Div => BinaryOp::Div,
+        AssignOp::Mod => BinaryOp::Mod,
+        AssignOp::Add => BinaryOp::Add,
+        AssignOp::AddWrap => BinaryOp::AddWrap,
+        AssignOp::AddSat => BinaryOp::AddSat,
+        AssignOp::Sub => BinaryOp::Sub,
+        AssignOp::SubWrap => BinaryOp::SubWrap,
+        AssignOp::SubSat => BinaryOp::SubSat,
+        AssignOp::Shl => BinaryOp::Shl,
+        AssignOp::ShlSat => BinaryOp::ShlSat,
+        AssignOp::Shr => BinaryOp::Shr,
+        AssignOp::BitAnd => BinaryOp::BitAnd,
+        AssignOp::BitXor => BinaryOp::BitXor,
+        AssignOp::BitOr => BinaryOp::BitOr,
+    };
+    Some(op)
+}
@@ -0,0 +1,305 @@
+use std::{fmt, rc::Rc};
+
+use super::{
+    ops,
+    value::{Struct, Value},
+};
+
+/// Formats `args` the way `std.fmt.format` does, for `std.debug.print`.
+/// Returns a description of what's wrong if the format string doesn't
+/// fit the arguments.
+pub(super) fn format(fmt: &str, args: &[Value]) -> Result<String, String> {
+    let mut out = String::new();
+    let mut used = vec![false; args.len()];
+    let mut next = 0;
+    let mut chars = fmt.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '}' => return Err("missing opening {".to_string()),
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err("missing closing }".to_string()),
+                    }
+                }
+                let placeholder = Placeholder::parse(&placeholder)?;
+                let index = placeholder.position.unwrap_or_else(|| {
+                    next += 1;
+                    next - 1
+                });
+                let Some(arg) = args.get(index) else {
+                    return Err("too few arguments".to_string());
+                };
+                used[index] = true;
+                let text = value(arg, placeholder.spec, placeholder.precision)?;
+                out.push_str(&placeholder.pad(text));
+            }
+            c => out.push(c),
+        }
+    }
+    match used.iter().all(|&used| used) {
+        true => Ok(out),
+        false => Err(format!("unused argument in '{fmt}'")),
+    }
+}
+
+/// A `{[position][specifier]:[fill][alignment][width].[precision]}`.
+struct Placeholder<'a> {
+    position: Option<usize>,
+    spec: &'a str,
+    fill: char,
+    align: char,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl<'a> Placeholder<'a> {
+    fn parse(placeholder: &'a str) -> Result<Self, String> {
+        let (head, options) = placeholder.split_once(':').unwrap_or((placeholder, ""));
+        let (position, spec) = match head.strip_prefix('[') {
+            Some(rest) => {
+                let (position, spec) = rest
+                    .split_once(']')
+                    .ok_or_else(|| "missing ] after argument position".to_string())?;
+                (Some(position), spec)
+            }
+            None => {
+                let digits =
+                    head.len() - head.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+                match digits {
+                    0 => (None, head),
+                    _ => (Some(&head[..digits]), &head[digits..]),
+                }
+            }
+        };
+        let position = match position {
+            Some(position) => Some(
+                position
+                    .parse()
+                    .map_err(|_| format!("invalid argument position '{position}'"))?,
+            ),
+            None => None,
+        };
+
+        let mut placeholder = Placeholder {
+            position,
+            spec,
+            fill: ' ',
+            align: '>',
+            width: 0,
+            precision: None,
+        };
+        let mut options = options;
+        let mut chars = options.chars();
+        match (chars.next(), chars.next()) {
+            (Some(fill), Some(align @ ('<' | '^' | '>'))) => {
+                placeholder.fill = fill;
+                placeholder.align = align;
+                options = &options[fill.len_utf8() + 1..];
+            }
+            (Some(align @ ('<' | '^' | '>')), _) => {
+                placeholder.align = align;
+                options = &options[1..];
+            }
+            _ => {}
+        }
+        let (width, precision) = options.split_once('.').unwrap_or((options, ""));
+        let number = |text: &str| {
+            text.parse::<usize>()
+                .map_err(|_| format!("invalid width or precision '{text}'"))
+        };
+        if !width.is_empty() {
+            placeholder.width = number(width)?;
+        }
+        if options.contains('.') {
+            placeholder.precision = Some(number(precision)?);
+        }
+        Ok(placeholder)
+    }
+
+    fn pad(&self, text: String) -> String {
+        let len = text.chars().count();
+        if len >= self.width {
+            return text;
+        }
+        let padding = self.width - len;
+        let (before, after) = match self.align {
+            '<' => (0, padding),
+            '^' => (padding / 2, padding - padding / 2),
+            _ => (padding, 0),
+        };
+        let fill = |n| self.fill.to_string().repeat(n);
+        format!("{}{text}{}", fill(before), fill(after))
+    }
+}
+
+/// Writes a value the way `{}` formats it, or just its type for
+/// values `{}` can't format.
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match value(self, "", None) {
+            Ok(text) => write!(f, "{text}"),
+            Err(_) => write!(f, "{}", self.type_of()),
+        }
+    }
+}
+
+fn value(value: &Value, spec: &str, precision: Option<usize>) -> Result<String, String> {
+    let invalid = || {
+        Err(format!(
+            "invalid format string '{spec}' for type '{}'",
+            value.type_of()
+        ))
+    };
+    if let Some(spec) = spec.strip_prefix('?') {
+        return match value {
+            Value::Null => Ok("null".to_string()),
+            value => self::value(value, spec, precision),
+        };
+    }
+    if let Some(spec) = spec.strip_prefix('!') {
+        return match value {
+            Value::Error(name) => Ok(format!("error.{name}")),
+            value => self::value(value, spec, precision),
+        };
+    }
+    if spec == "s" {
+        return match value.bytes() {
+            Some(bytes) => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+            None => invalid(),
+        };
+    }
+
+    let text = match value {
+        Value::Int(x, _) => match spec {
+            "" | "any" | "d" => x.to_string(),
+            "x" | "X" | "b" | "o" => {
+                let sign = if x.is_negative() { "-" } else { "" };
+                let x = x.magnitude();
+                match spec {
+                    "x" => format!("{sign}{x:x}"),
+                    "X" => format!("{sign}{x:X}"),
+                    "b" => format!("{sign}{x:b}"),
+                    _ => format!("{sign}{x:o}"),
+                }
+            }
+            "c" => match x.to::<u8>() {
+                Some(byte) => (byte as char).to_string(),
+                None => return invalid(),
+            },
+            "u" => match x.to().and_then(char::from_u32) {
+                Some(c) => c.to_string(),
+                None => return Err(format!("invalid codepoint {x}")),
+            },
+            _ => return invalid(),
+        },
+        Value::Float(x, bits) => match spec {
+            "" | "any" | "e" => match precision {
+                _ if !x.is_finite() => float_special(*x),
+                Some(precision) => format!("{x:.precision$e}"),
+                None => format!("{:e}", shortest(*x, *bits)),
+            },
+            "d" => match precision {
+                _ if !x.is_finite() => float_special(*x),
+                Some(precision) => format!("{x:.precision$}"),
+                None => format!("{}", shortest(*x, *bits)),
+            },
+            _ => return invalid(),
+        },
+        Value::Bool(b) if matches!(spec, "" | "any") => b.to_string(),
+        Value::Void if matches!(spec, "" | "any") => "void".to_string(),
+        Value::Null if matches!(spec, "" | "any") => "null".to_string(),
+        Value::Undefined => "undefined".to_string(),
+        Value::EnumLiteral(name) => format!(".{name}"),
+        Value::Error(name) => format!("error.{name}"),
+        Value::Type(ty) => ty.to_string(),
+        Value::Enum(container, index) => {
+            let fields = container.fields();
+            format!("{}.{}", container.name(), fields[*index].name)
+        }
+        Value::Union(container, index, payload) => {
+            let fields = container.fields();
+            let payload = self::value(payload, spec, precision)?;
+            format!(
+                "{}{{ .{} = {payload} }}",
+                container.name(),
+                fields[*index].name
+            )
+        }
+        Value::Struct(Struct { ty, fields }) => {
+            let name = match ty {
+                Some(ty) => ty.name(),
+                None => ".".to_string(),
+            };
+            if fields.is_empty() {
+                return Ok(format!("{name}{{}}"));
+            }
+            let mut parts = Vec::new();
+            for (field, value) in fields {
+                parts.push(format!(
+                    ".{field} = {}",
+                    self::value(value, spec, precision)?
+                ));
+            }
+            format!("{name}{{ {} }}", parts.join(", "))
+        }
+        Value::Array(array) => list(&array.elems, spec, precision)?,
+        Value::Tuple(elems) => list(elems, spec, precision)?,
+        Value::Slice(slice) => list(&slice.elems(), spec, precision)?,
+        // Pointers to arrays and containers are printed as what they point to.
+        Value::Pointer(pointer) => match pointer.place.get() {
+            pointee @ (Value::Array(_) | Value::Struct(_) | Value::Enum(..) | Value::Union(..)) => {
+                self::value(&pointee, spec, precision)?
+            }
+            _ => {
+                let address = Rc::as_ptr(&pointer.place.var) as usize;
+                format!("{}@{address:x}", value.type_of())
+            }
+        },
+        Value::Fn(function) => format!("{}@{}", function.signature(), function.name),
+        Value::NativeFn(_) => value.type_of().to_string(),
+        _ => return invalid(),
+    };
+    Ok(text)
+}
+
+fn list(elems: &[Value], spec: &str, precision: Option<usize>) -> Result<String, String> {
+    let elems = elems
+        .iter()
+        .map(|elem| value(elem, spec, precision))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(format!("{{ {} }}", elems.join(", ")))
+}
+
+/// The shortest decimal that rounds to `x` at the precision of its type,
+/// which is how zig prints floats. As every float is kept in an `f64`,
+/// the shortest one for that can be longer, such as 0.0999755859375 for
+/// the `f16` closest to 0.1.
+fn shortest(x: f64, bits: Option<u16>) -> f64 {
+    if !matches!(bits, Some(16 | 32)) {
+        return x;
+    }
+    (0..17)
+        .map(|precision| format!("{x:.precision$e}").parse::<f64>().unwrap())
+        .find(|&short| ops::round_float(short, bits) == x)
+        .unwrap_or(x)
+}
+
+fn float_special(x: f64) -> String {
+    match x {
+        _ if x.is_nan() => "nan".to_string(),
+        _ if x > 0.0 => "inf".to_string(),
+        _ => "-inf".to_string(),
+    }
+}
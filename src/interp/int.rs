@@ -0,0 +1,241 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    ops::{Neg, Shr},
+};
+
+/// An integer value, of a fixed width type or a `comptime_int`.
+///
+/// It's kept as a sign and a magnitude rather than in an `i128`, so that
+/// every value of both `i128` and `u128` fits. `comptime_int`s are limited
+/// to the same range, which is `-(2^128 - 1)..=2^128 - 1`. Zero is never
+/// negative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Int {
+    negative: bool,
+    magnitude: u128,
+}
+
+impl Int {
+    pub const ZERO: Int = Int::new(false, 0);
+    pub const ONE: Int = Int::new(false, 1);
+
+    pub const fn new(negative: bool, magnitude: u128) -> Self {
+        Self {
+            negative: negative && magnitude != 0,
+            magnitude,
+        }
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.negative
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.magnitude == 0
+    }
+
+    pub fn magnitude(self) -> u128 {
+        self.magnitude
+    }
+
+    /// The number of bits needed for the magnitude.
+    pub fn bits(self) -> u32 {
+        u128::BITS - self.magnitude.leading_zeros()
+    }
+
+    /// Converts to a primitive integer type, if the value fits.
+    pub fn to<T: TryFrom<u128> + TryFrom<i128>>(self) -> Option<T> {
+        match self.negative {
+            false => T::try_from(self.magnitude).ok(),
+            true => 0i128
+                .checked_sub_unsigned(self.magnitude)
+                .and_then(|value| T::try_from(value).ok()),
+        }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        let magnitude = self.magnitude as f64;
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    /// Truncates a float towards zero, if the result is in range.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        let value = value.trunc();
+        // `u128::MAX as f64` rounds up to 2^128, which is out of range.
+        if !value.is_finite() || value.abs() >= u128::MAX as f64 {
+            return None;
+        }
+        Some(Self::new(value < 0.0, value.abs() as u128))
+    }
+
+    /// The lowest 128 bits of the value in two's complement.
+    pub fn to_bits(self) -> u128 {
+        match self.negative {
+            true => self.magnitude.wrapping_neg(),
+            false => self.magnitude,
+        }
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        if self.negative == other.negative {
+            let magnitude = self.magnitude.checked_add(other.magnitude)?;
+            return Some(Self::new(self.negative, magnitude));
+        }
+        // The signs differ, so the result is the difference of the magnitudes.
+        match self.magnitude.cmp(&other.magnitude) {
+            Ordering::Less => Some(Self::new(other.negative, other.magnitude - self.magnitude)),
+            _ => Some(Self::new(self.negative, self.magnitude - other.magnitude)),
+        }
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.checked_add(-other)
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        let magnitude = self.magnitude.checked_mul(other.magnitude)?;
+        Some(Self::new(self.negative != other.negative, magnitude))
+    }
+
+    /// Division rounding towards zero.
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        let magnitude = self.magnitude.checked_div(other.magnitude)?;
+        Some(Self::new(self.negative != other.negative, magnitude))
+    }
+
+    /// The remainder of `checked_div`, which has the sign of `self`.
+    pub fn checked_rem(self, other: Self) -> Option<Self> {
+        let magnitude = self.magnitude.checked_rem(other.magnitude)?;
+        Some(Self::new(self.negative, magnitude))
+    }
+
+    /// Division rounding towards negative infinity.
+    pub fn checked_div_floor(self, other: Self) -> Option<Self> {
+        let quotient = self.checked_div(other)?;
+        match !self.checked_rem(other)?.is_zero() && self.negative != other.negative {
+            true => quotient.checked_sub(Self::ONE),
+            false => Some(quotient),
+        }
+    }
+
+    /// The remainder of `checked_div_floor`, which has the sign of `other`.
+    pub fn checked_mod_floor(self, other: Self) -> Option<Self> {
+        let remainder = self.checked_rem(other)?;
+        match !remainder.is_zero() && self.negative != other.negative {
+            true => remainder.checked_add(other),
+            false => Some(remainder),
+        }
+    }
+
+    /// `self * 2^shift`.
+    pub fn checked_shl(self, shift: u32) -> Option<Self> {
+        if shift >= u128::BITS || self.magnitude.leading_zeros() < shift {
+            return (self.magnitude == 0).then_some(Self::ZERO);
+        }
+        Some(Self::new(self.negative, self.magnitude << shift))
+    }
+
+    /// Applies a bitwise operator as if both values were sign extended
+    /// to infinitely many bits, which is how zig treats `comptime_int`s.
+    pub fn bitwise(self, other: Self, op: impl Fn(u128, u128) -> u128) -> Option<Self> {
+        let sign = |value: Self| if value.negative { u128::MAX } else { 0 };
+        let negative = op(sign(self), sign(other)) != 0;
+        let bits = op(self.to_bits(), other.to_bits());
+        match negative {
+            false => Some(Self::new(false, bits)),
+            // -2^128 is the only result that doesn't fit.
+            true if bits == 0 => None,
+            true => Some(Self::new(true, bits.wrapping_neg())),
+        }
+    }
+}
+
+impl Neg for Int {
+    type Output = Int;
+
+    fn neg(self) -> Int {
+        Int::new(!self.negative, self.magnitude)
+    }
+}
+
+/// `self / 2^shift`, rounding towards negative infinity like
+/// an arithmetic shift does.
+impl Shr<u32> for Int {
+    type Output = Int;
+
+    fn shr(self, shift: u32) -> Int {
+        if shift >= u128::BITS {
+            return Int::new(self.negative, self.negative as u128);
+        }
+        match self.negative {
+            false => Int::new(false, self.magnitude >> shift),
+            true => Int::new(true, ((self.magnitude - 1) >> shift) + 1),
+        }
+    }
+}
+
+impl Ord for Int {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, false) => self.magnitude.cmp(&other.magnitude),
+            (true, true) => other.magnitude.cmp(&self.magnitude),
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+        }
+    }
+}
+
+impl PartialOrd for Int {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+macro_rules! from_unsigned {
+    ($($ty:ty),*) => {$(
+        impl From<$ty> for Int {
+            fn from(value: $ty) -> Self {
+                Int::new(false, value as u128)
+            }
+        }
+    )*};
+}
+
+macro_rules! from_signed {
+    ($($ty:ty),*) => {$(
+        impl From<$ty> for Int {
+            fn from(value: $ty) -> Self {
+                Int::new(value < 0, value.unsigned_abs() as u128)
+            }
+        }
+    )*};
+}
+
+from_unsigned!(u8, u16, u32, u64, u128, usize);
+from_signed!(i8, i16, i32, i64, i128, isize);
+
+impl From<bool> for Int {
+    fn from(value: bool) -> Self {
+        Int::new(false, value as u128)
+    }
+}
+
+impl From<char> for Int {
+    fn from(value: char) -> Self {
+        Int::new(false, value as u128)
+    }
+}
+
+impl fmt::Display for Int {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", self.magnitude)
+    }
+}
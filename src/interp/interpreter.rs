@@ -0,0 +1,935 @@
+use std::rc::Rc;
+
+use crate::{
+    ast::{
+        Ast, BinaryOp, Block, Capture, Decl, DeclKind, Expr, ExprKind, For, If, ParamType, Stmt,
+        StmtKind, Switch, VarDecl, While,
+    },
+    tokenizer::{PrimitiveType, Span},
+};
+
+use super::{
+    comptime::Instance,
+    error::{EvalError, EvalErrorKind, Frame},
+    int::Int,
+    ops,
+    scope::{Lazy, Scope},
+    value::{Container, Function, IntType, Place, Pointer, Step, Type, Value, Variable},
+};
+
+/// How deep calls can nest before it's called a stack overflow.
+const MAX_CALL_DEPTH: usize = 1000;
+
+/// The stack size a thread running the interpreter needs, as every call
+/// in the program being run takes a few nested calls of the interpreter,
+/// and more still in a debug build. The main thread's stack is only
+/// enough for a few hundred calls.
+pub const STACK_SIZE: usize = 256 << 20;
+
+/// How evaluation left an expression, when it didn't just produce a value.
+pub(super) enum Flow<'src> {
+    Break(Option<String>, Box<Value<'src>>, Span),
+    Continue(Option<String>, Span),
+    Return(Box<Value<'src>>, Span),
+    Error(Box<EvalError>),
+}
+
+pub(super) type Exec<'src, T> = Result<T, Flow<'src>>;
+
+/// A tree-walking interpreter for zig programs.
+///
+/// ```
+/// use rusty_zig::{interp::Interpreter, parser};
+///
+/// let src = r#"
+/// const std = @import("std");
+///
+/// fn fib(n: u32) u32 {
+///     return if (n < 2) n else fib(n - 1) + fib(n - 2);
+/// }
+///
+/// pub fn main() void {
+///     std.debug.print("{d}\n", .{fib(10)});
+/// }
+/// "#;
+/// let ast = parser::parse(src);
+/// let mut interpreter = Interpreter::new().capture_output(true);
+/// interpreter.run(&ast).unwrap();
+/// assert_eq!(interpreter.output(), "55\n");
+/// ```
+///
+/// Programs run with the safety checks of a debug build, so integer
+/// overflow, out of bounds indexing and the like stop the program with
+/// an `EvalError`. Declarations are kept across calls to `load`, which
+/// makes it usable for a REPL.
+pub struct Interpreter<'src> {
+    pub(super) root: Rc<Scope<'src>>,
+    pub(super) scope: Rc<Scope<'src>>,
+    pub(super) frames: Vec<Frame>,
+    /// The return types of the functions being called, innermost last.
+    pub(super) return_types: Vec<Type<'src>>,
+    /// What `std.debug.print` wrote, when it isn't written to stderr.
+    pub(super) output: Option<String>,
+    /// How many `comptime` expressions, blocks and calls are being
+    /// evaluated, where runtime variables can't be read.
+    pub(super) comptime: usize,
+    /// How many times runtime variables have been read, which tells
+    /// whether a `const` is comptime-known.
+    pub(super) runtime_reads: usize,
+    /// The types returned by generic functions so far.
+    pub(super) instances: Vec<Instance<'src>>,
+    /// The `std.builtin.Type` union that `@typeInfo` returns.
+    pub(super) type_info: Option<Rc<Container<'src>>>,
+}
+
+impl Default for Interpreter<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'src> Interpreter<'src> {
+    pub fn new() -> Self {
+        let root = Scope::root();
+        Self {
+            scope: root.clone(),
+            root,
+            frames: Vec::new(),
+            return_types: Vec::new(),
+            output: None,
+            comptime: 0,
+            runtime_reads: 0,
+            instances: Vec::new(),
+            type_info: None,
+        }
+    }
+
+    /// Keeps what `std.debug.print` writes instead of writing it to stderr.
+    pub fn capture_output(mut self, capture: bool) -> Self {
+        self.output = capture.then(String::new);
+        self
+    }
+
+    /// The output captured so far.
+    pub fn output(&self) -> &str {
+        self.output.as_deref().unwrap_or_default()
+    }
+
+    pub fn take_output(&mut self) -> String {
+        self.output.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// Adds the declarations of a file to the interpreter, replacing any
+    /// loaded before with the same names, and runs its `comptime` blocks.
+    pub fn load(&mut self, ast: &Ast<'src>) -> Result<(), EvalError> {
+        for decl in &ast.decls {
+            if let Some(name) = decl_name(decl) {
+                self.root.remove(name);
+            }
+        }
+        let root = self.root.clone();
+        let result = self
+            .declare_members(&root, &ast.decls)
+            .and_then(|comptime| {
+                for block in comptime {
+                    self.comptime(|this| this.block(block))?;
+                }
+                Ok(())
+            });
+        self.finish(result)
+    }
+
+    /// Loads a file and calls its `main` function.
+    pub fn run(&mut self, ast: &Ast<'src>) -> Result<(), EvalError> {
+        self.load(ast)?;
+        self.call_main()
+    }
+
+    pub fn call_main(&mut self) -> Result<(), EvalError> {
+        let span = Span::new(0, 0);
+        let result = match self.root.contains("main") {
+            false => Err(self.error(span, EvalErrorKind::NoMain)),
+            true => self
+                .lookup("main", span)
+                .and_then(|main| match main.value() {
+                    Value::Fn(main) => self.call(&main, None, &[], span),
+                    value => Err(self.error(
+                        span,
+                        EvalErrorKind::NotCallable(value.type_of().to_string()),
+                    )),
+                }),
+        };
+        match self.finish(result)? {
+            Value::Error(name) => {
+                let span = self.main_span();
+                Err(EvalError::new(span, EvalErrorKind::ErrorReturned(name)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Evaluates an expression with the declarations loaded so far in scope.
+    pub fn eval_expr(&mut self, expr: &Expr<'src>) -> Result<Value<'src>, EvalError> {
+        let result = self.eval(expr);
+        self.finish(result)
+    }
+
+    /// Runs a statement as if it were in a block around everything loaded
+    /// so far, so that the variables it declares stay around. Returns the
+    /// value of expression statements.
+    pub fn exec_stmt(&mut self, stmt: &Stmt<'src>) -> Result<Value<'src>, EvalError> {
+        let result = self.stmt(stmt);
+        self.finish(result)
+    }
+
+    /// The names declared at the top level.
+    pub fn names(&self) -> Vec<String> {
+        self.root.names()
+    }
+
+    /// Turns a way of leaving evaluation into the result of it, where
+    /// only values and errors can make it out.
+    fn finish<T>(&mut self, result: Exec<'src, T>) -> Result<T, EvalError> {
+        self.scope = self.root.clone();
+        self.frames.clear();
+        self.return_types.clear();
+        result.map_err(|flow| match flow {
+            Flow::Error(error) => *error,
+            Flow::Break(_, _, span) => EvalError::new(span, EvalErrorKind::BreakOutsideLoop),
+            Flow::Continue(_, span) => EvalError::new(span, EvalErrorKind::ContinueOutsideLoop),
+            Flow::Return(_, span) => EvalError::new(span, EvalErrorKind::ReturnOutsideFn),
+        })
+    }
+
+    fn main_span(&self) -> Span {
+        match self.root.get("main").map(|main| main.value()) {
+            Some(Value::Fn(main)) => main
+                .decl
+                .proto
+                .name
+                .as_ref()
+                .map_or(main.decl.proto.span, |name| name.span),
+            _ => Span::new(0, 0),
+        }
+    }
+
+    pub(super) fn error(&self, span: Span, kind: EvalErrorKind) -> Flow<'src> {
+        Flow::Error(Box::new(EvalError {
+            span,
+            kind,
+            trace: self.frames.iter().rev().cloned().collect(),
+        }))
+    }
+
+    pub(super) fn fail<T>(&self, span: Span, kind: EvalErrorKind) -> Exec<'src, T> {
+        Err(self.error(span, kind))
+    }
+
+    /// Attaches a span to the result of one of the operations in `ops`.
+    pub(super) fn at<T>(&self, span: Span, result: Result<T, EvalErrorKind>) -> Exec<'src, T> {
+        result.map_err(|kind| self.error(span, kind))
+    }
+
+    /// Runs `f` with `scope` as the current scope.
+    pub(super) fn in_scope<T>(
+        &mut self,
+        scope: Rc<Scope<'src>>,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let outer = std::mem::replace(&mut self.scope, scope);
+        let result = f(self);
+        self.scope = outer;
+        result
+    }
+
+    /// Adds the members of a file or container to `scope`. Functions are
+    /// added right away, but variables are only evaluated once they're
+    /// used. Returns the `comptime` blocks, which have to run after.
+    pub(super) fn declare_members<'a>(
+        &mut self,
+        scope: &Rc<Scope<'src>>,
+        decls: &'a [Decl<'src>],
+    ) -> Exec<'src, Vec<&'a Block<'src>>> {
+        let mut comptime = Vec::new();
+        for decl in decls {
+            let (name, declared) = match &decl.kind {
+                DeclKind::Var(var) => {
+                    let declared = scope.declare_lazy(&var.name.name, Rc::new(var.clone()));
+                    (&var.name, declared)
+                }
+                DeclKind::Fn(function) => {
+                    let Some(name) = &function.proto.name else {
+                        continue;
+                    };
+                    let function = Rc::new(Function {
+                        name: name.name.to_string(),
+                        decl: Rc::new(function.clone()),
+                        scope: scope.clone(),
+                    });
+                    let ty = Type::Fn(function.clone());
+                    let var = Variable::new(Value::Fn(function), ty, true);
+                    (name, scope.declare(&name.name, var))
+                }
+                DeclKind::Comptime(block) => {
+                    comptime.push(block);
+                    continue;
+                }
+                DeclKind::UsingNamespace(_) => {
+                    let kind = EvalErrorKind::Unsupported("usingnamespace".to_string());
+                    return self.fail(decl.span, kind);
+                }
+                DeclKind::Test { .. } | DeclKind::Field(_) | DeclKind::Error => continue,
+            };
+            if !declared {
+                return self.fail(name.span, EvalErrorKind::Redeclared(name.name.to_string()));
+            }
+        }
+        Ok(comptime)
+    }
+
+    /// Finds the variable a name refers to, evaluating it first if it's
+    /// a container level declaration that hasn't been used yet.
+    pub(super) fn lookup(&mut self, name: &str, span: Span) -> Exec<'src, Rc<Variable<'src>>> {
+        let mut scope = Some(self.scope.clone());
+        while let Some(current) = scope {
+            if let Some(var) = self.member(&current, name, span)? {
+                return Ok(var);
+            }
+            scope = current.parent().cloned();
+        }
+        self.fail(span, EvalErrorKind::Undeclared(name.to_string()))
+    }
+
+    /// Like `lookup`, but only looks in `scope` itself.
+    pub(super) fn member(
+        &mut self,
+        scope: &Rc<Scope<'src>>,
+        name: &str,
+        span: Span,
+    ) -> Exec<'src, Option<Rc<Variable<'src>>>> {
+        if let Some(var) = scope.get(name) {
+            self.read(&var, name, span)?;
+            return Ok(Some(var));
+        }
+        let decl = match scope.get_lazy(name) {
+            None => return Ok(None),
+            Some(Lazy::InProgress) => {
+                return self.fail(span, EvalErrorKind::DependencyLoop(name.to_string()))
+            }
+            Some(Lazy::Pending(decl)) => decl,
+        };
+        scope.start_lazy(name);
+        match self.in_scope(scope.clone(), |this| this.var_decl(&decl, true)) {
+            Ok(var) => {
+                scope.finish_lazy(name, var.clone());
+                self.read(&var, name, span)?;
+                Ok(Some(var))
+            }
+            Err(flow) => {
+                scope.cancel_lazy(name, decl);
+                Err(flow)
+            }
+        }
+    }
+
+    /// Evaluates a `var` or `const` into the variable it declares.
+    /// Container level declarations are evaluated at comptime, like
+    /// `comptime var`s, but their `var`s can only be used at runtime.
+    pub(super) fn var_decl(
+        &mut self,
+        decl: &VarDecl<'src>,
+        container_level: bool,
+    ) -> Exec<'src, Rc<Variable<'src>>> {
+        let reads = self.runtime_reads;
+        let (ty, value) = match container_level || decl.is_comptime {
+            true => self.comptime(|this| this.initializer(decl))?,
+            false => self.initializer(decl)?,
+        };
+        let ty = ty.unwrap_or_else(|| value.type_of());
+        if !decl.is_const && !decl.is_comptime && ty.is_comptime_only() {
+            return self.fail(decl.name.span, EvalErrorKind::ComptimeVar(ty.to_string()));
+        }
+        // Containers are named after the first declaration they're bound to.
+        if let Value::Type(Type::Container(container)) = &value {
+            container
+                .name
+                .borrow_mut()
+                .get_or_insert_with(|| decl.name.name.to_string());
+        }
+        // A `const` is comptime-known when its value didn't depend on anything
+        // that's only known at runtime.
+        let is_comptime = match container_level {
+            true => decl.is_const,
+            false => {
+                decl.is_comptime
+                    || self.comptime > 0
+                    || (decl.is_const && self.runtime_reads == reads)
+            }
+        };
+        match is_comptime {
+            true => Ok(Variable::new(value, ty, decl.is_const)),
+            false => Ok(Variable::runtime(value, ty, decl.is_const)),
+        }
+    }
+
+    /// The type and value of a `var` or `const`.
+    fn initializer(
+        &mut self,
+        decl: &VarDecl<'src>,
+    ) -> Exec<'src, (Option<Type<'src>>, Value<'src>)> {
+        let ty = match &decl.ty {
+            Some(ty) => Some(self.eval_type(ty)?),
+            None => None,
+        };
+        let value = match (&decl.value, &ty) {
+            (Some(value), Some(ty)) => self.eval_as(value, ty)?,
+            (Some(value), None) => self.eval(value)?,
+            (None, _) => {
+                let kind = EvalErrorKind::Unsupported("extern variables".to_string());
+                return self.fail(decl.span, kind);
+            }
+        };
+        Ok((ty, value))
+    }
+
+    pub(super) fn block(&mut self, block: &Block<'src>) -> Exec<'src, Value<'src>> {
+        let scope = Scope::child(&self.scope);
+        let result = self.in_scope(scope, |this| this.stmts(&block.stmts));
+        match (result, &block.label) {
+            (Err(Flow::Break(Some(label), value, _)), Some(own)) if label == own.name => Ok(*value),
+            (result, _) => result,
+        }
+    }
+
+    /// Runs the statements of a block, and then whatever they deferred.
+    fn stmts(&mut self, stmts: &[Stmt<'src>]) -> Exec<'src, Value<'src>> {
+        let mut deferred = Vec::new();
+        let mut result = Ok(Value::Void);
+        for stmt in stmts {
+            match &stmt.kind {
+                StmtKind::Defer(_) | StmtKind::Errdefer { .. } => deferred.push(stmt),
+                _ => {
+                    if let Err(flow) = self.stmt(stmt) {
+                        result = Err(flow);
+                        break;
+                    }
+                }
+            }
+        }
+        // Like in a zig program, a panic doesn't run any defers.
+        if let Err(Flow::Error(_)) = result {
+            return result;
+        }
+        let error = match &result {
+            Err(Flow::Return(value, _)) => match &**value {
+                Value::Error(name) => Some(name.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        for stmt in deferred.into_iter().rev() {
+            match &stmt.kind {
+                StmtKind::Defer(body) => {
+                    self.eval(body)?;
+                }
+                StmtKind::Errdefer { capture, body } => {
+                    if let Some(name) = &error {
+                        let capture = capture.as_ref().map(|c| (c, Value::Error(name.clone())));
+                        self.branch(body, capture, None)?;
+                    }
+                }
+                _ => unreachable!("only defers are deferred"),
+            }
+        }
+        result
+    }
+
+    pub(super) fn stmt(&mut self, stmt: &Stmt<'src>) -> Exec<'src, Value<'src>> {
+        match &stmt.kind {
+            StmtKind::Var(decl) => {
+                let var = self.var_decl(decl, false)?;
+                if !self.scope.declare(&decl.name.name, var) {
+                    let name = decl.name.name.to_string();
+                    return self.fail(decl.name.span, EvalErrorKind::Redeclared(name));
+                }
+                Ok(Value::Void)
+            }
+            StmtKind::Expr(expr) => self.eval(expr),
+            // Outside of a block there is nothing to defer to.
+            StmtKind::Defer(_) | StmtKind::Errdefer { .. } => Ok(Value::Void),
+        }
+    }
+
+    /// Calls a function. `receiver` is the value a method is called on,
+    /// which is passed before `args`.
+    pub(super) fn call(
+        &mut self,
+        function: &Rc<Function<'src>>,
+        receiver: Option<Value<'src>>,
+        args: &[Expr<'src>],
+        span: Span,
+    ) -> Exec<'src, Value<'src>> {
+        let proto = &function.decl.proto;
+        let Some(body) = &function.decl.body else {
+            let kind = EvalErrorKind::Unsupported("calling extern functions".to_string());
+            return self.fail(span, kind);
+        };
+        if proto.params.iter().any(|p| p.ty == ParamType::VarArgs) {
+            let kind = EvalErrorKind::Unsupported("variadic functions".to_string());
+            return self.fail(span, kind);
+        }
+        let found = args.len() + receiver.is_some() as usize;
+        if proto.params.len() != found {
+            let expected = proto.params.len();
+            return self.fail(span, EvalErrorKind::ArgCount { expected, found });
+        }
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return self.fail(span, EvalErrorKind::StackOverflow);
+        }
+        // Functions that return types run at comptime, and only once for
+        // each set of arguments, so that `List(i32) == List(i32)`.
+        let generic = matches!(
+            proto.return_type.kind,
+            ExprKind::PrimitiveType(PrimitiveType::Type)
+        );
+        let (callee, ret, values) = match generic {
+            true => self.comptime(|this| this.bind_params(function, receiver, args, span))?,
+            false => self.bind_params(function, receiver, args, span)?,
+        };
+        if generic {
+            if let Some(ty) = self.instance(function, &values) {
+                return Ok(ty.into());
+            }
+        }
+        // What a runtime call returns is only known at runtime.
+        if self.comptime == 0 && !ret.is_comptime_only() {
+            self.runtime_reads += 1;
+        }
+
+        self.frames.push(Frame {
+            name: function.name.clone(),
+            span,
+            comptime: generic || self.comptime > 0,
+        });
+        self.return_types.push(ret.clone());
+        let result = self.in_scope(callee, |this| match generic {
+            true => this.comptime(|this| this.block(body)),
+            false => this.block(body),
+        });
+        self.return_types.pop();
+        self.frames.pop();
+        let value = match result {
+            Ok(_) => Value::Void,
+            Err(Flow::Return(value, _)) => *value,
+            Err(Flow::Break(_, _, span)) => {
+                return self.fail(span, EvalErrorKind::BreakOutsideLoop)
+            }
+            Err(Flow::Continue(_, span)) => {
+                return self.fail(span, EvalErrorKind::ContinueOutsideLoop)
+            }
+            Err(flow) => return Err(flow),
+        };
+        let value = self.coerce(value, &ret, span)?;
+        if let (true, Value::Type(ty)) = (generic, &value) {
+            self.add_instance(function, values, ty.clone());
+        }
+        Ok(value)
+    }
+
+    /// Makes the scope a function's body runs in, with the arguments
+    /// bound to the parameters, and works out the return type. Also
+    /// returns the arguments.
+    fn bind_params(
+        &mut self,
+        function: &Rc<Function<'src>>,
+        receiver: Option<Value<'src>>,
+        args: &[Expr<'src>],
+        span: Span,
+    ) -> Exec<'src, (Rc<Scope<'src>>, Type<'src>, Vec<Value<'src>>)> {
+        let proto = &function.decl.proto;
+        // Parameter types can refer to the parameters before them, as in
+        // `fn max(comptime T: type, a: T, b: T) T`, so they're evaluated
+        // in the scope of the call as the arguments are bound.
+        let callee = Scope::child(&function.scope);
+        let mut receiver = receiver;
+        let mut args = args.iter();
+        let mut values = Vec::new();
+        for param in &proto.params {
+            let ty = match &param.ty {
+                ParamType::Expr(ty) => {
+                    Some(self.in_scope(callee.clone(), |this| this.eval_type(ty))?)
+                }
+                _ => None,
+            };
+            let value = match (receiver.take(), &ty) {
+                (Some(value), Some(ty)) => self.coerce(value, ty, span)?,
+                (Some(value), None) => value,
+                (None, ty) => {
+                    let arg = args.next().expect("the arguments were counted");
+                    let eval = |this: &mut Self| match ty {
+                        Some(ty) => this.eval_as(arg, ty),
+                        None => this.eval(arg),
+                    };
+                    match param.is_comptime {
+                        true => self.comptime(eval)?,
+                        false => eval(self)?,
+                    }
+                }
+            };
+            values.push(value.clone());
+            let ty = ty.unwrap_or_else(|| value.type_of());
+            if let Some(name) = param.name.as_ref().filter(|name| name.name != "_") {
+                let var = match param.is_comptime || self.comptime > 0 {
+                    true => Variable::new(value, ty, true),
+                    false => Variable::runtime(value, ty, true),
+                };
+                callee.declare(&name.name, var);
+            }
+        }
+        let ret = self.in_scope(callee.clone(), |this| this.eval_type(&proto.return_type))?;
+        let ret = match proto.inferred_error {
+            true => Type::ErrorUnion {
+                error: Box::new(Type::ErrorSet(None)),
+                payload: Box::new(ret),
+            },
+            false => ret,
+        };
+        Ok((callee, ret, values))
+    }
+
+    /// Evaluates the body of an `if`, a loop or a `catch`, with the
+    /// payload captured by `|x|` in scope.
+    pub(super) fn branch(
+        &mut self,
+        body: &Expr<'src>,
+        capture: Option<(&Capture<'src>, Value<'src>)>,
+        ty: Option<&Type<'src>>,
+    ) -> Exec<'src, Value<'src>> {
+        let scope = Scope::child(&self.scope);
+        if let Some((capture, value)) = capture {
+            self.bind(&scope, capture, value);
+        }
+        self.in_scope(scope, |this| match ty {
+            Some(ty) => this.eval_as(body, ty),
+            None => this.eval(body),
+        })
+    }
+
+    fn bind(&mut self, scope: &Rc<Scope<'src>>, capture: &Capture<'src>, value: Value<'src>) {
+        if capture.name.name != "_" {
+            let ty = value.type_of();
+            scope.declare(&capture.name.name, Variable::new(value, ty, true));
+        }
+    }
+
+    /// The value captured by `|x|` or `|*x|` for a payload at `place`.
+    fn capture(
+        &mut self,
+        capture: &Capture<'src>,
+        value: Value<'src>,
+        place: impl FnOnce(&mut Self) -> Exec<'src, Option<Place<'src>>>,
+    ) -> Exec<'src, Value<'src>> {
+        if !capture.is_pointer {
+            return Ok(value);
+        }
+        match place(self)? {
+            Some(place) => {
+                let child = self.place_type(&place);
+                Ok(Value::Pointer(Box::new(Pointer { place, child })))
+            }
+            None => self.fail(capture.span, EvalErrorKind::NotAssignable),
+        }
+    }
+
+    pub(super) fn if_expr(
+        &mut self,
+        expr: &If<'src>,
+        ty: Option<&Type<'src>>,
+    ) -> Exec<'src, Value<'src>> {
+        let cond = self.eval(&expr.cond)?;
+        let is_optional = expr.capture.is_some() || expr.else_capture.is_some();
+        let then = match (&cond, is_optional) {
+            (Value::Bool(cond), false) => *cond,
+            (Value::Error(_), true) | (Value::Null, true) => false,
+            (Value::Undefined, _) => {
+                return self.fail(expr.cond.span, EvalErrorKind::UndefinedValue)
+            }
+            (_, true) => true,
+            (_, false) => {
+                let found = cond.type_of().to_string();
+                let kind = EvalErrorKind::TypeMismatch {
+                    expected: "bool".to_string(),
+                    found,
+                };
+                return self.fail(expr.cond.span, kind);
+            }
+        };
+        if then {
+            let capture = match &expr.capture {
+                Some(capture) => {
+                    let value = self.capture(capture, cond, |this| this.place_of(&expr.cond))?;
+                    Some((capture, value))
+                }
+                None => None,
+            };
+            return self.branch(&expr.then, capture, ty);
+        }
+        match &expr.else_branch {
+            Some(else_branch) => {
+                let capture = expr.else_capture.as_ref().map(|capture| (capture, cond));
+                self.branch(else_branch, capture, ty)
+            }
+            None => Ok(Value::Void),
+        }
+    }
+
+    pub(super) fn while_loop(
+        &mut self,
+        expr: &While<'src>,
+        ty: Option<&Type<'src>>,
+    ) -> Exec<'src, Value<'src>> {
+        let label = expr.label.as_ref().map(|label| label.name.as_ref());
+        let is_optional = expr.capture.is_some() || expr.else_capture.is_some();
+        loop {
+            let cond = self.eval(&expr.cond)?;
+            let go_on = match (&cond, is_optional) {
+                (Value::Bool(cond), false) => *cond,
+                (Value::Error(_), true) | (Value::Null, true) => false,
+                (_, true) => true,
+                (_, false) => {
+                    let kind = EvalErrorKind::TypeMismatch {
+                        expected: "bool".to_string(),
+                        found: cond.type_of().to_string(),
+                    };
+                    return self.fail(expr.cond.span, kind);
+                }
+            };
+            if !go_on {
+                return match &expr.else_branch {
+                    Some(else_branch) => {
+                        let capture = expr.else_capture.as_ref().map(|capture| (capture, cond));
+                        self.branch(else_branch, capture, ty)
+                    }
+                    None => Ok(Value::Void),
+                };
+            }
+
+            let scope = Scope::child(&self.scope);
+            if let Some(capture) = &expr.capture {
+                let value = self.capture(capture, cond, |this| this.place_of(&expr.cond))?;
+                self.bind(&scope, capture, value);
+            }
+            let result = self.in_scope(scope.clone(), |this| this.eval(&expr.body));
+            match result {
+                Ok(_) => {}
+                Err(Flow::Break(target, value, _)) if targets(&target, label) => return Ok(*value),
+                Err(Flow::Continue(target, _)) if targets(&target, label) => {}
+                Err(flow) => return Err(flow),
+            }
+            if let Some(cont) = &expr.cont {
+                self.in_scope(scope, |this| this.eval(cont))?;
+            }
+        }
+    }
+
+    pub(super) fn for_loop(
+        &mut self,
+        expr: &For<'src>,
+        ty: Option<&Type<'src>>,
+    ) -> Exec<'src, Value<'src>> {
+        let label = expr.label.as_ref().map(|label| label.name.as_ref());
+        let mut inputs = Vec::new();
+        let mut len = None;
+        for input in &expr.inputs {
+            let input = self.for_input(input)?;
+            let input_len = match &input {
+                ForInput::Range(start, Some(end)) => Some(end.saturating_sub(*start)),
+                ForInput::Range(_, None) => None,
+                ForInput::Elems { len, .. } => Some(*len),
+            };
+            match (len, input_len) {
+                (Some(len), Some(input_len)) if len != input_len => {
+                    let message = "for loop over objects with non-equal lengths".to_string();
+                    return self.fail(expr.inputs[0].span, EvalErrorKind::Panic(message));
+                }
+                (None, input_len) => len = input_len,
+                _ => {}
+            }
+            inputs.push(input);
+        }
+        let Some(len) = len else {
+            let span = expr
+                .inputs
+                .first()
+                .map_or(expr.body.span, |input| input.span);
+            return self.fail(span, EvalErrorKind::UnboundedFor);
+        };
+
+        for i in 0..len {
+            let scope = Scope::child(&self.scope);
+            for (input, capture) in inputs.iter().zip(&expr.captures) {
+                let value = match input {
+                    ForInput::Range(start, _) => {
+                        Value::Int(Int::from(start + i), Some(IntType::USIZE))
+                    }
+                    ForInput::Elems { place, start, .. } => {
+                        let place = place.child(Step::Index(start + i));
+                        match capture.is_pointer {
+                            true => {
+                                let child = self.place_type(&place);
+                                Value::Pointer(Box::new(Pointer { place, child }))
+                            }
+                            false => place.get(),
+                        }
+                    }
+                };
+                self.bind(&scope, capture, value);
+            }
+            match self.in_scope(scope, |this| this.eval(&expr.body)) {
+                Ok(_) => {}
+                Err(Flow::Break(target, value, _)) if targets(&target, label) => return Ok(*value),
+                Err(Flow::Continue(target, _)) if targets(&target, label) => {}
+                Err(flow) => return Err(flow),
+            }
+        }
+        match &expr.else_branch {
+            Some(else_branch) => self.branch(else_branch, None, ty),
+            None => Ok(Value::Void),
+        }
+    }
+
+    /// Works out what a `for` loop input iterates over.
+    fn for_input(&mut self, input: &Expr<'src>) -> Exec<'src, ForInput<'src>> {
+        if let ExprKind::Range { start, end } = &input.kind {
+            let start = self.eval_index(start)?;
+            let end = match end {
+                Some(end) => Some(self.eval_index(end)?),
+                None => None,
+            };
+            return Ok(ForInput::Range(start, end));
+        }
+        let place = match self.place_of(input)? {
+            Some(place) => place,
+            None => {
+                let value = self.eval(input)?;
+                let ty = value.type_of();
+                Place::new(Variable::new(value, ty, true))
+            }
+        };
+        let elems = place.with(|value| match value {
+            Value::Array(array) => Ok((place.clone(), 0, array.elems.len())),
+            Value::Pointer(pointer) => pointer.place.with(|value| match value {
+                Value::Array(array) => Ok((pointer.place.clone(), 0, array.elems.len())),
+                value => Err(value.type_of()),
+            }),
+            Value::Slice(slice) => Ok((slice.place.clone(), slice.start, slice.len)),
+            value => Err(value.type_of()),
+        });
+        match elems {
+            Ok((place, start, len)) => Ok(ForInput::Elems { place, start, len }),
+            Err(ty) => self.fail(input.span, EvalErrorKind::NotIterable(ty.to_string())),
+        }
+    }
+
+    pub(super) fn switch(
+        &mut self,
+        expr: &Switch<'src>,
+        ty: Option<&Type<'src>>,
+    ) -> Exec<'src, Value<'src>> {
+        let cond = self.eval(&expr.cond)?;
+        let cond_type = cond.type_of();
+        let mut chosen = None;
+        'prongs: for prong in &expr.prongs {
+            if prong.is_else {
+                chosen = chosen.or(Some(prong));
+                continue;
+            }
+            for item in &prong.items {
+                let matched = match &item.kind {
+                    ExprKind::Range {
+                        start,
+                        end: Some(end),
+                    } => {
+                        let start = self.eval(start)?;
+                        let end = self.eval(end)?;
+                        let after_start = ops::binary(BinaryOp::GreaterEqual, &cond, &start);
+                        let before_end = ops::binary(BinaryOp::LessEqual, &cond, &end);
+                        let after_start = self.at(item.span, after_start)?;
+                        let before_end = self.at(item.span, before_end)?;
+                        matches!(
+                            (after_start, before_end),
+                            (Value::Bool(true), Value::Bool(true))
+                        )
+                    }
+                    _ => {
+                        let value = match &cond {
+                            Value::Enum(..) => self.eval_as(item, &cond_type)?,
+                            _ => self.eval(item)?,
+                        };
+                        self.at(item.span, ops::equal(&cond, &value))?
+                    }
+                };
+                if matched {
+                    chosen = Some(prong);
+                    break 'prongs;
+                }
+            }
+        }
+        let Some(prong) = chosen else {
+            return self.fail(expr.cond.span, EvalErrorKind::UnhandledSwitchValue);
+        };
+
+        let scope = Scope::child(&self.scope);
+        if let Some(capture) = prong.captures.first() {
+            let (value, is_union) = match &cond {
+                Value::Union(_, _, payload) => ((**payload).clone(), true),
+                value => (value.clone(), false),
+            };
+            let value = self.capture(capture, value, |this| {
+                let place = this.place_of(&expr.cond)?;
+                Ok(place.map(|place| match is_union {
+                    true => place.child(Step::Payload),
+                    false => place,
+                }))
+            })?;
+            self.bind(&scope, capture, value);
+        }
+        if let (Some(capture), Value::Union(container, index, _)) = (prong.captures.get(1), &cond) {
+            let tag = container.fields()[*index].name.clone();
+            self.bind(&scope, capture, Value::EnumLiteral(tag));
+        }
+        self.in_scope(scope, |this| match ty {
+            Some(ty) => this.eval_as(&prong.body, ty),
+            None => this.eval(&prong.body),
+        })
+    }
+}
+
+/// What a `for` loop iterates over.
+enum ForInput<'src> {
+    /// `start..end`, where the end is optional.
+    Range(usize, Option<usize>),
+    /// The elements of the array at `place`, starting at `start`.
+    Elems {
+        place: Place<'src>,
+        start: usize,
+        len: usize,
+    },
+}
+
+/// Whether a `break` or `continue` with the label `target` is meant for
+/// a loop labeled `label`. Unlabeled ones are for the innermost loop.
+fn targets(target: &Option<String>, label: Option<&str>) -> bool {
+    match target {
+        None => true,
+        Some(target) => Some(target.as_str()) == label,
+    }
+}
+
+fn decl_name<'a>(decl: &'a Decl) -> Option<&'a str> {
+    match &decl.kind {
+        DeclKind::Var(var) => Some(&var.name.name),
+        DeclKind::Fn(function) => function.proto.name.as_ref().map(|name| name.name.as_ref()),
+        _ => None,
+    }
+}
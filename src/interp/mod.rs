@@ -0,0 +1,16 @@
+mod builtins;
+mod coerce;
+mod comptime;
+mod error;
+mod eval;
+mod format;
+mod int;
+mod interpreter;
+mod ops;
+mod scope;
+mod value;
+
+pub use error::*;
+pub use int::*;
+pub use interpreter::*;
+pub use value::*;
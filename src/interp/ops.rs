@@ -0,0 +1,441 @@
+use std::cmp::Ordering;
+
+use crate::ast::BinaryOp;
+
+use super::{
+    error::EvalErrorKind,
+    int::Int,
+    value::{IntType, Type, Value},
+};
+
+type Result<T> = std::result::Result<T, EvalErrorKind>;
+
+pub(super) fn symbol(op: BinaryOp) -> &'static str {
+    use BinaryOp::*;
+    match op {
+        Or => "or",
+        And => "and",
+        Equal => "==",
+        NotEqual => "!=",
+        Less => "<",
+        Greater => ">",
+        LessEqual => "<=",
+        GreaterEqual => ">=",
+        BitAnd => "&",
+        BitXor => "^",
+        BitOr => "|",
+        OrElse => "orelse",
+        Shl => "<<",
+        Shr => ">>",
+        ShlSat => "<<|",
+        Add => "+",
+        Sub => "-",
+        Concat => "++",
+        AddWrap => "+%",
+        SubWrap => "-%",
+        AddSat => "+|",
+        SubSat => "-|",
+        Mul => "*",
+        Div => "/",
+        Mod => "%",
+        Repeat => "**",
+        MulWrap => "*%",
+        MulSat => "*|",
+        MergeErrorSets => "||",
+    }
+}
+
+fn is_comparison(op: BinaryOp) -> bool {
+    use BinaryOp::*;
+    matches!(
+        op,
+        Equal | NotEqual | Less | Greater | LessEqual | GreaterEqual
+    )
+}
+
+fn compare<'src>(op: BinaryOp, ordering: Option<Ordering>) -> Value<'src> {
+    use BinaryOp::*;
+    let result = match ordering {
+        Some(ordering) => match op {
+            Equal => ordering.is_eq(),
+            NotEqual => ordering.is_ne(),
+            Less => ordering.is_lt(),
+            Greater => ordering.is_gt(),
+            LessEqual => ordering.is_le(),
+            GreaterEqual => ordering.is_ge(),
+            _ => unreachable!("only called for comparisons"),
+        },
+        // NaN compares unequal to everything.
+        None => op == NotEqual,
+    };
+    Value::Bool(result)
+}
+
+fn incompatible(lhs: &Value, rhs: &Value) -> EvalErrorKind {
+    EvalErrorKind::IncompatibleTypes {
+        lhs: lhs.type_of().to_string(),
+        rhs: rhs.type_of().to_string(),
+    }
+}
+
+fn too_large(value: Int, ty: IntType) -> EvalErrorKind {
+    EvalErrorKind::IntegerTooLarge {
+        value: value.to_string(),
+        ty: ty.to_string(),
+    }
+}
+
+/// Checks that `value` fits in `ty`, for a comptime_int coerced to it.
+pub(super) fn fit(value: Int, ty: IntType) -> Result<Int> {
+    match ty.contains(value) {
+        true => Ok(value),
+        false => Err(too_large(value, ty)),
+    }
+}
+
+/// Peer type resolution for two integers: comptime_ints take the type of
+/// the other operand, and of two integer types the one that can hold
+/// every value of the other wins.
+fn peer_int(x: Int, a: Option<IntType>, y: Int, b: Option<IntType>) -> Result<Option<IntType>> {
+    let holds =
+        |wide: IntType, narrow: IntType| wide.min() <= narrow.min() && narrow.max() <= wide.max();
+    match (a, b) {
+        (None, None) => Ok(None),
+        (Some(a), None) => fit(y, a).map(|_| Some(a)),
+        (None, Some(b)) => fit(x, b).map(|_| Some(b)),
+        (Some(a), Some(b)) if a == b => Ok(Some(a)),
+        (Some(a), Some(b)) if holds(a, b) => Ok(Some(a)),
+        (Some(a), Some(b)) if holds(b, a) => Ok(Some(b)),
+        (Some(a), Some(b)) => Err(EvalErrorKind::IncompatibleTypes {
+            lhs: a.to_string(),
+            rhs: b.to_string(),
+        }),
+    }
+}
+
+/// Rounds `value` to the precision of a float type of `bits` bits.
+pub(super) fn round_float(value: f64, bits: Option<u16>) -> f64 {
+    match bits {
+        Some(16) => round_f16(value),
+        Some(32) => value as f32 as f64,
+        _ => value,
+    }
+}
+
+/// Rounds to the nearest `f16`, with ties to even, as there is no `f16`
+/// type in stable rust to convert to. An `f16` has 10 bits after the
+/// point, with exponents from -14 up, below which it gets subnormal.
+fn round_f16(value: f64) -> f64 {
+    const MAX: f64 = 65504.0;
+    if !value.is_finite() || value == 0.0 {
+        return value;
+    }
+    let exponent = ((value.to_bits() >> 52) & 0x7ff) as i32 - 1023;
+    // The distance between `f16`s around the value, which is a power of
+    // two, so dividing by it is exact.
+    let step = 2f64.powi(exponent.max(-14) - 10);
+    let rounded = (value / step).round_ties_even() * step;
+    match rounded.abs() > MAX {
+        true => f64::INFINITY.copysign(value),
+        false => rounded,
+    }
+}
+
+/// Applies a binary operator to numbers or bools. The operators that need
+/// more than the values, such as `and` and `orelse`, are left to the
+/// interpreter.
+pub(super) fn binary<'src>(
+    op: BinaryOp,
+    lhs: &Value<'src>,
+    rhs: &Value<'src>,
+) -> Result<Value<'src>> {
+    match (lhs, rhs) {
+        (Value::Int(x, a), Value::Int(y, b)) => {
+            if is_comparison(op) {
+                return Ok(compare(op, Some(x.cmp(y))));
+            }
+            let ty = peer_int(*x, *a, *y, *b)?;
+            int(op, *x, *y, ty)
+        }
+        (Value::Float(..), Value::Float(..) | Value::Int(_, None))
+        | (Value::Int(_, None), Value::Float(..)) => {
+            let (x, a) = float_parts(lhs);
+            let (y, b) = float_parts(rhs);
+            let bits = match (a, b) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                (a, b) => a.or(b),
+            };
+            float(op, x, y, bits)
+        }
+        (Value::Bool(x), Value::Bool(y)) if matches!(op, BinaryOp::Equal | BinaryOp::NotEqual) => {
+            Ok(compare(op, Some(x.cmp(y))))
+        }
+        _ if matches!(op, BinaryOp::Equal | BinaryOp::NotEqual) => {
+            let equal = equal(lhs, rhs)?;
+            Ok(Value::Bool(equal == (op == BinaryOp::Equal)))
+        }
+        _ => match lhs {
+            Value::Int(..) | Value::Float(..) => Err(incompatible(lhs, rhs)),
+            _ => Err(EvalErrorKind::InvalidOperator {
+                op: symbol(op),
+                ty: lhs.type_of().to_string(),
+            }),
+        },
+    }
+}
+
+fn float_parts(value: &Value) -> (f64, Option<u16>) {
+    match value {
+        Value::Float(value, bits) => (*value, *bits),
+        Value::Int(value, _) => (value.to_f64(), None),
+        _ => unreachable!("only called for numbers"),
+    }
+}
+
+fn int<'src>(op: BinaryOp, x: Int, y: Int, ty: Option<IntType>) -> Result<Value<'src>> {
+    use BinaryOp::*;
+    let type_name = || ty.map_or("comptime_int".to_string(), |ty| ty.to_string());
+    // Wrapping and saturating arithmetic only makes sense with a width.
+    let fixed = || {
+        ty.ok_or_else(|| EvalErrorKind::InvalidOperator {
+            op: symbol(op),
+            ty: type_name(),
+        })
+    };
+    let checked = |result: Option<Int>| match (result, ty) {
+        (Some(result), Some(ty)) if !ty.contains(result) => Err(EvalErrorKind::Overflow),
+        (Some(result), _) => Ok(result),
+        (None, _) => Err(EvalErrorKind::Overflow),
+    };
+    // Wraps the two's complement result, which is right modulo 2^128
+    // and so for every width up to that.
+    let wrap = |result: fn(u128, u128) -> u128| {
+        fixed().map(|ty| ty.from_bits(result(x.to_bits(), y.to_bits())))
+    };
+    // Results too large for an `Int` saturate by the sign they would have had.
+    let saturate = |result: Option<Int>, negative: bool| {
+        let ty = fixed()?;
+        Ok(match result {
+            Some(result) => result.clamp(ty.min(), ty.max()),
+            None if negative => ty.min(),
+            None => ty.max(),
+        })
+    };
+    // Shift amounts have to be smaller than the width of the type.
+    let shift = || {
+        let bits = ty.map_or(128, |ty| ty.bits.max(1) as u32);
+        match y.to::<u32>() {
+            Some(y) if y < bits => Ok(y),
+            _ => Err(EvalErrorKind::ShiftOverflow),
+        }
+    };
+    let divisor = || {
+        if y.is_zero() {
+            return Err(EvalErrorKind::DivisionByZero);
+        }
+        if ty.is_none_or(|ty| ty.signed) && (x.is_negative() || y.is_negative()) {
+            return Err(EvalErrorKind::SignedDivision(type_name()));
+        }
+        Ok(y)
+    };
+    let bitwise = |op: fn(u128, u128) -> u128| checked(x.bitwise(y, op));
+
+    let result = match op {
+        Add => checked(x.checked_add(y))?,
+        Sub => checked(x.checked_sub(y))?,
+        Mul => checked(x.checked_mul(y))?,
+        Div => checked(x.checked_div(divisor()?))?,
+        Mod => checked(x.checked_rem(divisor()?))?,
+        AddWrap => wrap(u128::wrapping_add)?,
+        SubWrap => wrap(u128::wrapping_sub)?,
+        MulWrap => wrap(u128::wrapping_mul)?,
+        AddSat => saturate(x.checked_add(y), x.is_negative())?,
+        SubSat => saturate(x.checked_sub(y), x.is_negative())?,
+        MulSat => saturate(x.checked_mul(y), x.is_negative() != y.is_negative())?,
+        BitAnd => bitwise(|x, y| x & y)?,
+        BitOr => bitwise(|x, y| x | y)?,
+        BitXor => bitwise(|x, y| x ^ y)?,
+        Shl => {
+            let y = shift()?;
+            match ty {
+                // Bits shifted out of a fixed width integer are lost.
+                Some(ty) => ty.from_bits(x.to_bits() << y),
+                None => checked(x.checked_shl(y))?,
+            }
+        }
+        ShlSat => {
+            let y = shift()?;
+            saturate(x.checked_shl(y), x.is_negative())?
+        }
+        Shr => x >> shift()?,
+        _ => {
+            return Err(EvalErrorKind::InvalidOperator {
+                op: symbol(op),
+                ty: type_name(),
+            })
+        }
+    };
+    Ok(Value::Int(result, ty))
+}
+
+fn float<'src>(op: BinaryOp, x: f64, y: f64, bits: Option<u16>) -> Result<Value<'src>> {
+    use BinaryOp::*;
+    if is_comparison(op) {
+        return Ok(compare(op, x.partial_cmp(&y)));
+    }
+    let result = match op {
+        Add => x + y,
+        Sub => x - y,
+        Mul => x * y,
+        Div if y == 0.0 && bits.is_none() => return Err(EvalErrorKind::DivisionByZero),
+        Div => x / y,
+        Mod if y == 0.0 => return Err(EvalErrorKind::DivisionByZero),
+        Mod if x < 0.0 || y < 0.0 => {
+            let ty = bits.map_or("comptime_float".to_string(), |bits| format!("f{bits}"));
+            return Err(EvalErrorKind::SignedDivision(ty));
+        }
+        Mod => x % y,
+        _ => {
+            let ty = bits.map_or("comptime_float".to_string(), |bits| format!("f{bits}"));
+            return Err(EvalErrorKind::InvalidOperator { op: symbol(op), ty });
+        }
+    };
+    Ok(Value::Float(round_float(result, bits), bits))
+}
+
+/// `==` for the values that aren't numbers or bools.
+pub(super) fn equal<'src>(lhs: &Value<'src>, rhs: &Value<'src>) -> Result<bool> {
+    let tag_name = |value: &Value| match value {
+        Value::Enum(container, index) | Value::Union(container, index, _) => container
+            .fields()
+            .get(*index)
+            .map(|field| field.name.clone()),
+        Value::EnumLiteral(name) => Some(name.clone()),
+        _ => None,
+    };
+    let equal = match (lhs, rhs) {
+        (Value::Int(..) | Value::Float(..) | Value::Bool(_), _) => {
+            matches!(binary(BinaryOp::Equal, lhs, rhs)?, Value::Bool(true))
+        }
+        (Value::Null, Value::Null) | (Value::Void, Value::Void) => true,
+        // Comparing an optional to null.
+        (Value::Null, _) | (_, Value::Null) => false,
+        (Value::Enum(a, x), Value::Enum(b, y)) => a == b && x == y,
+        (Value::Enum(..) | Value::Union(..) | Value::EnumLiteral(_), Value::EnumLiteral(_))
+        | (Value::EnumLiteral(_), Value::Enum(..) | Value::Union(..)) => {
+            tag_name(lhs) == tag_name(rhs)
+        }
+        (Value::Error(a), Value::Error(b)) => a == b,
+        (Value::Type(a), Value::Type(b)) => a == b,
+        (Value::Pointer(a), Value::Pointer(b)) => a.place.same_as(&b.place),
+        (Value::Fn(a), Value::Fn(b)) => a == b,
+        (Value::Undefined, _) | (_, Value::Undefined) => return Err(EvalErrorKind::UndefinedValue),
+        _ if lhs.type_of() != rhs.type_of() => return Err(incompatible(lhs, rhs)),
+        _ => {
+            return Err(EvalErrorKind::InvalidOperator {
+                op: "==",
+                ty: lhs.type_of().to_string(),
+            })
+        }
+    };
+    Ok(equal)
+}
+
+pub(super) fn negate<'src>(value: &Value<'src>, wrap: bool) -> Result<Value<'src>> {
+    let op = if wrap { "-%" } else { "-" };
+    match value {
+        Value::Int(x, Some(ty)) if ty.signed || wrap => match wrap {
+            true => Ok(Value::Int(ty.wrap(-*x), Some(*ty))),
+            false => match ty.contains(-*x) {
+                true => Ok(Value::Int(-*x, Some(*ty))),
+                false => Err(EvalErrorKind::Overflow),
+            },
+        },
+        Value::Int(x, None) if !wrap => Ok(Value::Int(-*x, None)),
+        Value::Float(x, bits) if !wrap => Ok(Value::Float(-x, *bits)),
+        _ => Err(EvalErrorKind::InvalidOperator {
+            op,
+            ty: value.type_of().to_string(),
+        }),
+    }
+}
+
+pub(super) fn bit_not<'src>(value: &Value<'src>) -> Result<Value<'src>> {
+    match value {
+        Value::Int(x, Some(ty)) => Ok(Value::Int(ty.from_bits(!x.to_bits()), Some(*ty))),
+        _ => Err(EvalErrorKind::InvalidOperator {
+            op: "~",
+            ty: value.type_of().to_string(),
+        }),
+    }
+}
+
+/// `@intCast`, which checks that the value fits.
+pub(super) fn int_cast<'src>(value: &Value<'src>, ty: IntType) -> Result<Value<'src>> {
+    match value {
+        Value::Int(x, _) if ty.contains(*x) => Ok(Value::Int(*x, Some(ty))),
+        Value::Int(..) => Err(EvalErrorKind::CastTruncated),
+        _ => Err(expected_int(value)),
+    }
+}
+
+/// `@truncate`, which drops the bits that don't fit.
+pub(super) fn truncate<'src>(value: &Value<'src>, ty: IntType) -> Result<Value<'src>> {
+    match value {
+        Value::Int(x, _) => Ok(Value::Int(ty.wrap(*x), Some(ty))),
+        _ => Err(expected_int(value)),
+    }
+}
+
+pub(super) fn int_from_float<'src>(
+    value: &Value<'src>,
+    ty: Option<IntType>,
+) -> Result<Value<'src>> {
+    match value {
+        Value::Float(x, _) => match Int::from_f64(*x) {
+            Some(x) if ty.is_none_or(|ty| ty.contains(x)) => Ok(Value::Int(x, ty)),
+            _ => Err(EvalErrorKind::FloatOutOfBounds),
+        },
+        _ => Err(EvalErrorKind::TypeMismatch {
+            expected: "float".to_string(),
+            found: value.type_of().to_string(),
+        }),
+    }
+}
+
+pub(super) fn float_from_int<'src>(value: &Value<'src>, bits: Option<u16>) -> Result<Value<'src>> {
+    match value {
+        Value::Int(x, _) => Ok(Value::Float(round_float(x.to_f64(), bits), bits)),
+        _ => Err(expected_int(value)),
+    }
+}
+
+pub(super) fn expected_int(value: &Value) -> EvalErrorKind {
+    EvalErrorKind::TypeMismatch {
+        expected: "integer".to_string(),
+        found: value.type_of().to_string(),
+    }
+}
+
+/// The integer type of a type value, for the casting builtins.
+pub(super) fn int_type(ty: &Type) -> Result<Option<IntType>> {
+    match ty {
+        Type::Int(int) => Ok(Some(*int)),
+        Type::ComptimeInt => Ok(None),
+        _ => Err(EvalErrorKind::TypeMismatch {
+            expected: "integer type".to_string(),
+            found: ty.to_string(),
+        }),
+    }
+}
+
+pub(super) fn float_type(ty: &Type) -> Result<Option<u16>> {
+    match ty {
+        Type::Float(bits) => Ok(Some(*bits)),
+        Type::ComptimeFloat => Ok(None),
+        _ => Err(EvalErrorKind::TypeMismatch {
+            expected: "float type".to_string(),
+            found: ty.to_string(),
+        }),
+    }
+}
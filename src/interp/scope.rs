@@ -0,0 +1,123 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+use crate::ast::VarDecl;
+
+use super::value::{Container, Variable};
+
+/// The names declared in a block, a function call or a container.
+/// Scopes point up to the scope they are nested in, and a lookup goes
+/// up until it finds the name.
+///
+/// The declarations of containers can refer to each other in any order,
+/// so those aren't evaluated until they're first looked up.
+#[derive(Default)]
+pub(crate) struct Scope<'src> {
+    parent: Option<Rc<Scope<'src>>>,
+    vars: RefCell<HashMap<String, Rc<Variable<'src>>>>,
+    lazy: RefCell<HashMap<String, Lazy<'src>>>,
+    /// The container the declarations belong to, for `@This()`.
+    container: RefCell<Weak<Container<'src>>>,
+}
+
+#[derive(Clone)]
+pub(crate) enum Lazy<'src> {
+    Pending(Rc<VarDecl<'src>>),
+    /// Being evaluated, so looking it up again is a dependency loop.
+    InProgress,
+}
+
+impl<'src> Scope<'src> {
+    pub(crate) fn root() -> Rc<Self> {
+        Rc::new(Self::default())
+    }
+
+    pub(crate) fn child(parent: &Rc<Self>) -> Rc<Self> {
+        Rc::new(Self {
+            parent: Some(parent.clone()),
+            ..Self::default()
+        })
+    }
+
+    pub(crate) fn parent(&self) -> Option<&Rc<Self>> {
+        self.parent.as_ref()
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<Rc<Variable<'src>>> {
+        self.vars.borrow().get(name).cloned()
+    }
+
+    pub(crate) fn get_lazy(&self, name: &str) -> Option<Lazy<'src>> {
+        self.lazy.borrow().get(name).cloned()
+    }
+
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        self.vars.borrow().contains_key(name) || self.lazy.borrow().contains_key(name)
+    }
+
+    /// Adds a variable, returning false if the name is taken already.
+    pub(crate) fn declare(&self, name: &str, var: Rc<Variable<'src>>) -> bool {
+        if self.contains(name) {
+            return false;
+        }
+        self.vars.borrow_mut().insert(name.to_string(), var);
+        true
+    }
+
+    pub(crate) fn declare_lazy(&self, name: &str, decl: Rc<VarDecl<'src>>) -> bool {
+        if self.contains(name) {
+            return false;
+        }
+        self.lazy
+            .borrow_mut()
+            .insert(name.to_string(), Lazy::Pending(decl));
+        true
+    }
+
+    /// Forgets about a name, so that it can be declared again.
+    pub(crate) fn remove(&self, name: &str) {
+        self.vars.borrow_mut().remove(name);
+        self.lazy.borrow_mut().remove(name);
+    }
+
+    pub(crate) fn start_lazy(&self, name: &str) {
+        self.lazy
+            .borrow_mut()
+            .insert(name.to_string(), Lazy::InProgress);
+    }
+
+    /// Puts a lazy declaration back the way it was, after evaluating it failed.
+    pub(crate) fn cancel_lazy(&self, name: &str, decl: Rc<VarDecl<'src>>) {
+        self.lazy
+            .borrow_mut()
+            .insert(name.to_string(), Lazy::Pending(decl));
+    }
+
+    pub(crate) fn finish_lazy(&self, name: &str, var: Rc<Variable<'src>>) {
+        self.lazy.borrow_mut().remove(name);
+        self.vars.borrow_mut().insert(name.to_string(), var);
+    }
+
+    /// Every name declared in this scope, but not the ones above it.
+    pub(crate) fn names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.vars.borrow().keys().cloned().collect();
+        names.extend(self.lazy.borrow().keys().cloned());
+        names.sort();
+        names
+    }
+
+    pub(crate) fn set_container(&self, container: &Rc<Container<'src>>) {
+        *self.container.borrow_mut() = Rc::downgrade(container);
+    }
+
+    /// The innermost container this scope is in.
+    pub(crate) fn container(&self) -> Option<Rc<Container<'src>>> {
+        match self.container.borrow().upgrade() {
+            Some(container) => Some(container),
+            None => self.parent.as_ref()?.container(),
+        }
+    }
+}
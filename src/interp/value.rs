@@ -0,0 +1,779 @@
+use std::{cell::RefCell, fmt, rc::Rc};
+
+use crate::{
+    ast::{ContainerDecl, ContainerKind, Expr, ExprKind, FnDecl, ParamType, PointerSize},
+    tokenizer::PrimitiveType,
+};
+
+use super::{int::Int, scope::Scope};
+
+/// A fixed width integer type such as `u8` or `i32`, of up to 128 bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IntType {
+    pub signed: bool,
+    pub bits: u16,
+    /// Set for `usize` and `isize`, which are their own types in zig
+    /// even though they are 64 bits wide here.
+    pub pointer_sized: bool,
+}
+
+impl IntType {
+    pub const USIZE: IntType = IntType {
+        signed: false,
+        bits: 64,
+        pointer_sized: true,
+    };
+    pub const U8: IntType = IntType::new(false, 8);
+
+    pub const fn new(signed: bool, bits: u16) -> Self {
+        Self {
+            signed,
+            bits,
+            pointer_sized: false,
+        }
+    }
+
+    /// All ones in the lowest `bits` bits.
+    fn mask(&self) -> u128 {
+        match self.bits {
+            bits if bits >= 128 => u128::MAX,
+            bits => (1 << bits) - 1,
+        }
+    }
+
+    pub fn min(&self) -> Int {
+        match (self.signed, self.bits) {
+            (false, _) | (true, 0) => Int::ZERO,
+            (true, bits) => Int::new(true, 1 << (bits.min(128) - 1)),
+        }
+    }
+
+    pub fn max(&self) -> Int {
+        match (self.signed, self.bits) {
+            (_, 0) => Int::ZERO,
+            (true, bits) => Int::new(false, (1 << (bits.min(128) - 1)) - 1),
+            (false, _) => Int::new(false, self.mask()),
+        }
+    }
+
+    pub fn contains(&self, value: Int) -> bool {
+        (self.min()..=self.max()).contains(&value)
+    }
+
+    /// Truncates `value` to the width of the type, the way two's
+    /// complement arithmetic wraps around.
+    pub fn wrap(&self, value: Int) -> Int {
+        self.from_bits(value.to_bits())
+    }
+
+    /// The value of the lowest `bits` bits of `bits` as this type.
+    pub fn from_bits(&self, bits: u128) -> Int {
+        let bits = bits & self.mask();
+        let sign_bit = self.bits > 0 && bits >> (self.bits.min(128) - 1) & 1 == 1;
+        match self.signed && sign_bit {
+            // The magnitude is the two's complement of the value.
+            true => Int::new(true, (!bits & self.mask()) + 1),
+            false => Int::new(false, bits),
+        }
+    }
+}
+
+impl fmt::Display for IntType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.pointer_sized, self.signed) {
+            (true, true) => write!(f, "isize"),
+            (true, false) => write!(f, "usize"),
+            (false, true) => write!(f, "i{}", self.bits),
+            (false, false) => write!(f, "u{}", self.bits),
+        }
+    }
+}
+
+/// The parts of the standard library the interpreter knows about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Namespace {
+    Std,
+    Debug,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NativeFn {
+    /// `std.debug.print`
+    Print,
+    /// `std.debug.assert`
+    Assert,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type<'src> {
+    Int(IntType),
+    ComptimeInt,
+    /// A float type by its width. Everything is computed in an `f64`,
+    /// with `f16` and `f32` results rounded to their precision.
+    Float(u16),
+    ComptimeFloat,
+    Bool,
+    Void,
+    NoReturn,
+    Type,
+    AnyOpaque,
+    /// The type of `null`.
+    Null,
+    /// The type of `undefined`.
+    Undefined,
+    /// The type of `.foo` before it is coerced to an enum.
+    EnumLiteral,
+    /// An error set, where `None` is `anyerror`.
+    ErrorSet(Option<Rc<Vec<String>>>),
+    ErrorUnion {
+        error: Box<Type<'src>>,
+        payload: Box<Type<'src>>,
+    },
+    Optional(Box<Type<'src>>),
+    Array {
+        len: usize,
+        sentinel: Option<i64>,
+        child: Box<Type<'src>>,
+    },
+    Pointer {
+        size: PointerSize,
+        is_const: bool,
+        sentinel: Option<i64>,
+        child: Box<Type<'src>>,
+    },
+    /// A `struct`, `enum` or `union`.
+    Container(Rc<Container<'src>>),
+    /// The type of `.{ 1, 2 }`.
+    Tuple(Vec<Type<'src>>),
+    /// The type of `.{ .x = 1 }`.
+    AnonStruct(Vec<(String, Type<'src>)>),
+    Fn(Rc<Function<'src>>),
+    Namespace(Namespace),
+    NativeFn(NativeFn),
+}
+
+impl<'src> Type<'src> {
+    pub fn from_primitive(primitive: PrimitiveType) -> Self {
+        use PrimitiveType as P;
+        let int = |signed, bits| Type::Int(IntType::new(signed, bits));
+        match primitive {
+            P::I8 => int(true, 8),
+            P::U8 | P::Char => int(false, 8),
+            P::I16 | P::Short => int(true, 16),
+            P::U16 | P::Ushort => int(false, 16),
+            P::I32 | P::Int => int(true, 32),
+            P::U32 | P::Uint => int(false, 32),
+            P::I64 | P::Long | P::Longlong => int(true, 64),
+            P::U64 | P::Ulong | P::ULonglong => int(false, 64),
+            P::I128 => int(true, 128),
+            P::U128 => int(false, 128),
+            P::SignedInt(bits) => int(true, bits),
+            P::UnsignedInt(bits) => int(false, bits),
+            P::ISize => Type::Int(IntType {
+                signed: true,
+                ..IntType::USIZE
+            }),
+            P::USize => Type::Int(IntType::USIZE),
+            P::F16 => Type::Float(16),
+            P::F32 => Type::Float(32),
+            P::F64 => Type::Float(64),
+            P::F80 | P::Longdouble => Type::Float(80),
+            P::F128 => Type::Float(128),
+            P::Bool => Type::Bool,
+            P::AnyOpaque => Type::AnyOpaque,
+            P::Void => Type::Void,
+            P::NoReturn => Type::NoReturn,
+            P::Type => Type::Type,
+            P::AnyError => Type::ErrorSet(None),
+            P::ComptimeInt => Type::ComptimeInt,
+            P::ComptimeFloat => Type::ComptimeFloat,
+        }
+    }
+
+    /// `[]const u8`
+    pub fn string() -> Self {
+        Type::Pointer {
+            size: PointerSize::Slice,
+            is_const: true,
+            sentinel: None,
+            child: Box::new(Type::Int(IntType::U8)),
+        }
+    }
+
+    /// Types that only exist at compile time, which a `var` can't have.
+    pub fn is_comptime_only(&self) -> bool {
+        matches!(
+            self,
+            Type::ComptimeInt
+                | Type::ComptimeFloat
+                | Type::Type
+                | Type::EnumLiteral
+                | Type::Null
+                | Type::Undefined
+                | Type::Fn(_)
+                | Type::Namespace(_)
+                | Type::NativeFn(_)
+        )
+    }
+
+    /// The type a `.{ ... }` or `.foo` is written for, looking through
+    /// optionals and error unions.
+    pub fn payload(&self) -> &Self {
+        match self {
+            Type::Optional(child) => child.payload(),
+            Type::ErrorUnion { payload, .. } => payload.payload(),
+            _ => self,
+        }
+    }
+
+    pub fn container(&self) -> Option<&Rc<Container<'src>>> {
+        match self {
+            Type::Container(container) => Some(container),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Type<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Int(int) => write!(f, "{int}"),
+            Type::ComptimeInt => write!(f, "comptime_int"),
+            Type::Float(bits) => write!(f, "f{bits}"),
+            Type::ComptimeFloat => write!(f, "comptime_float"),
+            Type::Bool => write!(f, "bool"),
+            Type::Void => write!(f, "void"),
+            Type::NoReturn => write!(f, "noreturn"),
+            Type::Type => write!(f, "type"),
+            Type::AnyOpaque => write!(f, "anyopaque"),
+            Type::Null => write!(f, "@TypeOf(null)"),
+            Type::Undefined => write!(f, "@TypeOf(undefined)"),
+            Type::EnumLiteral => write!(f, "@TypeOf(.enum_literal)"),
+            Type::ErrorSet(None) => write!(f, "anyerror"),
+            Type::ErrorSet(Some(names)) => write!(f, "error{{{}}}", names.join(",")),
+            Type::ErrorUnion { error, payload } => write!(f, "{error}!{payload}"),
+            Type::Optional(child) => write!(f, "?{child}"),
+            Type::Array {
+                len,
+                sentinel,
+                child,
+            } => match sentinel {
+                Some(sentinel) => write!(f, "[{len}:{sentinel}]{child}"),
+                None => write!(f, "[{len}]{child}"),
+            },
+            Type::Pointer {
+                size,
+                is_const,
+                sentinel,
+                child,
+            } => {
+                let sentinel = sentinel.map(|s| format!(":{s}")).unwrap_or_default();
+                match size {
+                    PointerSize::One => write!(f, "*")?,
+                    PointerSize::Many => write!(f, "[*{sentinel}]")?,
+                    PointerSize::Slice => write!(f, "[{sentinel}]")?,
+                    PointerSize::C => write!(f, "[*c]")?,
+                }
+                if *is_const {
+                    write!(f, "const ")?;
+                }
+                write!(f, "{child}")
+            }
+            Type::Container(container) => write!(f, "{}", container.name()),
+            Type::Tuple(types) => {
+                let types: Vec<_> = types.iter().map(Type::to_string).collect();
+                write!(f, "struct {{ {} }}", types.join(", "))
+            }
+            Type::AnonStruct(fields) => {
+                let fields: Vec<_> = fields
+                    .iter()
+                    .map(|(name, ty)| format!("{name}: {ty}"))
+                    .collect();
+                write!(f, "struct {{ {} }}", fields.join(", "))
+            }
+            Type::Fn(function) => write!(f, "{}", function.signature()),
+            Type::Namespace(Namespace::Std) => write!(f, "std"),
+            Type::Namespace(Namespace::Debug) => write!(f, "std.debug"),
+            Type::NativeFn(NativeFn::Print) => write!(f, "fn ([]const u8, anytype) void"),
+            Type::NativeFn(NativeFn::Assert) => write!(f, "fn (bool) void"),
+        }
+    }
+}
+
+/// A `struct`, `enum` or `union` type. Every evaluation of a container
+/// expression makes a new one, and two containers are only the same type
+/// if they came from the same evaluation.
+pub struct Container<'src> {
+    pub(crate) kind: ContainerKind,
+    /// The name of the declaration the type was first bound to, as zig
+    /// names types after them.
+    pub(crate) name: RefCell<Option<String>>,
+    pub(crate) decl: Rc<ContainerDecl<'src>>,
+    /// The declarations of the container, whose parent is the scope
+    /// the container expression was evaluated in.
+    pub(crate) scope: Rc<Scope<'src>>,
+    /// Resolved the first time they're needed, so that fields can refer
+    /// to the container they're in, as in `next: ?*Node`.
+    pub(crate) fields: RefCell<Option<Rc<Vec<Field<'src>>>>>,
+}
+
+/// A field of a container. Enum fields have the `void` type and their
+/// tag value as the default.
+#[derive(Debug, Clone)]
+pub struct Field<'src> {
+    pub name: String,
+    pub ty: Type<'src>,
+    pub default: Option<Value<'src>>,
+}
+
+impl<'src> Container<'src> {
+    pub fn name(&self) -> String {
+        match &*self.name.borrow() {
+            Some(name) => name.clone(),
+            None => match self.kind {
+                ContainerKind::Struct => "struct".to_string(),
+                ContainerKind::Enum => "enum".to_string(),
+                ContainerKind::Union => "union".to_string(),
+                ContainerKind::Opaque => "opaque".to_string(),
+            },
+        }
+    }
+
+    /// The fields, if they have been resolved already. There is always
+    /// a value of the type to hand when they're needed here.
+    pub fn fields(&self) -> Rc<Vec<Field<'src>>> {
+        self.fields.borrow().clone().unwrap_or_default()
+    }
+
+    pub fn field_index(&self, name: &str) -> Option<usize> {
+        self.fields().iter().position(|field| field.name == name)
+    }
+}
+
+impl PartialEq for Container<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for Container<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Container({})", self.name())
+    }
+}
+
+/// A function along with the scope it was declared in.
+pub struct Function<'src> {
+    pub name: String,
+    pub(crate) decl: Rc<FnDecl<'src>>,
+    pub(crate) scope: Rc<Scope<'src>>,
+}
+
+impl Function<'_> {
+    /// The signature as written, such as `fn (i32, i32) i32`.
+    pub fn signature(&self) -> String {
+        let proto = &self.decl.proto;
+        let params: Vec<_> = proto
+            .params
+            .iter()
+            .map(|param| {
+                let ty = match &param.ty {
+                    ParamType::Expr(expr) => describe(expr),
+                    ParamType::AnyType => "anytype".to_string(),
+                    ParamType::VarArgs => "...".to_string(),
+                };
+                match param.is_comptime {
+                    true => format!("comptime {ty}"),
+                    false => ty,
+                }
+            })
+            .collect();
+        let error = if proto.inferred_error { "!" } else { "" };
+        let ret = describe(&proto.return_type);
+        format!("fn ({}) {error}{ret}", params.join(", "))
+    }
+}
+
+impl PartialEq for Function<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for Function<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Function({})", self.name)
+    }
+}
+
+/// Writes a type expression back out, for function signatures.
+fn describe(expr: &Expr) -> String {
+    match &expr.kind {
+        ExprKind::Identifier(name) => name.to_string(),
+        ExprKind::PrimitiveType(primitive) => Type::from_primitive(*primitive).to_string(),
+        ExprKind::Field { expr, name } => format!("{}.{}", describe(expr), name.name),
+        ExprKind::Optional(child) => format!("?{}", describe(child)),
+        ExprKind::ErrorUnion { error, payload } => {
+            format!("{}!{}", describe(error), describe(payload))
+        }
+        ExprKind::Pointer(pointer) => {
+            let size = match pointer.size {
+                PointerSize::One => "*",
+                PointerSize::Many => "[*]",
+                PointerSize::Slice => "[]",
+                PointerSize::C => "[*c]",
+            };
+            let constness = if pointer.is_const { "const " } else { "" };
+            format!("{size}{constness}{}", describe(&pointer.child))
+        }
+        ExprKind::Array { len, child, .. } => format!("[{}]{}", describe(len), describe(child)),
+        ExprKind::Integer(value) => value.to_string(),
+        ExprKind::Call { callee, args } => {
+            let args: Vec<_> = args.iter().map(describe).collect();
+            format!("{}({})", describe(callee), args.join(", "))
+        }
+        ExprKind::Builtin { name, args } => {
+            let args: Vec<_> = args.iter().map(describe).collect();
+            format!("@{name}({})", args.join(", "))
+        }
+        ExprKind::Grouped(expr) => format!("({})", describe(expr)),
+        _ => "...".to_string(),
+    }
+}
+
+/// A `var` or `const`, or anything else a pointer can point to.
+#[derive(Debug)]
+pub struct Variable<'src> {
+    pub(crate) value: RefCell<Value<'src>>,
+    pub(crate) ty: Type<'src>,
+    pub(crate) is_const: bool,
+    /// Whether the value is known at compile time, which it isn't for
+    /// `var`s and parameters outside of `comptime` code.
+    pub(crate) is_comptime: bool,
+}
+
+impl<'src> Variable<'src> {
+    pub fn new(value: Value<'src>, ty: Type<'src>, is_const: bool) -> Rc<Self> {
+        Rc::new(Self {
+            value: RefCell::new(value),
+            ty,
+            is_const,
+            is_comptime: true,
+        })
+    }
+
+    /// A variable whose value is only known at runtime.
+    pub fn runtime(value: Value<'src>, ty: Type<'src>, is_const: bool) -> Rc<Self> {
+        Rc::new(Self {
+            value: RefCell::new(value),
+            ty,
+            is_const,
+            is_comptime: false,
+        })
+    }
+
+    pub fn value(&self) -> Value<'src> {
+        self.value.borrow().clone()
+    }
+
+    pub fn ty(&self) -> &Type<'src> {
+        &self.ty
+    }
+}
+
+/// A way into a value, from a variable through fields and elements.
+#[derive(Clone)]
+pub struct Place<'src> {
+    pub(crate) var: Rc<Variable<'src>>,
+    pub(crate) path: Vec<Step>,
+    /// Whether the place can't be assigned to, because the variable is
+    /// a `const` or it was reached through a pointer to const.
+    pub(crate) is_const: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Step {
+    /// A field of a struct or tuple.
+    Field(usize),
+    Index(usize),
+    /// The payload of a union.
+    Payload,
+}
+
+impl<'src> Place<'src> {
+    pub(crate) fn new(var: Rc<Variable<'src>>) -> Self {
+        let is_const = var.is_const;
+        Self {
+            var,
+            path: Vec::new(),
+            is_const,
+        }
+    }
+
+    pub(crate) fn child(&self, step: Step) -> Self {
+        let mut place = self.clone();
+        place.path.push(step);
+        place
+    }
+
+    /// Calls `f` with the value at the place, or `undefined` if the path
+    /// leads into an undefined value.
+    pub(crate) fn with<T>(&self, f: impl FnOnce(&Value<'src>) -> T) -> T {
+        let value = self.var.value.borrow();
+        let mut current = &*value;
+        for step in &self.path {
+            match current.step(*step) {
+                Some(next) => current = next,
+                None => return f(&Value::Undefined),
+            }
+        }
+        f(current)
+    }
+
+    pub(crate) fn get(&self) -> Value<'src> {
+        self.with(Value::clone)
+    }
+
+    /// Stores `new` at the place. Returns false if the path leads into
+    /// an undefined value, so there is nowhere to store it.
+    pub(crate) fn set(&self, new: Value<'src>) -> bool {
+        let mut value = self.var.value.borrow_mut();
+        let mut current = &mut *value;
+        for step in &self.path {
+            match current.step_mut(*step) {
+                Some(next) => current = next,
+                None => return false,
+            }
+        }
+        *current = new;
+        true
+    }
+
+    pub(crate) fn same_as(&self, other: &Place<'src>) -> bool {
+        Rc::ptr_eq(&self.var, &other.var) && self.path == other.path
+    }
+}
+
+impl fmt::Debug for Place<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Place({:p}, {:?})", Rc::as_ptr(&self.var), self.path)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Pointer<'src> {
+    pub(crate) place: Place<'src>,
+    pub(crate) child: Type<'src>,
+}
+
+/// A slice of an array stored in some variable.
+#[derive(Debug, Clone)]
+pub struct Slice<'src> {
+    /// Where the array is.
+    pub(crate) place: Place<'src>,
+    pub(crate) start: usize,
+    pub(crate) len: usize,
+    pub(crate) child: Type<'src>,
+}
+
+impl<'src> Slice<'src> {
+    pub(crate) fn elem(&self, index: usize) -> Place<'src> {
+        self.place.child(Step::Index(self.start + index))
+    }
+
+    pub(crate) fn elems(&self) -> Vec<Value<'src>> {
+        self.place.with(|array| match array {
+            Value::Array(array) => array.elems[self.start..self.start + self.len].to_vec(),
+            _ => Vec::new(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Array<'src> {
+    pub child: Type<'src>,
+    pub sentinel: Option<i64>,
+    pub elems: Vec<Value<'src>>,
+}
+
+impl Array<'_> {
+    /// Strings are arrays of `u8`.
+    pub fn bytes(&self) -> Option<Vec<u8>> {
+        if self.child != Type::Int(IntType::U8) {
+            return None;
+        }
+        self.elems
+            .iter()
+            .map(|elem| match elem {
+                Value::Int(byte, _) => byte.to(),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Struct<'src> {
+    /// `None` for anonymous struct literals, until they're coerced to a struct type.
+    pub ty: Option<Rc<Container<'src>>>,
+    pub fields: Vec<(String, Value<'src>)>,
+}
+
+/// A value of a zig program. Values are copied around a lot, so the
+/// bigger ones are boxed.
+///
+/// Optionals don't have a value of their own: a `?T` is either `null`
+/// or a value of `T`. Error unions are the same, with errors in place of `null`.
+#[derive(Debug, Clone)]
+pub enum Value<'src> {
+    Void,
+    Undefined,
+    Null,
+    Bool(bool),
+    /// An integer and its type, `None` being `comptime_int`.
+    Int(Int, Option<IntType>),
+    /// A float and the width of its type, `None` being `comptime_float`.
+    Float(f64, Option<u16>),
+    EnumLiteral(String),
+    Error(String),
+    Type(Type<'src>),
+    Array(Box<Array<'src>>),
+    Tuple(Vec<Value<'src>>),
+    Struct(Struct<'src>),
+    /// An enum value by the index of its field.
+    Enum(Rc<Container<'src>>, usize),
+    /// A union value by the index of its active field.
+    Union(Rc<Container<'src>>, usize, Box<Value<'src>>),
+    Pointer(Box<Pointer<'src>>),
+    Slice(Box<Slice<'src>>),
+    Fn(Rc<Function<'src>>),
+    NativeFn(NativeFn),
+}
+
+impl<'src> Value<'src> {
+    pub fn type_of(&self) -> Type<'src> {
+        match self {
+            Value::Void => Type::Void,
+            Value::Undefined => Type::Undefined,
+            Value::Null => Type::Null,
+            Value::Bool(_) => Type::Bool,
+            Value::Int(_, Some(ty)) => Type::Int(*ty),
+            Value::Int(_, None) => Type::ComptimeInt,
+            Value::Float(_, Some(bits)) => Type::Float(*bits),
+            Value::Float(_, None) => Type::ComptimeFloat,
+            Value::EnumLiteral(_) => Type::EnumLiteral,
+            Value::Error(name) => Type::ErrorSet(Some(Rc::new(vec![name.clone()]))),
+            Value::Type(_) => Type::Type,
+            Value::Array(array) => Type::Array {
+                len: array.elems.len(),
+                sentinel: array.sentinel,
+                child: Box::new(array.child.clone()),
+            },
+            Value::Tuple(elems) => Type::Tuple(elems.iter().map(Value::type_of).collect()),
+            Value::Struct(Struct { ty: Some(ty), .. })
+            | Value::Enum(ty, _)
+            | Value::Union(ty, _, _) => Type::Container(ty.clone()),
+            Value::Struct(Struct { ty: None, fields }) => Type::AnonStruct(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.type_of()))
+                    .collect(),
+            ),
+            Value::Pointer(pointer) => Type::Pointer {
+                size: PointerSize::One,
+                is_const: pointer.place.is_const,
+                sentinel: None,
+                child: Box::new(pointer.child.clone()),
+            },
+            Value::Slice(slice) => Type::Pointer {
+                size: PointerSize::Slice,
+                is_const: slice.place.is_const,
+                sentinel: None,
+                child: Box::new(slice.child.clone()),
+            },
+            Value::Fn(function) => Type::Fn(function.clone()),
+            Value::NativeFn(native) => Type::NativeFn(*native),
+        }
+    }
+
+    pub fn as_type(&self) -> Option<&Type<'src>> {
+        match self {
+            Value::Type(ty) => Some(ty),
+            _ => None,
+        }
+    }
+
+    /// The bytes of a string, which is an array of `u8`, or
+    /// a pointer to or slice of one.
+    pub fn bytes(&self) -> Option<Vec<u8>> {
+        match self {
+            Value::Array(array) => array.bytes(),
+            Value::Pointer(pointer) => pointer.place.with(|value| match value {
+                Value::Array(array) => array.bytes(),
+                _ => None,
+            }),
+            Value::Slice(slice) => {
+                let array = Array {
+                    child: slice.child.clone(),
+                    sentinel: None,
+                    elems: slice.elems(),
+                };
+                array.bytes()
+            }
+            _ => None,
+        }
+    }
+
+    fn step(&self, step: Step) -> Option<&Value<'src>> {
+        match (self, step) {
+            (Value::Struct(s), Step::Field(i)) => s.fields.get(i).map(|(_, value)| value),
+            (Value::Tuple(elems), Step::Field(i)) => elems.get(i),
+            (Value::Array(array), Step::Index(i)) => array.elems.get(i),
+            (Value::Union(_, _, payload), Step::Payload) => Some(payload),
+            _ => None,
+        }
+    }
+
+    fn step_mut(&mut self, step: Step) -> Option<&mut Value<'src>> {
+        match (self, step) {
+            (Value::Struct(s), Step::Field(i)) => s.fields.get_mut(i).map(|(_, value)| value),
+            (Value::Tuple(elems), Step::Field(i)) => elems.get_mut(i),
+            (Value::Array(array), Step::Index(i)) => array.elems.get_mut(i),
+            (Value::Union(_, _, payload), Step::Payload) => Some(payload),
+            _ => None,
+        }
+    }
+}
+
+impl<'src> From<Type<'src>> for Value<'src> {
+    fn from(ty: Type<'src>) -> Self {
+        Value::Type(ty)
+    }
+}
+
+/// Makes an undefined value of type `ty`, which for arrays and structs
+/// has undefined elements or fields so that they can be assigned to one by one.
+pub(crate) fn undefined_of<'src>(ty: &Type<'src>) -> Value<'src> {
+    match ty {
+        Type::Array {
+            len,
+            sentinel,
+            child,
+        } => Value::Array(Box::new(Array {
+            child: (**child).clone(),
+            sentinel: *sentinel,
+            elems: vec![undefined_of(child); *len],
+        })),
+        Type::Container(container) if container.kind == ContainerKind::Struct => {
+            Value::Struct(Struct {
+                ty: Some(container.clone()),
+                fields: container
+                    .fields()
+                    .iter()
+                    .map(|field| (field.name.clone(), undefined_of(&field.ty)))
+                    .collect(),
+            })
+        }
+        _ => Value::Undefined,
+    }
+}
@@ -1 +1,8 @@
+pub mod ast;
+pub mod diagnostic;
+pub mod fmt;
+pub mod interp;
+pub mod parser;
+pub mod repl;
+pub mod source;
 pub mod tokenizer;
@@ -1,17 +1,173 @@
-use rusty_zig::tokenizer::{Span, Token, Tokenizer};
-
-fn main() {
-    let src = String::from(
-        "{[% }](  %= ;
-[; , &, . .? .* °⁂😭
-        :༼ つ ◕_◕ ༽つ
-        == , => != ! ...
-        ^ ^= |&
-        ,)&={}&(||)",
-    );
-    
-    let tokenizer = Tokenizer::new(src);
-    for tok in tokenizer {
-        println!("{tok:?}");
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::process::ExitCode;
+
+use rusty_zig::{
+    diagnostic::Diagnostic,
+    fmt,
+    interp::{self, Interpreter},
+    parser,
+    repl::{Output, Repl},
+};
+
+fn main() -> ExitCode {
+    // The interpreter recurses along with the program it runs.
+    std::thread::Builder::new()
+        .stack_size(interp::STACK_SIZE)
+        .spawn(command)
+        .expect("couldn't start the main thread")
+        .join()
+        .unwrap_or(ExitCode::FAILURE)
+}
+
+fn command() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None => repl_command(),
+        Some("fmt") => fmt_command(&args[1..]),
+        Some("run") => run_command(&args[1..]),
+        Some(_) => {
+            eprintln!("usage: rusty-zig [fmt [--check] [files...] | run <file>]");
+            eprintln!("without arguments, an interactive REPL is started");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Without arguments, a REPL reading its input from stdin until it ends
+/// or `:quit`.
+fn repl_command() -> ExitCode {
+    let stdin = io::stdin();
+    let interactive = stdin.is_terminal();
+    if interactive {
+        println!("rusty-zig repl, :help for help");
+    }
+    let mut repl = Repl::new().color(io::stderr().is_terminal());
+    let mut lines = stdin.lock().lines();
+    while !repl.has_quit() {
+        if interactive {
+            print!("{}", repl.prompt());
+            let _ = io::stdout().flush();
+        }
+        let output = match lines.next() {
+            Some(Ok(line)) => repl.line(&line),
+            Some(Err(err)) => {
+                eprintln!("error: couldn't read stdin: {err}");
+                return ExitCode::FAILURE;
+            }
+            None => {
+                show(repl.finish());
+                return ExitCode::SUCCESS;
+            }
+        };
+        show(output);
+    }
+    ExitCode::SUCCESS
+}
+
+fn show(output: Option<Output>) {
+    if let Some(output) = output {
+        eprint!("{}", output.err);
+        print!("{}", output.out);
+    }
+}
+
+/// `run <file>`, which runs the `main` function of a zig file.
+fn run_command(args: &[String]) -> ExitCode {
+    let [file] = args else {
+        eprintln!("usage: rusty-zig run <file>");
+        return ExitCode::FAILURE;
+    };
+    let src = match std::fs::read_to_string(file) {
+        Ok(src) => src,
+        Err(err) => {
+            eprintln!("error: couldn't read {file}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let color = std::io::stderr().is_terminal();
+    let ast = parser::parse(&src);
+    if ast.has_errors() {
+        for error in &ast.errors {
+            eprint!("{}", Diagnostic::from(error).render(file, &src, color));
+        }
+        return ExitCode::FAILURE;
+    }
+    match Interpreter::new().run(&ast) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprint!("{}", Diagnostic::from(&error).render(file, &src, color));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// `fmt [--check] [files...]`, which formats the files in place, or stdin
+/// to stdout when there are none. With `--check` nothing is written, and
+/// the files that aren't formatted are listed instead.
+fn fmt_command(args: &[String]) -> ExitCode {
+    let check = args.iter().any(|arg| arg == "--check");
+    let files: Vec<&String> = args.iter().filter(|arg| *arg != "--check").collect();
+    let color = std::io::stderr().is_terminal();
+
+    if files.is_empty() {
+        let mut src = String::new();
+        if let Err(err) = std::io::stdin().read_to_string(&mut src) {
+            eprintln!("error: couldn't read stdin: {err}");
+            return ExitCode::FAILURE;
+        }
+        return match fmt::format(&src) {
+            Ok(out) if check => match out == src {
+                true => ExitCode::SUCCESS,
+                false => {
+                    println!("<stdin>");
+                    ExitCode::FAILURE
+                }
+            },
+            Ok(out) => {
+                print!("{out}");
+                ExitCode::SUCCESS
+            }
+            Err(errors) => {
+                for error in &errors {
+                    eprint!("{}", Diagnostic::from(error).render("<stdin>", &src, color));
+                }
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let mut failed = false;
+    for file in files {
+        let src = match std::fs::read_to_string(file) {
+            Ok(src) => src,
+            Err(err) => {
+                eprintln!("error: couldn't read {file}: {err}");
+                failed = true;
+                continue;
+            }
+        };
+        match fmt::format(&src) {
+            Ok(out) if out == src => {}
+            Ok(_) if check => {
+                println!("{file}");
+                failed = true;
+            }
+            Ok(out) => {
+                if let Err(err) = std::fs::write(file, out) {
+                    eprintln!("error: couldn't write {file}: {err}");
+                    failed = true;
+                }
+            }
+            Err(errors) => {
+                for error in &errors {
+                    eprint!("{}", Diagnostic::from(error).render(file, &src, color));
+                }
+                failed = true;
+            }
+        }
+    }
+    match failed {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
     }
 }
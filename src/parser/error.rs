@@ -0,0 +1,68 @@
+use std::fmt;
+
+use crate::tokenizer::{LexError, LexErrorKind, Span};
+
+/// An error found while parsing. The parser doesn't stop at errors, it
+/// skips ahead to the next statement or declaration and carries on, so
+/// every error in a file can be reported at once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub(crate) span: Span,
+    pub(crate) kind: ParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    /// The source couldn't be tokenized.
+    Lex(LexErrorKind),
+
+    /// A token that doesn't fit the grammar. `expected` describes
+    /// what could have come there instead, such as `';'` or
+    /// `an expression`, and `found` is the offending token.
+    Expected {
+        expected: &'static str,
+        found: String,
+    },
+
+    /// A comparison whose operand is another comparison without
+    /// parentheses, such as `a == b == c`.
+    ChainedComparison,
+}
+
+impl ParseError {
+    pub fn new(span: Span, kind: ParseErrorKind) -> Self {
+        Self { span, kind }
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn kind(&self) -> &ParseErrorKind {
+        &self.kind
+    }
+}
+
+impl From<LexError> for ParseError {
+    fn from(error: LexError) -> Self {
+        Self::new(error.span, ParseErrorKind::Lex(error.kind))
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lex(kind) => write!(f, "{kind}"),
+            Self::Expected { expected, found } => write!(f, "expected {expected}, found {found}"),
+            Self::ChainedComparison => write!(f, "comparison operators cannot be chained"),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -0,0 +1,8 @@
+mod error;
+#[allow(clippy::module_inception)]
+mod parser;
+mod precedence;
+
+pub use error::*;
+pub use parser::*;
+pub use precedence::*;
@@ -0,0 +1,1684 @@
+use std::borrow::Cow;
+
+use crate::{
+    ast::{
+        Asm, AsmInput, AsmOutput, AsmOutputKind, AssignOp, Ast, BinaryOp, Block, Capture,
+        ContainerArg, ContainerDecl, ContainerField, ContainerKind, ContainerLayout, Decl,
+        DeclKind, Expr, ExprKind, FieldInit, FnDecl, FnProto, For, Ident, If, Linkage, Param,
+        ParamType, PointerSize, PointerType, Stmt, StmtKind, Switch, SwitchProng, UnaryOp, VarDecl,
+        While,
+    },
+    tokenizer::{KeywordType, Span, StringLiteral, Token, TokenType, Tokenizer},
+};
+
+use super::{binary_precedence, ParseError, ParseErrorKind, Precedence};
+
+type ParseResult<T> = Result<T, ParseError>;
+
+/// A recursive descent parser for zig, following the grammar in the
+/// zig language reference. Binary operators are parsed by `parse_binary`,
+/// which looks up how tightly each one binds in `BINARY_PRECEDENCE`.
+pub struct Parser<'src> {
+    src: &'src str,
+    /// All tokens of the source, always ending with an `Eof` token.
+    tokens: Vec<Token<'src>>,
+    pos: usize,
+    /// The end of the last consumed token, which is where nodes end.
+    prev_end: usize,
+    /// The errors found so far, starting with those of the tokenizer.
+    errors: Vec<ParseError>,
+}
+
+impl<'src> Parser<'src> {
+    pub fn new(src: &'src str) -> Self {
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+        for result in Tokenizer::new(src).results() {
+            match result {
+                Ok(token) => tokens.push(token),
+                Err(error) => errors.push(error.into()),
+            }
+        }
+        tokens.push(Token::new(Span::new(src.len(), src.len()), TokenType::Eof));
+        Self {
+            src,
+            tokens,
+            pos: 0,
+            prev_end: 0,
+            errors,
+        }
+    }
+
+    /// Parses the whole source as the members of a container. This always
+    /// produces a tree, with the errors that were found in `Ast::errors`
+    /// and the parts of the source that couldn't be parsed left as
+    /// `DeclKind::Error` and `ExprKind::Error` nodes.
+    pub fn parse(mut self) -> Ast<'src> {
+        let doc = self.container_doc_comments();
+        let mut decls = self.parse_members();
+        // The members only stop early at a `}` that doesn't close anything.
+        while !self.check(TokenType::Eof) {
+            let error = self.error("a declaration");
+            self.record(error);
+            self.advance();
+            decls.extend(self.parse_members());
+        }
+        Ast {
+            span: Span::new(0, self.src.len()),
+            doc,
+            decls,
+            errors: self.errors,
+        }
+    }
+
+    /// Records an error to be reported once parsing is done. Only the first
+    /// error at any position is kept, as the others are almost always
+    /// caused by it.
+    fn record(&mut self, error: ParseError) {
+        if self.errors.iter().all(|e| e.span.start != error.span.start) {
+            self.errors.push(error);
+        }
+    }
+
+    /// Expects the `;` ending a declaration or statement. A missing `;` is
+    /// recorded without failing, so that parsing goes on with the next statement.
+    fn expect_semicolon(&mut self) {
+        if let Err(error) = self.expect(TokenType::Semicolon, "';'") {
+            self.record(error);
+        }
+    }
+
+    /// Skips ahead to where the next container member probably starts,
+    /// after an error in the current one. Brackets are kept track of so
+    /// that the members of nested containers are skipped too.
+    fn sync_member(&mut self, start: usize) {
+        use KeywordType::*;
+        let mut depth = 0usize;
+        loop {
+            match (self.peek(), self.peek_at(1)) {
+                (
+                    TokenType::Keyword(
+                        Test | Comptime | Pub | Export | Extern | Inline | UsingNamespace
+                        | ThreadLocal | Const | Var | Fn,
+                    ),
+                    _,
+                )
+                | (TokenType::Identifier(_), TokenType::Comma)
+                    if depth == 0 && self.pos > start =>
+                {
+                    return
+                }
+                (TokenType::Comma | TokenType::Semicolon, _) if depth == 0 => {
+                    self.advance();
+                    return;
+                }
+                (TokenType::LParen | TokenType::LBracket | TokenType::LBrace, _) => depth += 1,
+                (TokenType::RParen | TokenType::RBracket, _) => depth = depth.saturating_sub(1),
+                (TokenType::RBrace, _) if depth == 0 => return,
+                (TokenType::RBrace, _) => depth -= 1,
+                (TokenType::Eof, _) => return,
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    /// Skips ahead to the end of the current statement after an error in
+    /// it, which is the next `;` or the `}` of the enclosing block.
+    fn sync_statement(&mut self) {
+        let mut depth = 0usize;
+        loop {
+            match self.peek() {
+                TokenType::LBrace => depth += 1,
+                TokenType::RBrace if depth == 0 => return,
+                TokenType::RBrace => depth -= 1,
+                TokenType::Semicolon if depth == 0 => {
+                    self.advance();
+                    return;
+                }
+                TokenType::Eof => return,
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    fn current(&self) -> &Token<'src> {
+        &self.tokens[self.pos]
+    }
+
+    fn peek(&self) -> &TokenType<'src> {
+        &self.tokens[self.pos].1
+    }
+
+    /// Looks `n` tokens ahead, stopping at the `Eof` token.
+    fn peek_at(&self, n: usize) -> &TokenType<'src> {
+        &self.tokens[(self.pos + n).min(self.tokens.len() - 1)].1
+    }
+
+    fn start(&self) -> usize {
+        self.current().0.start
+    }
+
+    /// The span from `start` up to the end of the last consumed token.
+    fn span_from(&self, start: usize) -> Span {
+        Span::new(start, self.prev_end.max(start))
+    }
+
+    fn advance(&mut self) -> Token<'src> {
+        let token = self.tokens[self.pos].clone();
+        if !matches!(token.1, TokenType::Eof) {
+            self.pos += 1;
+        }
+        self.prev_end = token.0.end;
+        token
+    }
+
+    fn check(&self, token_type: TokenType) -> bool {
+        self.current().is_token_type(token_type)
+    }
+
+    fn check_keyword(&self, keyword: KeywordType) -> bool {
+        matches!(self.peek(), TokenType::Keyword(k) if *k == keyword)
+    }
+
+    fn eat(&mut self, token_type: TokenType) -> Option<Span> {
+        self.check(token_type).then(|| self.advance().0)
+    }
+
+    fn eat_keyword(&mut self, keyword: KeywordType) -> Option<Span> {
+        self.check_keyword(keyword).then(|| self.advance().0)
+    }
+
+    fn expect(&mut self, token_type: TokenType, expected: &'static str) -> ParseResult<Span> {
+        self.eat(token_type).ok_or_else(|| self.error(expected))
+    }
+
+    fn expect_keyword(
+        &mut self,
+        keyword: KeywordType,
+        expected: &'static str,
+    ) -> ParseResult<Span> {
+        self.eat_keyword(keyword)
+            .ok_or_else(|| self.error(expected))
+    }
+
+    fn expect_identifier(&mut self) -> ParseResult<Ident<'src>> {
+        match self.peek() {
+            TokenType::Identifier(name) => {
+                let name = name.clone();
+                let span = self.advance().0;
+                Ok(Ident { span, name })
+            }
+            _ => Err(self.error("an identifier")),
+        }
+    }
+
+    /// Like `expect_identifier`, but primitive type names such as the
+    /// `type` of `field.type` are fine too, as in zig they're identifiers.
+    fn expect_field_name(&mut self) -> ParseResult<Ident<'src>> {
+        match self.peek() {
+            TokenType::PrimitiveType(_) => {
+                let span = self.advance().0;
+                let name = Cow::Borrowed(span.slice(self.src));
+                Ok(Ident { span, name })
+            }
+            _ => self.expect_identifier(),
+        }
+    }
+
+    fn expect_string(&mut self) -> ParseResult<StringLiteral<'src>> {
+        match self.peek() {
+            TokenType::StringLiteral(literal) => {
+                let literal = literal.clone();
+                self.advance();
+                Ok(literal)
+            }
+            _ => Err(self.error("a string literal")),
+        }
+    }
+
+    /// Builds an error saying that `expected` was expected at the current token.
+    fn error(&self, expected: &'static str) -> ParseError {
+        let token = self.current();
+        let found = match token.1 {
+            TokenType::Eof => String::from("end of file"),
+            _ => format!("'{}'", token.0.slice(self.src)),
+        };
+        ParseError::new(token.0, ParseErrorKind::Expected { expected, found })
+    }
+
+    /// Checks if the parser is at the `label:` of a labeled block or loop.
+    fn at_label(&self) -> bool {
+        use KeywordType::*;
+        matches!(
+            (self.peek(), self.peek_at(1), self.peek_at(2)),
+            (
+                TokenType::Identifier(_),
+                TokenType::Colon,
+                TokenType::LBrace | TokenType::Keyword(Inline | While | For)
+            )
+        )
+    }
+
+    fn expr(&self, start: usize, kind: ExprKind<'src>) -> Expr<'src> {
+        Expr {
+            span: self.span_from(start),
+            kind,
+        }
+    }
+
+    fn doc_comments(&mut self) -> Vec<Cow<'src, str>> {
+        let mut doc = Vec::new();
+        while let TokenType::DocComment(text) = self.peek() {
+            doc.push(text.clone());
+            self.advance();
+        }
+        doc
+    }
+
+    fn container_doc_comments(&mut self) -> Vec<Cow<'src, str>> {
+        let mut doc = Vec::new();
+        while let TokenType::ContainerDocComment(text) = self.peek() {
+            doc.push(text.clone());
+            self.advance();
+        }
+        doc
+    }
+
+    /// Parses container members up to a `}` or the end of the file.
+    /// Members with errors in them are skipped and left as `DeclKind::Error`.
+    fn parse_members(&mut self) -> Vec<Decl<'src>> {
+        let mut decls = Vec::new();
+        loop {
+            let doc = self.doc_comments();
+            if matches!(self.peek(), TokenType::RBrace | TokenType::Eof) {
+                if !doc.is_empty() {
+                    let error = self.error("a declaration after the doc comment");
+                    self.record(error);
+                }
+                return decls;
+            }
+
+            let start = self.start();
+            let start_pos = self.pos;
+            let is_pub = self.eat_keyword(KeywordType::Pub).is_some();
+            let kind = match self.parse_member(is_pub) {
+                Ok(kind) => kind,
+                Err(error) => {
+                    self.record(error);
+                    self.sync_member(start_pos);
+                    DeclKind::Error
+                }
+            };
+            decls.push(Decl {
+                span: self.span_from(start),
+                doc,
+                is_pub,
+                kind,
+            });
+        }
+    }
+
+    fn parse_member(&mut self, is_pub: bool) -> ParseResult<DeclKind<'src>> {
+        use KeywordType::*;
+        match self.peek() {
+            TokenType::Keyword(Test) if !is_pub => {
+                self.advance();
+                let start = self.start();
+                let name = match self.peek() {
+                    TokenType::StringLiteral(literal) => {
+                        let kind = ExprKind::String(literal.clone());
+                        self.advance();
+                        Some(self.expr(start, kind))
+                    }
+                    TokenType::Identifier(name) => {
+                        let kind = ExprKind::Identifier(name.clone());
+                        self.advance();
+                        Some(self.expr(start, kind))
+                    }
+                    _ => None,
+                };
+                let body = self.parse_block(None)?;
+                Ok(DeclKind::Test { name, body })
+            }
+            TokenType::Keyword(Comptime)
+                if !is_pub && matches!(self.peek_at(1), TokenType::LBrace) =>
+            {
+                self.advance();
+                Ok(DeclKind::Comptime(self.parse_block(None)?))
+            }
+            TokenType::Keyword(UsingNamespace) => {
+                self.advance();
+                let expr = self.parse_expr()?;
+                self.expect_semicolon();
+                Ok(DeclKind::UsingNamespace(expr))
+            }
+            TokenType::Keyword(Export | Extern | Inline | Fn | Const | Var | ThreadLocal) => {
+                self.parse_decl()
+            }
+            _ if !is_pub => Ok(DeclKind::Field(self.parse_field()?)),
+            _ => Err(self.error("a declaration")),
+        }
+    }
+
+    fn parse_linkage(&mut self) -> Option<Linkage<'src>> {
+        if self.eat_keyword(KeywordType::Export).is_some() {
+            return Some(Linkage::Export);
+        }
+        self.eat_keyword(KeywordType::Extern)?;
+        match self.peek() {
+            TokenType::StringLiteral(library) => {
+                let library = library.clone();
+                self.advance();
+                Some(Linkage::Extern(Some(library)))
+            }
+            _ => Some(Linkage::Extern(None)),
+        }
+    }
+
+    /// Parses a function or variable declaration in a container.
+    fn parse_decl(&mut self) -> ParseResult<DeclKind<'src>> {
+        let linkage = self.parse_linkage();
+        let is_inline = self.eat_keyword(KeywordType::Inline).is_some();
+        if is_inline || self.check_keyword(KeywordType::Fn) {
+            let proto = self.parse_fn_proto()?;
+            let body = match self.eat(TokenType::Semicolon) {
+                Some(_) => None,
+                None => Some(self.parse_block(None)?),
+            };
+            return Ok(DeclKind::Fn(FnDecl {
+                linkage,
+                is_inline,
+                proto,
+                body,
+            }));
+        }
+
+        let is_threadlocal = self.eat_keyword(KeywordType::ThreadLocal).is_some();
+        let mut decl = self.parse_var_decl()?;
+        decl.is_threadlocal = is_threadlocal;
+        decl.linkage = linkage;
+        Ok(DeclKind::Var(decl))
+    }
+
+    /// Parses `const name: T = value;`, including the semicolon.
+    fn parse_var_decl(&mut self) -> ParseResult<VarDecl<'src>> {
+        let start = self.start();
+        let is_const = if self.eat_keyword(KeywordType::Const).is_some() {
+            true
+        } else {
+            self.expect_keyword(KeywordType::Var, "'const' or 'var'")?;
+            false
+        };
+        let name = self.expect_identifier()?;
+        let ty = match self.eat(TokenType::Colon) {
+            Some(_) => Some(self.parse_type_expr()?),
+            None => None,
+        };
+        let align = self.parse_keyword_arg(KeywordType::Align)?;
+        let addrspace = self.parse_keyword_arg(KeywordType::AddrSpace)?;
+        let linksection = self.parse_keyword_arg(KeywordType::LinkSection)?;
+        let value = match self.eat(TokenType::Equal) {
+            Some(_) => Some(self.parse_expr()?),
+            None => None,
+        };
+        self.expect_semicolon();
+        Ok(VarDecl {
+            span: self.span_from(start),
+            is_const,
+            is_comptime: false,
+            is_threadlocal: false,
+            linkage: None,
+            name,
+            ty,
+            align,
+            addrspace,
+            linksection,
+            value,
+        })
+    }
+
+    /// Parses things like `align(4)` and `callconv(.C)` if they are there.
+    fn parse_keyword_arg(&mut self, keyword: KeywordType) -> ParseResult<Option<Expr<'src>>> {
+        if self.eat_keyword(keyword).is_none() {
+            return Ok(None);
+        }
+        self.expect(TokenType::LParen, "'('")?;
+        let expr = self.parse_expr()?;
+        self.expect(TokenType::RParen, "')'")?;
+        Ok(Some(expr))
+    }
+
+    fn parse_fn_proto(&mut self) -> ParseResult<FnProto<'src>> {
+        let start = self.start();
+        self.expect_keyword(KeywordType::Fn, "'fn'")?;
+        let name = match self.peek() {
+            TokenType::Identifier(_) => Some(self.expect_identifier()?),
+            _ => None,
+        };
+
+        self.expect(TokenType::LParen, "'('")?;
+        let mut params = Vec::new();
+        while self.eat(TokenType::RParen).is_none() {
+            params.push(self.parse_param()?);
+            if self.eat(TokenType::Comma).is_none() {
+                self.expect(TokenType::RParen, "',' or ')'")?;
+                break;
+            }
+        }
+
+        let align = self.parse_keyword_arg(KeywordType::Align)?;
+        let addrspace = self.parse_keyword_arg(KeywordType::AddrSpace)?;
+        let linksection = self.parse_keyword_arg(KeywordType::LinkSection)?;
+        let callconv = self.parse_keyword_arg(KeywordType::CallConv)?;
+        let inferred_error = self.eat(TokenType::ExclamationMark).is_some();
+        let return_type = Box::new(self.parse_type_expr()?);
+        Ok(FnProto {
+            span: self.span_from(start),
+            name,
+            params,
+            align,
+            addrspace,
+            linksection,
+            callconv,
+            inferred_error,
+            return_type,
+        })
+    }
+
+    fn parse_param(&mut self) -> ParseResult<Param<'src>> {
+        let doc = self.doc_comments();
+        let start = self.start();
+        let is_comptime = self.eat_keyword(KeywordType::Comptime).is_some();
+        let is_noalias = self.eat_keyword(KeywordType::NoAlias).is_some();
+        let name = match (self.peek(), self.peek_at(1)) {
+            (TokenType::Identifier(_), TokenType::Colon) => {
+                let name = self.expect_identifier()?;
+                self.advance();
+                Some(name)
+            }
+            _ => None,
+        };
+        let ty = if self.eat_keyword(KeywordType::AnyType).is_some() {
+            ParamType::AnyType
+        } else if self.eat(TokenType::Dot3).is_some() {
+            ParamType::VarArgs
+        } else {
+            ParamType::Expr(self.parse_type_expr()?)
+        };
+        Ok(Param {
+            span: self.span_from(start),
+            doc,
+            is_comptime,
+            is_noalias,
+            name,
+            ty,
+        })
+    }
+
+    /// Parses a container field along with the `,` after it,
+    /// which can only be left out for the last field.
+    fn parse_field(&mut self) -> ParseResult<ContainerField<'src>> {
+        let start = self.start();
+        let is_comptime = self.eat_keyword(KeywordType::Comptime).is_some();
+        let (name, ty) = match (self.peek(), self.peek_at(1)) {
+            (TokenType::Identifier(_), TokenType::Colon) => {
+                let name = self.expect_identifier()?;
+                self.advance();
+                (Some(name), Some(self.parse_type_expr()?))
+            }
+            (TokenType::Identifier(_), TokenType::Comma | TokenType::Equal | TokenType::RBrace) => {
+                (Some(self.expect_identifier()?), None)
+            }
+            _ => (None, Some(self.parse_type_expr()?)),
+        };
+        let align = self.parse_keyword_arg(KeywordType::Align)?;
+        let value = match self.eat(TokenType::Equal) {
+            Some(_) => Some(self.parse_expr()?),
+            None => None,
+        };
+        let span = self.span_from(start);
+        if !self.check(TokenType::RBrace) {
+            self.expect(TokenType::Comma, "',' after the field")?;
+        }
+        Ok(ContainerField {
+            span,
+            is_comptime,
+            name,
+            ty,
+            align,
+            value,
+        })
+    }
+
+    /// Parses a block, with the label already consumed by the caller.
+    fn parse_block(&mut self, label: Option<Ident<'src>>) -> ParseResult<Block<'src>> {
+        let start = self.start();
+        self.expect(TokenType::LBrace, "'{'")?;
+        let mut stmts = Vec::new();
+        while self.eat(TokenType::RBrace).is_none() {
+            if self.check(TokenType::Eof) {
+                let error = self.error("'}'");
+                self.record(error);
+                break;
+            }
+            stmts.push(self.parse_statement());
+        }
+        Ok(Block {
+            span: self.span_from(start),
+            label,
+            stmts,
+        })
+    }
+
+    /// Parses a statement. Statements with errors in them are
+    /// skipped and left as an `ExprKind::Error` expression.
+    fn parse_statement(&mut self) -> Stmt<'src> {
+        let start = self.start();
+        let kind = match self.try_parse_statement() {
+            Ok(kind) => kind,
+            Err(error) => {
+                self.record(error);
+                self.sync_statement();
+                StmtKind::Expr(self.expr(start, ExprKind::Error))
+            }
+        };
+        Stmt {
+            span: self.span_from(start),
+            kind,
+        }
+    }
+
+    fn try_parse_statement(&mut self) -> ParseResult<StmtKind<'src>> {
+        use KeywordType::*;
+        Ok(match (self.peek(), self.peek_at(1)) {
+            (TokenType::Keyword(Comptime), TokenType::Keyword(Const | Var)) => {
+                self.advance();
+                let mut decl = self.parse_var_decl()?;
+                decl.is_comptime = true;
+                StmtKind::Var(Box::new(decl))
+            }
+            (TokenType::Keyword(Const | Var), _) => StmtKind::Var(Box::new(self.parse_var_decl()?)),
+            (TokenType::Keyword(Defer), _) => {
+                self.advance();
+                StmtKind::Defer(self.parse_block_expr_statement()?)
+            }
+            (TokenType::Keyword(Errdefer), _) => {
+                self.advance();
+                let capture = self.parse_optional_capture()?;
+                let body = self.parse_block_expr_statement()?;
+                StmtKind::Errdefer { capture, body }
+            }
+            _ => StmtKind::Expr(self.parse_block_expr_statement()?),
+        })
+    }
+
+    /// Parses the expression of an expression statement along with its
+    /// `;`, which isn't needed after statements ending in a block.
+    fn parse_block_expr_statement(&mut self) -> ParseResult<Expr<'src>> {
+        use KeywordType::*;
+        let start = self.start();
+        let expr = match (self.peek(), self.peek_at(1)) {
+            (TokenType::Keyword(If), _) => self.parse_if(true)?,
+            (TokenType::Keyword(Inline | While | For), _) => self.parse_loop(None, true)?,
+            (TokenType::Keyword(Switch), _) => self.parse_switch()?,
+            (TokenType::LBrace, _) => self.parse_labeled(true)?,
+            _ if self.at_label() => self.parse_labeled(true)?,
+            (TokenType::Keyword(Comptime | NoSuspend | Suspend), TokenType::LBrace) => {
+                let keyword = self.advance().1;
+                let block = self.parse_block(None)?;
+                let block = Box::new(self.expr(block.span.start, ExprKind::Block(block)));
+                let kind = match keyword {
+                    TokenType::Keyword(Comptime) => ExprKind::Comptime(block),
+                    TokenType::Keyword(NoSuspend) => ExprKind::NoSuspend(block),
+                    _ => ExprKind::Suspend(block),
+                };
+                self.expr(start, kind)
+            }
+            _ => self.parse_assign_expr()?,
+        };
+        if !ends_with_block(&expr) {
+            self.expect_semicolon();
+        }
+        Ok(expr)
+    }
+
+    /// Parses a labeled or unlabeled block or loop.
+    fn parse_labeled(&mut self, is_statement: bool) -> ParseResult<Expr<'src>> {
+        let start = self.start();
+        let label = match self.at_label() {
+            true => {
+                let label = self.expect_identifier()?;
+                self.advance();
+                Some(label)
+            }
+            false => None,
+        };
+        if self.check(TokenType::LBrace) {
+            let block = self.parse_block(label)?;
+            return Ok(self.expr(start, ExprKind::Block(block)));
+        }
+        let mut expr = self.parse_loop(label, is_statement)?;
+        expr.span = self.span_from(start);
+        Ok(expr)
+    }
+
+    /// Parses an assignment, or just an expression if there is no assignment operator.
+    fn parse_assign_expr(&mut self) -> ParseResult<Expr<'src>> {
+        let start = self.start();
+        let target = self.parse_expr()?;
+        if !self.current().is_assign_op() {
+            return Ok(target);
+        }
+        let op = assign_op(&self.advance().1);
+        let value = self.parse_expr()?;
+        Ok(self.expr(
+            start,
+            ExprKind::Assign {
+                op,
+                target: Box::new(target),
+                value: Box::new(value),
+            },
+        ))
+    }
+
+    fn parse_expr(&mut self) -> ParseResult<Expr<'src>> {
+        self.parse_binary(Precedence::Lowest)
+    }
+
+    /// Parses an expression made of operators binding tighter than `min`,
+    /// by looking up the precedence of every operator in `BINARY_PRECEDENCE`.
+    fn parse_binary(&mut self, min: Precedence) -> ParseResult<Expr<'src>> {
+        let start = self.start();
+        let mut lhs = self.parse_prefix()?;
+        while let Some(precedence) = binary_precedence(self.peek()) {
+            if precedence <= min {
+                break;
+            }
+
+            let op = self.advance().1;
+            if let TokenType::Keyword(KeywordType::Catch) = op {
+                let capture = self.parse_optional_capture()?;
+                let rhs = self.parse_binary(precedence)?;
+                let kind = ExprKind::Catch {
+                    lhs: Box::new(lhs),
+                    capture,
+                    rhs: Box::new(rhs),
+                };
+                lhs = self.expr(start, kind);
+                continue;
+            }
+
+            let rhs = self.parse_binary(precedence)?;
+            lhs = self.expr(
+                start,
+                ExprKind::Binary {
+                    op: binary_op(&op).unwrap(),
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                },
+            );
+            // `a == b == c` is an error rather than `(a == b) == c`.
+            if precedence == Precedence::Compare && self.current().is_compare_op() {
+                return Err(ParseError::new(
+                    self.current().0,
+                    ParseErrorKind::ChainedComparison,
+                ));
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn parse_prefix(&mut self) -> ParseResult<Expr<'src>> {
+        let start = self.start();
+        let op = match self.peek() {
+            TokenType::ExclamationMark => UnaryOp::Not,
+            TokenType::Minus => UnaryOp::Negate,
+            TokenType::MinusPercent => UnaryOp::NegateWrap,
+            TokenType::Tilde => UnaryOp::BitNot,
+            TokenType::Ampersand => UnaryOp::AddressOf,
+            TokenType::Keyword(KeywordType::Try) => UnaryOp::Try,
+            TokenType::Keyword(KeywordType::Await) => UnaryOp::Await,
+            _ => return self.parse_primary(),
+        };
+        self.advance();
+        let expr = Box::new(self.parse_prefix()?);
+        Ok(self.expr(start, ExprKind::Unary { op, expr }))
+    }
+
+    /// Checks if the current token ends an expression, which is how
+    /// `break` and `return` know whether they have an operand.
+    fn at_expr_end(&self) -> bool {
+        matches!(
+            self.peek(),
+            TokenType::Semicolon
+                | TokenType::Comma
+                | TokenType::RParen
+                | TokenType::RBrace
+                | TokenType::RBracket
+                | TokenType::Eof
+                | TokenType::Keyword(KeywordType::Else)
+        )
+    }
+
+    fn parse_break_label(&mut self) -> ParseResult<Option<Ident<'src>>> {
+        match self.eat(TokenType::Colon) {
+            Some(_) => Ok(Some(self.expect_identifier()?)),
+            None => Ok(None),
+        }
+    }
+
+    fn parse_primary(&mut self) -> ParseResult<Expr<'src>> {
+        use KeywordType::*;
+        let start = self.start();
+        match (self.peek(), self.peek_at(1)) {
+            (TokenType::Keyword(Asm), _) => self.parse_asm(),
+            (TokenType::Keyword(If), _) => self.parse_if(false),
+            (TokenType::Keyword(Inline | While | For) | TokenType::LBrace, _) => {
+                self.parse_labeled(false)
+            }
+            _ if self.at_label() => self.parse_labeled(false),
+            (TokenType::Keyword(Break), _) => {
+                self.advance();
+                let label = self.parse_break_label()?;
+                let value = match self.at_expr_end() {
+                    true => None,
+                    false => Some(Box::new(self.parse_expr()?)),
+                };
+                Ok(self.expr(start, ExprKind::Break { label, value }))
+            }
+            (TokenType::Keyword(Continue), _) => {
+                self.advance();
+                let label = self.parse_break_label()?;
+                Ok(self.expr(start, ExprKind::Continue { label }))
+            }
+            (TokenType::Keyword(Return), _) => {
+                self.advance();
+                let value = match self.at_expr_end() {
+                    true => None,
+                    false => Some(Box::new(self.parse_expr()?)),
+                };
+                Ok(self.expr(start, ExprKind::Return(value)))
+            }
+            (TokenType::Keyword(Comptime | NoSuspend | Resume), _) => {
+                let keyword = self.advance().1;
+                let expr = Box::new(self.parse_expr()?);
+                let kind = match keyword {
+                    TokenType::Keyword(Comptime) => ExprKind::Comptime(expr),
+                    TokenType::Keyword(NoSuspend) => ExprKind::NoSuspend(expr),
+                    _ => ExprKind::Resume(expr),
+                };
+                Ok(self.expr(start, kind))
+            }
+            _ => self.parse_curly_suffix(),
+        }
+    }
+
+    /// Parses a type expression that may be followed by an initializer, as in `Point{ .x = 1 }`.
+    fn parse_curly_suffix(&mut self) -> ParseResult<Expr<'src>> {
+        let start = self.start();
+        let ty = self.parse_type_expr()?;
+        if !self.check(TokenType::LBrace) {
+            return Ok(ty);
+        }
+        self.parse_init_list(start, Some(Box::new(ty)))
+    }
+
+    /// Parses the `{ ... }` of an initializer.
+    fn parse_init_list(
+        &mut self,
+        start: usize,
+        ty: Option<Box<Expr<'src>>>,
+    ) -> ParseResult<Expr<'src>> {
+        self.expect(TokenType::LBrace, "'{'")?;
+        let is_struct = matches!(
+            (self.peek(), self.peek_at(1), self.peek_at(2)),
+            (TokenType::RBrace, _, _)
+                | (TokenType::Dot, TokenType::Identifier(_), TokenType::Equal)
+        );
+
+        let mut fields = Vec::new();
+        let mut elems = Vec::new();
+        while self.eat(TokenType::RBrace).is_none() {
+            if is_struct {
+                let start = self.start();
+                self.expect(TokenType::Dot, "'.'")?;
+                let name = self.expect_identifier()?;
+                self.expect(TokenType::Equal, "'='")?;
+                let value = self.parse_expr()?;
+                fields.push(FieldInit {
+                    span: self.span_from(start),
+                    name,
+                    value,
+                });
+            } else {
+                elems.push(self.parse_expr()?);
+            }
+            if self.eat(TokenType::Comma).is_none() {
+                self.expect(TokenType::RBrace, "',' or '}'")?;
+                break;
+            }
+        }
+
+        let kind = match is_struct {
+            true => ExprKind::StructInit { ty, fields },
+            false => ExprKind::ArrayInit { ty, elems },
+        };
+        Ok(self.expr(start, kind))
+    }
+
+    /// Parses a type expression, which can start with prefix
+    /// type operators such as `?`, `*const` or `[]`.
+    fn parse_type_expr(&mut self) -> ParseResult<Expr<'src>> {
+        let start = self.start();
+        match (self.peek(), self.peek_at(1)) {
+            (TokenType::QuestionMark, _) => {
+                self.advance();
+                let child = Box::new(self.parse_type_expr()?);
+                Ok(self.expr(start, ExprKind::Optional(child)))
+            }
+            (TokenType::Keyword(KeywordType::AnyFrame), TokenType::MinusArrow) => {
+                self.advance();
+                self.advance();
+                let child = Box::new(self.parse_type_expr()?);
+                Ok(self.expr(start, ExprKind::AnyFrame(Some(child))))
+            }
+            (TokenType::Asterisk, _) => {
+                self.advance();
+                self.parse_pointer(start, PointerSize::One, None)
+            }
+            (TokenType::Asterisk2, _) => {
+                // `**T` is a pointer to a pointer, but the tokenizer
+                // sees a single `**` token.
+                self.advance();
+                let inner_start = start + 1;
+                let inner = self.parse_pointer(inner_start, PointerSize::One, None)?;
+                let child = Box::new(inner);
+                Ok(self.expr(start, ExprKind::Pointer(pointer(PointerSize::One, child))))
+            }
+            (TokenType::LBracket, _) => self.parse_bracket_type(),
+            _ => self.parse_error_union(),
+        }
+    }
+
+    /// Parses `[]T`, `[*]T`, `[*c]T`, `[N]T` and their sentinel terminated variants.
+    fn parse_bracket_type(&mut self) -> ParseResult<Expr<'src>> {
+        let start = self.start();
+        self.expect(TokenType::LBracket, "'['")?;
+        let size = match (self.peek(), self.peek_at(1), self.peek_at(2)) {
+            (TokenType::Asterisk, TokenType::Identifier(c), TokenType::RBracket) if c == "c" => {
+                self.advance();
+                self.advance();
+                Some(PointerSize::C)
+            }
+            (TokenType::Asterisk, _, _) => {
+                self.advance();
+                Some(PointerSize::Many)
+            }
+            (TokenType::RBracket | TokenType::Colon, _, _) => Some(PointerSize::Slice),
+            _ => None,
+        };
+
+        let len = match size {
+            Some(_) => None,
+            None => Some(Box::new(self.parse_expr()?)),
+        };
+        let sentinel = match self.eat(TokenType::Colon) {
+            Some(_) => Some(Box::new(self.parse_expr()?)),
+            None => None,
+        };
+        self.expect(TokenType::RBracket, "']'")?;
+
+        match (size, len) {
+            (Some(size), _) => self.parse_pointer(start, size, sentinel),
+            (None, Some(len)) => {
+                let child = Box::new(self.parse_type_expr()?);
+                Ok(self.expr(
+                    start,
+                    ExprKind::Array {
+                        len,
+                        sentinel,
+                        child,
+                    },
+                ))
+            }
+            (None, None) => unreachable!(),
+        }
+    }
+
+    /// Parses the qualifiers and child type of a pointer
+    /// type, after the `*` or brackets.
+    fn parse_pointer(
+        &mut self,
+        start: usize,
+        size: PointerSize,
+        sentinel: Option<Box<Expr<'src>>>,
+    ) -> ParseResult<Expr<'src>> {
+        let mut align = None;
+        let mut bit_range = None;
+        let mut addrspace = None;
+        let (mut is_const, mut is_volatile, mut is_allowzero) = (false, false, false);
+        loop {
+            match self.peek() {
+                TokenType::Keyword(KeywordType::Const) => is_const = true,
+                TokenType::Keyword(KeywordType::Volatile) => is_volatile = true,
+                TokenType::Keyword(KeywordType::AllowZero) => is_allowzero = true,
+                TokenType::Keyword(KeywordType::AddrSpace) => {
+                    addrspace = self
+                        .parse_keyword_arg(KeywordType::AddrSpace)?
+                        .map(Box::new);
+                    continue;
+                }
+                TokenType::Keyword(KeywordType::Align) => {
+                    self.advance();
+                    self.expect(TokenType::LParen, "'('")?;
+                    align = Some(Box::new(self.parse_expr()?));
+                    if self.eat(TokenType::Colon).is_some() {
+                        let bit_start = Box::new(self.parse_expr()?);
+                        self.expect(TokenType::Colon, "':'")?;
+                        let bit_end = Box::new(self.parse_expr()?);
+                        bit_range = Some((bit_start, bit_end));
+                    }
+                    self.expect(TokenType::RParen, "')'")?;
+                    continue;
+                }
+                _ => break,
+            }
+            self.advance();
+        }
+        let child = Box::new(self.parse_type_expr()?);
+        Ok(self.expr(
+            start,
+            ExprKind::Pointer(PointerType {
+                size,
+                sentinel,
+                align,
+                bit_range,
+                addrspace,
+                is_const,
+                is_volatile,
+                is_allowzero,
+                child,
+            }),
+        ))
+    }
+
+    fn parse_error_union(&mut self) -> ParseResult<Expr<'src>> {
+        let start = self.start();
+        let error = self.parse_suffix()?;
+        if self.eat(TokenType::ExclamationMark).is_none() {
+            return Ok(error);
+        }
+        let payload = Box::new(self.parse_type_expr()?);
+        Ok(self.expr(
+            start,
+            ExprKind::ErrorUnion {
+                error: Box::new(error),
+                payload,
+            },
+        ))
+    }
+
+    fn parse_args(&mut self) -> ParseResult<Vec<Expr<'src>>> {
+        self.expect(TokenType::LParen, "'('")?;
+        let mut args = Vec::new();
+        while self.eat(TokenType::RParen).is_none() {
+            args.push(self.parse_expr()?);
+            if self.eat(TokenType::Comma).is_none() {
+                self.expect(TokenType::RParen, "',' or ')'")?;
+                break;
+            }
+        }
+        Ok(args)
+    }
+
+    /// Parses a primary type expression followed by any number of
+    /// calls, field accesses, indexing and slicing, `.*` and `.?`.
+    fn parse_suffix(&mut self) -> ParseResult<Expr<'src>> {
+        let start = self.start();
+        if self.eat_keyword(KeywordType::Async).is_some() {
+            let call = Box::new(self.parse_suffix()?);
+            if !matches!(call.kind, ExprKind::Call { .. }) {
+                return Err(self.error("a function call after 'async'"));
+            }
+            return Ok(self.expr(start, ExprKind::Async(call)));
+        }
+
+        let mut expr = self.parse_primary_type()?;
+        loop {
+            let kind = match self.peek() {
+                TokenType::LParen => {
+                    let args = self.parse_args()?;
+                    ExprKind::Call {
+                        callee: Box::new(expr),
+                        args,
+                    }
+                }
+                TokenType::Dot => {
+                    self.advance();
+                    let name = self.expect_field_name()?;
+                    ExprKind::Field {
+                        expr: Box::new(expr),
+                        name,
+                    }
+                }
+                TokenType::DotAsterisk => {
+                    self.advance();
+                    ExprKind::Deref(Box::new(expr))
+                }
+                TokenType::DotQuestionMark => {
+                    self.advance();
+                    ExprKind::Unwrap(Box::new(expr))
+                }
+                TokenType::LBracket => {
+                    self.advance();
+                    let index = Box::new(self.parse_expr()?);
+                    let kind = if self.eat(TokenType::Dot2).is_some() {
+                        let end =
+                            match matches!(self.peek(), TokenType::RBracket | TokenType::Colon) {
+                                true => None,
+                                false => Some(Box::new(self.parse_expr()?)),
+                            };
+                        let sentinel = match self.eat(TokenType::Colon) {
+                            Some(_) => Some(Box::new(self.parse_expr()?)),
+                            None => None,
+                        };
+                        ExprKind::Slice {
+                            expr: Box::new(expr),
+                            start: index,
+                            end,
+                            sentinel,
+                        }
+                    } else {
+                        ExprKind::Index {
+                            expr: Box::new(expr),
+                            index,
+                        }
+                    };
+                    self.expect(TokenType::RBracket, "']'")?;
+                    kind
+                }
+                _ => return Ok(expr),
+            };
+            expr = self.expr(start, kind);
+        }
+    }
+
+    fn parse_primary_type(&mut self) -> ParseResult<Expr<'src>> {
+        use KeywordType::*;
+        let start = self.start();
+        let kind = match (self.peek(), self.peek_at(1)) {
+            (TokenType::Integer(value), _) => ExprKind::Integer(value.clone()),
+            (TokenType::Float(value), _) => ExprKind::Float(*value),
+            (TokenType::Char(value), _) => ExprKind::Char(*value),
+            (TokenType::StringLiteral(literal), _) => ExprKind::String(literal.clone()),
+            (TokenType::MultilineStringLiteral(literal), _) => {
+                ExprKind::MultilineString(literal.clone())
+            }
+            _ if self.at_label() => return self.parse_labeled(false),
+            (TokenType::Identifier(name), _) => ExprKind::Identifier(name.clone()),
+            (TokenType::PrimitiveType(ty), _) => ExprKind::PrimitiveType(*ty),
+            (TokenType::Keyword(Unreachable), _) => ExprKind::Unreachable,
+            (TokenType::Keyword(AnyFrame), _) => ExprKind::AnyFrame(None),
+            (TokenType::Builtin(name), _) => {
+                let name = name.clone();
+                self.advance();
+                let args = self.parse_args()?;
+                return Ok(self.expr(start, ExprKind::Builtin { name, args }));
+            }
+            (TokenType::Dot, TokenType::Identifier(_)) => {
+                self.advance();
+                let name = self.expect_identifier()?;
+                return Ok(self.expr(start, ExprKind::EnumLiteral(name.name)));
+            }
+            (TokenType::Dot, TokenType::LBrace) => {
+                self.advance();
+                return self.parse_init_list(start, None);
+            }
+            (TokenType::Keyword(Error), TokenType::Dot) => {
+                self.advance();
+                self.advance();
+                let name = self.expect_identifier()?;
+                return Ok(self.expr(start, ExprKind::ErrorValue(name.name)));
+            }
+            (TokenType::Keyword(Error), TokenType::LBrace) => return self.parse_error_set(),
+            (TokenType::Keyword(Fn), _) => {
+                let proto = self.parse_fn_proto()?;
+                return Ok(self.expr(start, ExprKind::FnProto(Box::new(proto))));
+            }
+            (TokenType::Keyword(Struct | Enum | Union | Opaque | Packed | Extern), _) => {
+                return self.parse_container_decl();
+            }
+            (TokenType::LParen, _) => {
+                self.advance();
+                let expr = Box::new(self.parse_expr()?);
+                self.expect(TokenType::RParen, "')'")?;
+                return Ok(self.expr(start, ExprKind::Grouped(expr)));
+            }
+            (TokenType::Keyword(If), _) => return self.parse_if(false),
+            (TokenType::Keyword(Switch), _) => return self.parse_switch(),
+            (TokenType::Keyword(Inline | While | For), _) => return self.parse_loop(None, false),
+            (TokenType::Keyword(Comptime), _) => {
+                self.advance();
+                let expr = Box::new(self.parse_type_expr()?);
+                return Ok(self.expr(start, ExprKind::Comptime(expr)));
+            }
+            _ => return Err(self.error("an expression")),
+        };
+        self.advance();
+        Ok(self.expr(start, kind))
+    }
+
+    fn parse_error_set(&mut self) -> ParseResult<Expr<'src>> {
+        let start = self.start();
+        self.expect_keyword(KeywordType::Error, "'error'")?;
+        self.expect(TokenType::LBrace, "'{'")?;
+        let mut names = Vec::new();
+        loop {
+            self.doc_comments();
+            if self.eat(TokenType::RBrace).is_some() {
+                break;
+            }
+            names.push(self.expect_identifier()?);
+            if self.eat(TokenType::Comma).is_none() {
+                self.expect(TokenType::RBrace, "',' or '}'")?;
+                break;
+            }
+        }
+        Ok(self.expr(start, ExprKind::ErrorSet(names)))
+    }
+
+    fn parse_container_decl(&mut self) -> ParseResult<Expr<'src>> {
+        use KeywordType::*;
+        let start = self.start();
+        let layout = if self.eat_keyword(Packed).is_some() {
+            ContainerLayout::Packed
+        } else if self.eat_keyword(Extern).is_some() {
+            ContainerLayout::Extern
+        } else {
+            ContainerLayout::Auto
+        };
+        let kind = match self.peek() {
+            TokenType::Keyword(Struct) => ContainerKind::Struct,
+            TokenType::Keyword(Enum) => ContainerKind::Enum,
+            TokenType::Keyword(Union) => ContainerKind::Union,
+            TokenType::Keyword(Opaque) => ContainerKind::Opaque,
+            _ => return Err(self.error("'struct', 'enum', 'union' or 'opaque'")),
+        };
+        self.advance();
+
+        let arg = if self.eat(TokenType::LParen).is_some() {
+            let arg = match (kind, self.peek()) {
+                (ContainerKind::Union, TokenType::Keyword(Enum)) => {
+                    self.advance();
+                    let tag = match self.eat(TokenType::LParen) {
+                        Some(_) => {
+                            let tag = self.parse_expr()?;
+                            self.expect(TokenType::RParen, "')'")?;
+                            Some(Box::new(tag))
+                        }
+                        None => None,
+                    };
+                    ContainerArg::TaggedUnion(tag)
+                }
+                _ => ContainerArg::Type(Box::new(self.parse_expr()?)),
+            };
+            self.expect(TokenType::RParen, "')'")?;
+            Some(arg)
+        } else {
+            None
+        };
+
+        self.expect(TokenType::LBrace, "'{'")?;
+        let doc = self.container_doc_comments();
+        let decls = self.parse_members();
+        self.expect(TokenType::RBrace, "'}'")?;
+        Ok(self.expr(
+            start,
+            ExprKind::Container(ContainerDecl {
+                kind,
+                layout,
+                arg,
+                doc,
+                decls,
+            }),
+        ))
+    }
+
+    fn parse_asm(&mut self) -> ParseResult<Expr<'src>> {
+        let start = self.start();
+        self.expect_keyword(KeywordType::Asm, "'asm'")?;
+        let is_volatile = self.eat_keyword(KeywordType::Volatile).is_some();
+        self.expect(TokenType::LParen, "'('")?;
+        let template = Box::new(self.parse_expr()?);
+
+        // The outputs, inputs and clobbers are all optional, but each
+        // section needs the `:`s of the sections before it.
+        let mut outputs = Vec::new();
+        let mut inputs = Vec::new();
+        let mut clobbers = Vec::new();
+        if self.eat(TokenType::Colon).is_some() {
+            while matches!(self.peek(), TokenType::LBracket) {
+                let (start, name, constraint) = self.parse_asm_operand_start()?;
+                let kind = match self.eat(TokenType::MinusArrow) {
+                    Some(_) => AsmOutputKind::Return(Box::new(self.parse_type_expr()?)),
+                    None => AsmOutputKind::Var(self.expect_identifier()?),
+                };
+                self.expect(TokenType::RParen, "')'")?;
+                outputs.push(AsmOutput {
+                    span: self.span_from(start),
+                    name,
+                    constraint,
+                    kind,
+                });
+                if self.eat(TokenType::Comma).is_none() {
+                    break;
+                }
+            }
+        }
+        if self.eat(TokenType::Colon).is_some() {
+            while matches!(self.peek(), TokenType::LBracket) {
+                let (start, name, constraint) = self.parse_asm_operand_start()?;
+                let expr = self.parse_expr()?;
+                self.expect(TokenType::RParen, "')'")?;
+                inputs.push(AsmInput {
+                    span: self.span_from(start),
+                    name,
+                    constraint,
+                    expr,
+                });
+                if self.eat(TokenType::Comma).is_none() {
+                    break;
+                }
+            }
+        }
+        if self.eat(TokenType::Colon).is_some() {
+            while matches!(self.peek(), TokenType::StringLiteral(_)) {
+                clobbers.push(self.expect_string()?);
+                if self.eat(TokenType::Comma).is_none() {
+                    break;
+                }
+            }
+        }
+        self.expect(TokenType::RParen, "')'")?;
+        Ok(self.expr(
+            start,
+            ExprKind::Asm(Asm {
+                is_volatile,
+                template,
+                outputs,
+                inputs,
+                clobbers,
+            }),
+        ))
+    }
+
+    /// Parses the `[name] "constraint" (` that asm outputs and inputs start with.
+    fn parse_asm_operand_start(
+        &mut self,
+    ) -> ParseResult<(usize, Ident<'src>, StringLiteral<'src>)> {
+        let start = self.start();
+        self.expect(TokenType::LBracket, "'['")?;
+        let name = self.expect_identifier()?;
+        self.expect(TokenType::RBracket, "']'")?;
+        let constraint = self.expect_string()?;
+        self.expect(TokenType::LParen, "'('")?;
+        Ok((start, name, constraint))
+    }
+
+    /// Parses `|x|`, `|*x|` and `|x, i|` captures.
+    fn parse_captures(&mut self) -> ParseResult<Vec<Capture<'src>>> {
+        self.expect(TokenType::Pipe, "'|'")?;
+        let mut captures = Vec::new();
+        loop {
+            let start = self.start();
+            let is_pointer = self.eat(TokenType::Asterisk).is_some();
+            let name = self.expect_identifier()?;
+            captures.push(Capture {
+                span: self.span_from(start),
+                is_pointer,
+                name,
+            });
+            if self.eat(TokenType::Comma).is_none() {
+                self.expect(TokenType::Pipe, "',' or '|'")?;
+                return Ok(captures);
+            }
+        }
+    }
+
+    fn parse_capture(&mut self) -> ParseResult<Capture<'src>> {
+        let mut captures = self.parse_captures()?;
+        if captures.len() > 1 {
+            return Err(self.error("a single capture"));
+        }
+        Ok(captures.remove(0))
+    }
+
+    fn parse_optional_capture(&mut self) -> ParseResult<Option<Capture<'src>>> {
+        match self.check(TokenType::Pipe) {
+            true => Ok(Some(self.parse_capture()?)),
+            false => Ok(None),
+        }
+    }
+
+    /// Parses the body of an `if`, loop or switch prong. In statements the
+    /// bodies can be assignments, while in expressions they can't.
+    fn parse_body(&mut self, is_statement: bool) -> ParseResult<Expr<'src>> {
+        match is_statement {
+            true => self.parse_assign_expr(),
+            false => self.parse_expr(),
+        }
+    }
+
+    fn parse_condition(&mut self) -> ParseResult<Box<Expr<'src>>> {
+        self.expect(TokenType::LParen, "'('")?;
+        let cond = self.parse_expr()?;
+        self.expect(TokenType::RParen, "')'")?;
+        Ok(Box::new(cond))
+    }
+
+    fn parse_if(&mut self, is_statement: bool) -> ParseResult<Expr<'src>> {
+        let start = self.start();
+        self.expect_keyword(KeywordType::If, "'if'")?;
+        let cond = self.parse_condition()?;
+        let capture = self.parse_optional_capture()?;
+        let then = Box::new(self.parse_body(is_statement)?);
+
+        let (else_capture, else_branch) = match self.eat_keyword(KeywordType::Else) {
+            Some(_) => {
+                let capture = self.parse_optional_capture()?;
+                (capture, Some(Box::new(self.parse_body(is_statement)?)))
+            }
+            None => (None, None),
+        };
+        Ok(self.expr(
+            start,
+            ExprKind::If(Box::new(If {
+                cond,
+                capture,
+                then,
+                else_capture,
+                else_branch,
+            })),
+        ))
+    }
+
+    /// Parses a `while` or `for` loop, with the label already consumed.
+    fn parse_loop(
+        &mut self,
+        label: Option<Ident<'src>>,
+        is_statement: bool,
+    ) -> ParseResult<Expr<'src>> {
+        let start = self.start();
+        let is_inline = self.eat_keyword(KeywordType::Inline).is_some();
+        if self.eat_keyword(KeywordType::For).is_some() {
+            self.expect(TokenType::LParen, "'('")?;
+            let mut inputs = Vec::new();
+            while self.eat(TokenType::RParen).is_none() {
+                let input_start = self.start();
+                let input = self.parse_expr()?;
+                let input = match self.eat(TokenType::Dot2) {
+                    Some(_) => {
+                        let end = match matches!(self.peek(), TokenType::RParen | TokenType::Comma)
+                        {
+                            true => None,
+                            false => Some(Box::new(self.parse_expr()?)),
+                        };
+                        let start = Box::new(input);
+                        self.expr(input_start, ExprKind::Range { start, end })
+                    }
+                    None => input,
+                };
+                inputs.push(input);
+                if self.eat(TokenType::Comma).is_none() {
+                    self.expect(TokenType::RParen, "',' or ')'")?;
+                    break;
+                }
+            }
+            let captures = self.parse_captures()?;
+            let body = Box::new(self.parse_body(is_statement)?);
+            let else_branch = match self.eat_keyword(KeywordType::Else) {
+                Some(_) => Some(Box::new(self.parse_body(is_statement)?)),
+                None => None,
+            };
+            return Ok(self.expr(
+                start,
+                ExprKind::For(Box::new(For {
+                    label,
+                    is_inline,
+                    inputs,
+                    captures,
+                    body,
+                    else_branch,
+                })),
+            ));
+        }
+
+        self.expect_keyword(KeywordType::While, "'while' or 'for'")?;
+        let cond = self.parse_condition()?;
+        let capture = self.parse_optional_capture()?;
+        let cont = match self.eat(TokenType::Colon) {
+            Some(_) => {
+                self.expect(TokenType::LParen, "'('")?;
+                let cont = self.parse_assign_expr()?;
+                self.expect(TokenType::RParen, "')'")?;
+                Some(Box::new(cont))
+            }
+            None => None,
+        };
+        let body = Box::new(self.parse_body(is_statement)?);
+        let (else_capture, else_branch) = match self.eat_keyword(KeywordType::Else) {
+            Some(_) => {
+                let capture = self.parse_optional_capture()?;
+                (capture, Some(Box::new(self.parse_body(is_statement)?)))
+            }
+            None => (None, None),
+        };
+        Ok(self.expr(
+            start,
+            ExprKind::While(Box::new(While {
+                label,
+                is_inline,
+                cond,
+                capture,
+                cont,
+                body,
+                else_capture,
+                else_branch,
+            })),
+        ))
+    }
+
+    fn parse_switch(&mut self) -> ParseResult<Expr<'src>> {
+        let start = self.start();
+        self.expect_keyword(KeywordType::Switch, "'switch'")?;
+        let cond = self.parse_condition()?;
+        self.expect(TokenType::LBrace, "'{'")?;
+        let mut prongs = Vec::new();
+        while self.eat(TokenType::RBrace).is_none() {
+            prongs.push(self.parse_switch_prong()?);
+            if self.eat(TokenType::Comma).is_none() {
+                self.expect(TokenType::RBrace, "',' or '}'")?;
+                break;
+            }
+        }
+        Ok(self.expr(start, ExprKind::Switch(Switch { cond, prongs })))
+    }
+
+    fn parse_switch_prong(&mut self) -> ParseResult<SwitchProng<'src>> {
+        let prong_start = self.start();
+        let is_inline = self.eat_keyword(KeywordType::Inline).is_some();
+        let is_else = self.eat_keyword(KeywordType::Else).is_some();
+        let mut items = Vec::new();
+        while !is_else && !self.check(TokenType::EqualArrow) {
+            let start = self.start();
+            let item = self.parse_expr()?;
+            let item = match self.eat(TokenType::Dot3) {
+                Some(_) => {
+                    let end = Some(Box::new(self.parse_expr()?));
+                    let start_expr = Box::new(item);
+                    self.expr(
+                        start,
+                        ExprKind::Range {
+                            start: start_expr,
+                            end,
+                        },
+                    )
+                }
+                None => item,
+            };
+            items.push(item);
+            if self.eat(TokenType::Comma).is_none() {
+                break;
+            }
+        }
+        self.expect(TokenType::EqualArrow, "'=>'")?;
+        let captures = match self.check(TokenType::Pipe) {
+            true => self.parse_captures()?,
+            false => Vec::new(),
+        };
+        let body = self.parse_assign_expr()?;
+        Ok(SwitchProng {
+            span: self.span_from(prong_start),
+            is_inline,
+            items,
+            is_else,
+            captures,
+            body,
+        })
+    }
+}
+
+/// Parses `src` as a zig source file. See `Parser::parse`.
+pub fn parse(src: &str) -> Ast<'_> {
+    Parser::new(src).parse()
+}
+
+/// Parses `src` as a single expression, such as `1 + 2 * x`. Unlike
+/// whole files, expressions aren't recovered from errors, so either the
+/// expression or every error found in it is returned.
+pub fn parse_expr(src: &str) -> Result<Expr<'_>, Vec<ParseError>> {
+    let mut parser = Parser::new(src);
+    let expr = parser
+        .parse_expr()
+        .and_then(|expr| match parser.check(TokenType::Eof) {
+            true => Ok(expr),
+            false => Err(parser.error("the end of the expression")),
+        });
+    match expr {
+        Ok(expr) if parser.errors.is_empty() => Ok(expr),
+        Ok(_) => Err(parser.errors),
+        Err(error) => {
+            parser.record(error);
+            Err(parser.errors)
+        }
+    }
+}
+
+/// Parses `src` as a single statement, such as `var x: u8 = 1;` or
+/// `x += 1;`. Like `parse_expr`, either the statement or every error
+/// found in it is returned.
+pub fn parse_stmt(src: &str) -> Result<Stmt<'_>, Vec<ParseError>> {
+    let mut parser = Parser::new(src);
+    let stmt = parser.parse_statement();
+    if !parser.check(TokenType::Eof) {
+        let error = parser.error("the end of the statement");
+        parser.record(error);
+    }
+    match parser.errors.is_empty() {
+        true => Ok(stmt),
+        false => Err(parser.errors),
+    }
+}
+
+/// Builds a pointer type with no qualifiers.
+fn pointer<'src>(size: PointerSize, child: Box<Expr<'src>>) -> PointerType<'src> {
+    PointerType {
+        size,
+        sentinel: None,
+        align: None,
+        bit_range: None,
+        addrspace: None,
+        is_const: false,
+        is_volatile: false,
+        is_allowzero: false,
+        child,
+    }
+}
+
+/// Checks if an expression statement ends with a block,
+/// in which case it doesn't need a `;` after it.
+fn ends_with_block(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Block(_) | ExprKind::Switch(_) => true,
+        ExprKind::If(expr) => ends_with_block(expr.else_branch.as_ref().unwrap_or(&expr.then)),
+        ExprKind::While(expr) => ends_with_block(expr.else_branch.as_ref().unwrap_or(&expr.body)),
+        ExprKind::For(expr) => ends_with_block(expr.else_branch.as_ref().unwrap_or(&expr.body)),
+        ExprKind::Comptime(expr) | ExprKind::NoSuspend(expr) | ExprKind::Suspend(expr) => {
+            ends_with_block(expr)
+        }
+        _ => false,
+    }
+}
+
+fn binary_op(token_type: &TokenType) -> Option<BinaryOp> {
+    use TokenType::*;
+    Some(match token_type {
+        Keyword(KeywordType::Or) => BinaryOp::Or,
+        Keyword(KeywordType::And) => BinaryOp::And,
+        EqualEqual => BinaryOp::Equal,
+        ExclamationMarkEqual => BinaryOp::NotEqual,
+        LArrow => BinaryOp::Less,
+        RArrow => BinaryOp::Greater,
+        LArrowEqual => BinaryOp::LessEqual,
+        RArrowEqual => BinaryOp::GreaterEqual,
+        Ampersand => BinaryOp::BitAnd,
+        Caret => BinaryOp::BitXor,
+        Pipe => BinaryOp::BitOr,
+        Keyword(KeywordType::OrElse) => BinaryOp::OrElse,
+        LArrow2 => BinaryOp::Shl,
+        RArrow2 => BinaryOp::Shr,
+        LArrow2Pipe => BinaryOp::ShlSat,
+        Plus => BinaryOp::Add,
+        Minus => BinaryOp::Sub,
+        Plus2 => BinaryOp::Concat,
+        PlusPercent => BinaryOp::AddWrap,
+        MinusPercent => BinaryOp::SubWrap,
+        PlusPipe => BinaryOp::AddSat,
+        MinusPipe => BinaryOp::SubSat,
+        Asterisk => BinaryOp::Mul,
+        Slash => BinaryOp::Div,
+        Percent => BinaryOp::Mod,
+        Asterisk2 => BinaryOp::Repeat,
+        AsteriskPercent => BinaryOp::MulWrap,
+        AsteriskPipe => BinaryOp::MulSat,
+        Pipe2 => BinaryOp::MergeErrorSets,
+        _ => return None,
+    })
+}
+
+/// Maps a token for which `Token::is_assign_op` holds to its operator.
+fn assign_op(token_type: &TokenType) -> AssignOp {
+    use TokenType::*;
+    match token_type {
+        AsteriskEqual => AssignOp::Mul,
+        AsteriskPercentEqual => AssignOp::MulWrap,
+        AsteriskPipeEqual => AssignOp::MulSat,
+        SlashEqual => AssignOp::Div,
+        PercentEqual => AssignOp::Mod,
+        PlusEqual => AssignOp::Add,
+        PlusPercentEqual => AssignOp::AddWrap,
+        PlusPipeEqual => AssignOp::AddSat,
+        MinusEqual => AssignOp::Sub,
+        MinusPercentEqual => AssignOp::SubWrap,
+        MinusPipeEqual => AssignOp::SubSat,
+        LArrow2Equal => AssignOp::Shl,
+        LArrow2PipeEqual => AssignOp::ShlSat,
+        RArrow2Equal => AssignOp::Shr,
+        AmpersandEqual => AssignOp::BitAnd,
+        CaretEqual => AssignOp::BitXor,
+        PipeEqual => AssignOp::BitOr,
+        _ => AssignOp::Assign,
+    }
+}
@@ -0,0 +1,75 @@
+use crate::tokenizer::{KeywordType, TokenType};
+
+/// How tightly a binary operator binds, from loosest to tightest.
+/// Operators of the same precedence are left associative, except
+/// for comparisons, which can't be chained at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Precedence {
+    /// Looser than any operator, used to parse a whole expression.
+    Lowest,
+    /// `or`
+    Or,
+    /// `and`
+    And,
+    /// `== != < > <= >=`
+    Compare,
+    /// `& ^ | orelse catch`
+    Bitwise,
+    /// `<< >> <<|`
+    Shift,
+    /// `+ - ++ +% -% +| -|`
+    Additive,
+    /// `|| * / % ** *% *|`
+    Multiplicative,
+    /// Prefix operators such as `!`, `-` and `try`, which bind
+    /// tighter than any binary operator.
+    Prefix,
+}
+
+/// The precedence of every binary operator, following the
+/// precedence table in the zig language reference.
+pub static BINARY_PRECEDENCE: [(TokenType<'static>, Precedence); 30] = {
+    use Precedence::*;
+    use TokenType::*;
+    [
+        (Keyword(KeywordType::Or), Precedence::Or),
+        (Keyword(KeywordType::And), Precedence::And),
+        (EqualEqual, Compare),
+        (ExclamationMarkEqual, Compare),
+        (LArrow, Compare),
+        (RArrow, Compare),
+        (LArrowEqual, Compare),
+        (RArrowEqual, Compare),
+        (Ampersand, Bitwise),
+        (Caret, Bitwise),
+        (Pipe, Bitwise),
+        (Keyword(KeywordType::OrElse), Bitwise),
+        (Keyword(KeywordType::Catch), Bitwise),
+        (LArrow2, Shift),
+        (RArrow2, Shift),
+        (LArrow2Pipe, Shift),
+        (Plus, Additive),
+        (Minus, Additive),
+        (Plus2, Additive),
+        (PlusPercent, Additive),
+        (MinusPercent, Additive),
+        (PlusPipe, Additive),
+        (MinusPipe, Additive),
+        (Pipe2, Multiplicative),
+        (Asterisk, Multiplicative),
+        (Slash, Multiplicative),
+        (Percent, Multiplicative),
+        (Asterisk2, Multiplicative),
+        (AsteriskPercent, Multiplicative),
+        (AsteriskPipe, Multiplicative),
+    ]
+};
+
+/// Looks up the precedence of `token_type` as a binary operator.
+/// Returns `None` for tokens that aren't binary operators.
+pub fn binary_precedence(token_type: &TokenType) -> Option<Precedence> {
+    BINARY_PRECEDENCE
+        .iter()
+        .find(|(operator, _)| operator == token_type)
+        .map(|&(_, precedence)| precedence)
+}
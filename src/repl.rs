@@ -0,0 +1,436 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::{
+    ast::{Ast, DeclKind, Expr, ExprKind, Stmt},
+    diagnostic::Diagnostic,
+    interp::{EvalError, Interpreter, Value},
+    parser::{self, ParseError},
+    source::{FileId, SourceMap},
+    tokenizer::{TokenType, Tokenizer},
+};
+
+const HELP: &str = "\
+Enter zig declarations, statements or expressions. Input is read until
+its braces are balanced, so blocks can span several lines.
+
+  :mode [tokens|ast|eval]  show the tokens, the syntax tree or the result
+  :names                   list what has been declared so far
+  :reset                   forget everything declared so far
+  :history                 list the inputs so far
+  !!, !<n>                 run the last input, or input <n>, again
+  :help                    show this
+  :quit                    exit, as does ctrl-d
+";
+
+/// What the REPL shows for each input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Tokens,
+    Ast,
+    Eval,
+}
+
+impl Mode {
+    fn name(self) -> &'static str {
+        match self {
+            Mode::Tokens => "tokens",
+            Mode::Ast => "ast",
+            Mode::Eval => "eval",
+        }
+    }
+}
+
+/// An input, by what it parsed as.
+enum Input<'src> {
+    Decls(Ast<'src>),
+    Expr(Expr<'src>),
+    Stmt(Stmt<'src>),
+}
+
+/// What the REPL writes in response to an input. `out` is meant for
+/// stdout, and `err` for stderr, which also gets what the program printed
+/// with `std.debug.print`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Output {
+    pub out: String,
+    pub err: String,
+}
+
+/// An interactive session, which is given its input a line at a time.
+/// Declarations and variables are kept from one input to the next.
+///
+/// ```
+/// use rusty_zig::repl::Repl;
+///
+/// let mut repl = Repl::new();
+/// assert_eq!(repl.line("fn double(x: u32) u32 {"), None);
+/// assert_eq!(repl.prompt(), "... ");
+/// repl.line("    return x * 2;");
+/// repl.line("}");
+/// let output = repl.line("double(21)").unwrap();
+/// assert_eq!(output.out, "42 (u32)\n");
+/// ```
+pub struct Repl {
+    mode: Mode,
+    /// Borrows from `inputs`, so it's declared first to be dropped first.
+    interpreter: Interpreter<'static>,
+    /// Every input evaluated since the last `:reset`, each in a file of its
+    /// own, so that errors in code from earlier inputs can be shown.
+    inputs: SourceMap,
+    /// The input each top level name was last declared in.
+    declared_in: HashMap<String, FileId>,
+    history: Vec<String>,
+    /// The lines of an input whose brackets aren't closed yet.
+    pending: String,
+    output: Output,
+    color: bool,
+    quit: bool,
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Self {
+            mode: Mode::Eval,
+            interpreter: Interpreter::new().capture_output(true),
+            inputs: SourceMap::new(),
+            declared_in: HashMap::new(),
+            history: Vec::new(),
+            pending: String::new(),
+            output: Output::default(),
+            color: false,
+            quit: false,
+        }
+    }
+
+    /// Colors the diagnostics with ANSI escape codes.
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// What to prompt for the next line with, which shows whether it
+    /// starts a new input or continues the last one.
+    pub fn prompt(&self) -> &'static str {
+        match self.pending.is_empty() {
+            true => "> ",
+            false => "... ",
+        }
+    }
+
+    /// Whether `:quit` was entered.
+    pub fn has_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Takes a line of input. An input is read until there are as many
+    /// closing braces, brackets and parentheses as opening ones, and until
+    /// then `None` is returned.
+    pub fn line(&mut self, line: &str) -> Option<Output> {
+        self.pending.push_str(line);
+        self.pending.push('\n');
+        if depth(&self.pending) > 0 {
+            return None;
+        }
+        self.finish()
+    }
+
+    /// Handles what's left of the input once there are no more lines,
+    /// even if its brackets aren't closed.
+    pub fn finish(&mut self) -> Option<Output> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let input = std::mem::take(&mut self.pending);
+        self.command(input.trim());
+        Some(std::mem::take(&mut self.output))
+    }
+
+    /// Handles an input.
+    fn command(&mut self, input: &str) {
+        let input = match self.recall(input) {
+            Some(input) => input,
+            None => return,
+        };
+        let (command, arg) = input.split_once(' ').unwrap_or((&input, ""));
+        match (command, arg.trim()) {
+            ("", _) => {}
+            (":q" | ":quit", _) => self.quit = true,
+            (":h" | ":help", _) => self.output.out.push_str(HELP),
+            (":history", _) => {
+                for (i, input) in self.history.iter().enumerate() {
+                    let input = input.trim_end().replace('\n', "\n      ");
+                    let _ = writeln!(self.output.out, "{:>4}  {input}", i + 1);
+                }
+            }
+            (":names", _) => {
+                let names = self.interpreter.names().join(" ");
+                let _ = writeln!(self.output.out, "{names}");
+            }
+            (":reset", _) => {
+                self.interpreter = Interpreter::new().capture_output(true);
+                self.inputs = SourceMap::new();
+                self.declared_in.clear();
+                let _ = writeln!(self.output.out, "forgot everything declared so far");
+            }
+            (":mode", "") => {
+                let _ = writeln!(self.output.out, "{}", self.mode.name());
+            }
+            (":mode", mode) => match mode {
+                "tokens" => self.mode = Mode::Tokens,
+                "ast" => self.mode = Mode::Ast,
+                "eval" => self.mode = Mode::Eval,
+                _ => {
+                    let _ = writeln!(
+                        self.output.err,
+                        "error: unknown mode '{mode}', expected tokens, ast or eval"
+                    );
+                }
+            },
+            _ if command.starts_with(':') => {
+                let _ = writeln!(
+                    self.output.err,
+                    "error: unknown command '{command}', see :help"
+                );
+            }
+            _ => {
+                self.history.push(input.clone());
+                match self.mode {
+                    Mode::Tokens => self.tokens(&input),
+                    Mode::Ast => self.ast(&input),
+                    Mode::Eval => self.eval(&input),
+                }
+            }
+        }
+    }
+
+    /// Expands `!!` and `!<n>` to an input from the history.
+    fn recall(&mut self, input: &str) -> Option<String> {
+        let entry = match input.strip_prefix('!') {
+            Some("!") => self.history.last(),
+            Some(n) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => n
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| self.history.get(n.checked_sub(1)?)),
+            _ => return Some(input.to_string()),
+        };
+        match entry {
+            Some(entry) => {
+                let _ = writeln!(self.output.out, "{}", entry.trim_end());
+                Some(entry.trim().to_string())
+            }
+            None => {
+                let _ = writeln!(self.output.err, "error: no input {input} in the history");
+                None
+            }
+        }
+    }
+
+    fn tokens(&mut self, input: &str) {
+        for result in Tokenizer::new(input).results() {
+            match result {
+                Ok(token) => {
+                    let _ = writeln!(self.output.out, "{token:?}");
+                }
+                Err(error) => self.report(&Diagnostic::from(&error), input),
+            }
+        }
+    }
+
+    fn ast(&mut self, input: &str) {
+        let with_semicolon = format!("{input};");
+        let tree = match parse(input, &with_semicolon) {
+            Ok(Input::Decls(ast)) => format!("{:#?}", ast.decls),
+            Ok(Input::Expr(expr)) => format!("{expr:#?}"),
+            Ok(Input::Stmt(stmt)) => format!("{stmt:#?}"),
+            Err(errors) => {
+                for error in &errors {
+                    self.report(&Diagnostic::from(error), input);
+                }
+                return;
+            }
+        };
+        let _ = writeln!(self.output.out, "{tree}");
+    }
+
+    fn eval(&mut self, input: &str) {
+        // A `;` is added to the file, so that one can be left off at the
+        // end of the input, which is then parsed with and without it. It
+        // goes on a line of its own, so that it isn't shown with errors.
+        let name = format!("<input {}>", self.history.len());
+        let file = match input.ends_with([';', '}']) {
+            true => self.inputs.add(name, input),
+            false => self.inputs.add(name, format!("{input}\n;")),
+        };
+        let with_semicolon = self.source(file);
+        let src = &with_semicolon[..input.len()];
+        let result = match parse(src, with_semicolon) {
+            Ok(Input::Decls(ast)) => {
+                for decl in &ast.decls {
+                    let name = match &decl.kind {
+                        DeclKind::Var(var) => Some(&var.name),
+                        DeclKind::Fn(function) => function.proto.name.as_ref(),
+                        _ => None,
+                    };
+                    if let Some(name) = name {
+                        self.declared_in.insert(name.name.to_string(), file);
+                    }
+                }
+                self.load(&ast).map(|()| Value::Void)
+            }
+            Ok(Input::Expr(expr)) => self.interpreter.eval_expr(&expr),
+            Ok(Input::Stmt(stmt)) => self.interpreter.exec_stmt(&stmt),
+            Err(errors) => {
+                for error in &errors {
+                    self.report_in(&Diagnostic::from(error), file);
+                }
+                return;
+            }
+        };
+        let printed = self.interpreter.take_output();
+        self.output.err.push_str(&printed);
+        match result {
+            Ok(Value::Void) => {}
+            Ok(value) => {
+                let _ = match value.bytes() {
+                    Some(bytes) => {
+                        let string = String::from_utf8_lossy(&bytes);
+                        writeln!(self.output.out, "{string:?} ({})", value.type_of())
+                    }
+                    None => writeln!(self.output.out, "{value} ({})", value.type_of()),
+                };
+            }
+            Err(error) => self.report_eval(&error, file),
+        }
+    }
+
+    /// The source of an input, for as long as the interpreter is around.
+    fn source(&self, file: FileId) -> &'static str {
+        let src = self.inputs.get(file).src();
+        // SAFETY: the interpreter borrows from the inputs for as long as
+        // it's around. The text of an input is never changed once it's
+        // added, and is only dropped after the interpreter is, either by
+        // `:reset` replacing both or by the field order of `Repl`. Moving
+        // a `SourceFile` doesn't move the text on the heap it points to.
+        unsafe { &*(src as *const str) }
+    }
+
+    /// Adds declarations to the interpreter. Variables are only evaluated
+    /// once they're used, so they're used right away to show any errors.
+    fn load(&mut self, ast: &Ast<'static>) -> Result<(), EvalError> {
+        self.interpreter.load(ast)?;
+        for decl in &ast.decls {
+            if let DeclKind::Var(var) = &decl.kind {
+                let name = Expr {
+                    span: var.name.span,
+                    kind: ExprKind::Identifier(var.name.name.clone()),
+                };
+                self.interpreter.eval_expr(&name)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reports an error from evaluating `file`. The code of each call is
+    /// in the input that declared the function, and the error is in the
+    /// innermost one, so the error and the calls leading up to it can be
+    /// in different inputs. The calls are shown as notes then.
+    fn report_eval(&mut self, error: &EvalError, file: FileId) {
+        let trace = error.trace();
+        let files: Vec<FileId> = trace
+            .iter()
+            .map(|frame| self.declared_in.get(&frame.name).copied().unwrap_or(file))
+            .chain([file])
+            .collect();
+        if files.iter().all(|&other| other == files[0]) {
+            return self.report_in(&Diagnostic::from(error), file);
+        }
+
+        let mut diagnostic = Diagnostic::error(error.kind().to_string(), error.span());
+        let mut i = 0;
+        while i < trace.len() {
+            let frame = &trace[i];
+            let repeats = trace[i..]
+                .iter()
+                .take_while(|other| *other == frame)
+                .count();
+            // Each call is made from the code of the one outside it.
+            let caller = self.inputs.get(files[i + repeats]);
+            let at = caller.position(frame.span.start());
+            let at = format!("{}:{}:{}", caller.name(), at.line + 1, at.char_col + 1);
+            let call = match frame.comptime {
+                true => "comptime call",
+                false => "call",
+            };
+            diagnostic = diagnostic.with_note(match repeats {
+                1 => format!("in {call} to '{}' at {at}", frame.name),
+                _ => format!("in {call} to '{}' at {at}, {repeats} times", frame.name),
+            });
+            i += repeats;
+        }
+        self.report_in(&diagnostic, files[0]);
+    }
+
+    fn report_in(&mut self, diagnostic: &Diagnostic, file: FileId) {
+        let rendered = diagnostic.render_file(self.inputs.get(file), self.color);
+        self.output.err.push_str(&rendered);
+    }
+
+    fn report(&mut self, diagnostic: &Diagnostic, src: &str) {
+        let name = format!("<input {}>", self.history.len());
+        let rendered = diagnostic.render(&name, src, self.color);
+        self.output.err.push_str(&rendered);
+    }
+}
+
+/// How many more opening braces, brackets and parentheses there are
+/// than closing ones.
+fn depth(src: &str) -> i32 {
+    Tokenizer::new(src)
+        .map(|token| match token.token_type() {
+            TokenType::LBrace | TokenType::LBracket | TokenType::LParen => 1,
+            TokenType::RBrace | TokenType::RBracket | TokenType::RParen => -1,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Parses an input as declarations, an expression or a statement, in that
+/// order. `with_semicolon` is the input with a `;` after it, so that one
+/// can be left off at the end of a line.
+fn parse<'src>(src: &'src str, with_semicolon: &'src str) -> Result<Input<'src>, Vec<ParseError>> {
+    let ast = parser::parse(src);
+    if !ast.has_errors() && !ast.decls.is_empty() {
+        return Ok(Input::Decls(ast));
+    }
+    let fixed = parser::parse(with_semicolon);
+    if !fixed.has_errors() && !fixed.decls.is_empty() {
+        return Ok(Input::Decls(fixed));
+    }
+    if let Ok(expr) = parser::parse_expr(src) {
+        return Ok(Input::Expr(expr));
+    }
+    let stmt = parser::parse_stmt(src)
+        .or_else(|errors| parser::parse_stmt(with_semicolon).map_err(|_| errors));
+    match stmt {
+        Ok(stmt) => Ok(Input::Stmt(stmt)),
+        // The errors of whatever the input looks most like.
+        Err(_) if is_decl(src) => Err(ast.errors),
+        Err(errors) => Err(errors),
+    }
+}
+
+/// Whether the input starts the way only declarations do.
+fn is_decl(src: &str) -> bool {
+    let first = src.split_whitespace().next().unwrap_or_default();
+    matches!(
+        first,
+        "pub" | "fn" | "extern" | "export" | "inline" | "noinline" | "test" | "usingnamespace"
+    )
+}
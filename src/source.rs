@@ -0,0 +1,171 @@
+use crate::tokenizer::{ColumnUnit, LineCol, Position, Span};
+
+/// Identifies a file in a `SourceMap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileId(pub(crate) u32);
+
+/// The byte offsets every line of a source starts at. It is built once
+/// per file, after which converting between byte offsets and line/column
+/// pairs is a binary search instead of a walk over the whole source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    /// Always starts with 0 for the first line.
+    line_starts: Vec<usize>,
+    len: usize,
+}
+
+impl LineIndex {
+    pub fn new(src: &str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(
+            src.bytes()
+                .enumerate()
+                .filter(|&(_, c)| c == b'\n')
+                .map(|(offset, _)| offset + 1),
+        );
+        Self {
+            line_starts,
+            len: src.len(),
+        }
+    }
+
+    /// The number of lines. A trailing newline starts a new, empty, line.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// The byte range of `line`, including its line ending.
+    pub fn line_span(&self, line: usize) -> Option<Span> {
+        let start = *self.line_starts.get(line)?;
+        let end = self.line_starts.get(line + 1).copied().unwrap_or(self.len);
+        Some(Span::new(start, end))
+    }
+
+    /// Finds the line and byte column of `offset`. Offsets past the end
+    /// of the source are clamped to it.
+    pub fn line_col(&self, offset: usize) -> LineCol {
+        let offset = offset.min(self.len);
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        LineCol {
+            line,
+            col: offset - self.line_starts[line],
+        }
+    }
+
+    /// Finds the byte offset of a line and byte column. Returns `None` if
+    /// the line doesn't exist or the column is past the end of the line.
+    pub fn offset(&self, position: LineCol) -> Option<usize> {
+        let line = self.line_span(position.line)?;
+        let offset = line.start + position.col;
+        (offset <= line.end).then_some(offset)
+    }
+}
+
+/// A source file along with its line index.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    id: FileId,
+    name: String,
+    src: String,
+    lines: LineIndex,
+}
+
+impl SourceFile {
+    pub fn new(id: FileId, name: impl Into<String>, src: impl Into<String>) -> Self {
+        let src = src.into();
+        Self {
+            id,
+            name: name.into(),
+            lines: LineIndex::new(&src),
+            src,
+        }
+    }
+
+    pub fn id(&self) -> FileId {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn src(&self) -> &str {
+        &self.src
+    }
+
+    pub fn line_index(&self) -> &LineIndex {
+        &self.lines
+    }
+
+    /// The text of `line` without its line ending.
+    pub fn line_text(&self, line: usize) -> Option<&str> {
+        let text = self.lines.line_span(line)?.slice(&self.src);
+        let text = text.strip_suffix('\n').unwrap_or(text);
+        Some(text.strip_suffix('\r').unwrap_or(text))
+    }
+
+    /// Finds the position of `offset`, with the column in every unit.
+    pub fn position(&self, offset: usize) -> Position {
+        let LineCol { line, col } = self.lines.line_col(offset);
+        let start = self.lines.line_starts[line];
+        let end = self.lines.line_span(line).map_or(self.src.len(), |s| s.end);
+        Position::in_line(line, &self.src[start..end], col)
+    }
+
+    /// Finds the byte offset of a line and a column counted in `unit`.
+    /// Columns in the middle of a character are rounded down to its start,
+    /// which matters for UTF-16 columns pointing between a surrogate pair.
+    pub fn offset(&self, line: usize, col: usize, unit: ColumnUnit) -> Option<usize> {
+        if unit == ColumnUnit::Byte {
+            return self.lines.offset(LineCol { line, col });
+        }
+        let text = self.line_text(line)?;
+        let start = self.lines.line_starts[line];
+
+        let mut units = 0;
+        for (offset, c) in text.char_indices() {
+            let width = if unit == ColumnUnit::Char {
+                1
+            } else {
+                c.len_utf16()
+            };
+            if units + width > col {
+                return Some(start + offset);
+            }
+            units += width;
+        }
+        (units == col).then_some(start + text.len())
+    }
+
+    /// Resolves a span into the positions of its start and end.
+    pub fn resolve(&self, span: Span) -> (Position, Position) {
+        (self.position(span.start), self.position(span.end))
+    }
+}
+
+/// All the source files known to a tool, so that spans from
+/// different files can be told apart by their `FileId`.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, src: impl Into<String>) -> FileId {
+        let id = FileId(self.files.len() as u32);
+        self.files.push(SourceFile::new(id, name, src));
+        id
+    }
+
+    pub fn get(&self, id: FileId) -> &SourceFile {
+        &self.files[id.0 as usize]
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &SourceFile> {
+        self.files.iter()
+    }
+}
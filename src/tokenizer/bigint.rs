@@ -0,0 +1,174 @@
+use std::fmt;
+
+/// An arbitrary precision unsigned integer.
+/// Zig's `comptime_int` has no upper bound, so integer literals can't be
+/// stored in a fixed size type without losing information. This is a
+/// deliberately small implementation that supports what the tokenizer
+/// needs: building a value digit by digit and converting it back out.
+///
+/// The value is stored as little endian 32 bit limbs and is always kept
+/// normalized, i.e. without trailing zero limbs, so the derived
+/// `PartialEq` compares values.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+/// The error returned when a `BigUint` is converted into a fixed size
+/// integer type that is too small to hold it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntegerOverflow {
+    /// The number of bits needed to represent the value.
+    pub bits: u64,
+    /// The number of bits available in the target type.
+    pub target_bits: u32,
+}
+
+impl fmt::Display for IntegerOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "integer value needs {} bits but the target type only has {}",
+            self.bits, self.target_bits
+        )
+    }
+}
+
+impl std::error::Error for IntegerOverflow {}
+
+impl BigUint {
+    pub fn zero() -> Self {
+        Self { limbs: Vec::new() }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// Parses a string of digits in the given radix. The string must only
+    /// contain valid digits, separators have to be stripped beforehand.
+    pub fn from_str_radix(digits: &str, radix: u32) -> Option<Self> {
+        if digits.is_empty() {
+            return None;
+        }
+        let mut value = Self::zero();
+        for c in digits.chars() {
+            value.mul_add_small(radix, c.to_digit(radix)?);
+        }
+        Some(value)
+    }
+
+    /// Computes `self = self * mul + add` in place.
+    fn mul_add_small(&mut self, mul: u32, add: u32) {
+        let mut carry = add as u64;
+        for limb in self.limbs.iter_mut() {
+            let product = *limb as u64 * mul as u64 + carry;
+            *limb = product as u32;
+            carry = product >> 32;
+        }
+        if carry != 0 {
+            self.limbs.push(carry as u32);
+        }
+    }
+
+    /// Divides `self` by `div` in place and returns the remainder.
+    fn div_rem_small(&mut self, div: u32) -> u32 {
+        let mut rem = 0u64;
+        for limb in self.limbs.iter_mut().rev() {
+            let acc = (rem << 32) | *limb as u64;
+            *limb = (acc / div as u64) as u32;
+            rem = acc % div as u64;
+        }
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        rem as u32
+    }
+
+    /// The number of bits needed to represent the value.
+    /// Zero needs zero bits, just like `u0` in zig.
+    pub fn bits(&self) -> u64 {
+        match self.limbs.last() {
+            Some(last) => self.limbs.len() as u64 * 32 - last.leading_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    /// Checks if the value fits in a zig integer type with the
+    /// given signedness and bit width, e.g. `u7` or `i33`.
+    pub fn fits_in(&self, bits: u16, signed: bool) -> bool {
+        let available = if signed { bits.saturating_sub(1) } else { bits };
+        self.bits() <= available as u64
+    }
+
+    pub fn to_u128(&self) -> Result<u128, IntegerOverflow> {
+        if self.bits() > 128 {
+            return Err(IntegerOverflow {
+                bits: self.bits(),
+                target_bits: 128,
+            });
+        }
+        Ok(self
+            .limbs
+            .iter()
+            .rev()
+            .fold(0u128, |acc, &limb| (acc << 32) | limb as u128))
+    }
+
+    pub fn to_u64(&self) -> Result<u64, IntegerOverflow> {
+        self.to_u128()
+            .ok()
+            .and_then(|value| u64::try_from(value).ok())
+            .ok_or(IntegerOverflow {
+                bits: self.bits(),
+                target_bits: 64,
+            })
+    }
+
+    pub fn to_usize(&self) -> Result<usize, IntegerOverflow> {
+        self.to_u128()
+            .ok()
+            .and_then(|value| usize::try_from(value).ok())
+            .ok_or(IntegerOverflow {
+                bits: self.bits(),
+                target_bits: usize::BITS,
+            })
+    }
+
+    /// Formats the value in the given radix, without any prefix.
+    pub fn to_str_radix(&self, radix: u32) -> String {
+        if self.is_zero() {
+            return String::from("0");
+        }
+        let mut value = self.clone();
+        let mut digits = Vec::new();
+        while !value.is_zero() {
+            let digit = value.div_rem_small(radix);
+            digits.push(char::from_digit(digit, radix).unwrap());
+        }
+        digits.iter().rev().collect()
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(mut value: u128) -> Self {
+        let mut limbs = Vec::new();
+        while value != 0 {
+            limbs.push(value as u32);
+            value >>= 32;
+        }
+        Self { limbs }
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> Self {
+        Self::from(value as u128)
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad_integral(true, "", &self.to_str_radix(10))
+    }
+}
@@ -0,0 +1,425 @@
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+
+/// The zig builtin functions, i.e. everything that can come after an `@`.
+/// Builtins are called like functions but are implemented by the compiler,
+/// so unlike regular functions, an unknown one can be caught while tokenizing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BuiltinFn {
+    AddrSpaceCast,
+    AddWithOverflow,
+    AlignCast,
+    AlignOf,
+    As,
+    AtomicLoad,
+    AtomicRmw,
+    AtomicStore,
+    BitCast,
+    BitOffsetOf,
+    BitReverse,
+    BitSizeOf,
+    Breakpoint,
+    ByteSwap,
+    Call,
+    CDefine,
+    Ceil,
+    CImport,
+    CInclude,
+    Clz,
+    CmpxchgStrong,
+    CmpxchgWeak,
+    CompileError,
+    CompileLog,
+    ConstCast,
+    Cos,
+    Ctz,
+    CUndef,
+    CVaArg,
+    CVaCopy,
+    CVaEnd,
+    CVaStart,
+    DivExact,
+    DivFloor,
+    DivTrunc,
+    EmbedFile,
+    EnumFromInt,
+    ErrorFromInt,
+    ErrorName,
+    ErrorReturnTrace,
+    ErrSetCast,
+    Exp,
+    Exp2,
+    Export,
+    Extern,
+    Fabs,
+    Fence,
+    Field,
+    FieldParentPtr,
+    FloatCast,
+    FloatFromInt,
+    Floor,
+    FrameAddress,
+    HasDecl,
+    HasField,
+    Import,
+    InComptime,
+    IntCast,
+    IntFromBool,
+    IntFromEnum,
+    IntFromError,
+    IntFromFloat,
+    IntFromPtr,
+    Log,
+    Log2,
+    Log10,
+    Max,
+    Memcpy,
+    Memset,
+    Min,
+    Mod,
+    MulWithOverflow,
+    OffsetOf,
+    Panic,
+    PopCount,
+    Prefetch,
+    PtrCast,
+    PtrFromInt,
+    Reduce,
+    Rem,
+    ReturnAddress,
+    Round,
+    Select,
+    SetAlignStack,
+    SetCold,
+    SetEvalBranchQuota,
+    SetFloatMode,
+    SetRuntimeSafety,
+    ShlExact,
+    ShlWithOverflow,
+    ShrExact,
+    Shuffle,
+    Sin,
+    SizeOf,
+    Splat,
+    Sqrt,
+    Src,
+    SubWithOverflow,
+    TagName,
+    Tan,
+    This,
+    Trap,
+    Trunc,
+    Truncate,
+    Type,
+    TypeInfo,
+    TypeName,
+    TypeOf,
+    UnionInit,
+    Vector,
+    VolatileCast,
+    WasmMemoryGrow,
+    WasmMemorySize,
+    WorkGroupId,
+    WorkGroupSize,
+    WorkItemId,
+}
+
+/// The number of arguments a builtin function takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    Exact(usize),
+    /// Variadic builtins like `@compileLog` and `@min`,
+    /// with the minimum number of arguments they need.
+    AtLeast(usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, args: usize) -> bool {
+        match *self {
+            Self::Exact(n) => args == n,
+            Self::AtLeast(n) => args >= n,
+        }
+    }
+}
+
+/// Maps builtin names, without the `@`, to the builtin they refer to.
+pub static BUILTIN_MAP: Lazy<HashMap<&'static str, BuiltinFn>> = Lazy::new(|| {
+    use BuiltinFn::*;
+    HashMap::from([
+        ("addrSpaceCast", AddrSpaceCast),
+        ("addWithOverflow", AddWithOverflow),
+        ("alignCast", AlignCast),
+        ("alignOf", AlignOf),
+        ("as", As),
+        ("atomicLoad", AtomicLoad),
+        ("atomicRmw", AtomicRmw),
+        ("atomicStore", AtomicStore),
+        ("bitCast", BitCast),
+        ("bitOffsetOf", BitOffsetOf),
+        ("bitReverse", BitReverse),
+        ("bitSizeOf", BitSizeOf),
+        ("breakpoint", Breakpoint),
+        ("byteSwap", ByteSwap),
+        ("call", Call),
+        ("cDefine", CDefine),
+        ("ceil", Ceil),
+        ("cImport", CImport),
+        ("cInclude", CInclude),
+        ("clz", Clz),
+        ("cmpxchgStrong", CmpxchgStrong),
+        ("cmpxchgWeak", CmpxchgWeak),
+        ("compileError", CompileError),
+        ("compileLog", CompileLog),
+        ("constCast", ConstCast),
+        ("cos", Cos),
+        ("ctz", Ctz),
+        ("cUndef", CUndef),
+        ("cVaArg", CVaArg),
+        ("cVaCopy", CVaCopy),
+        ("cVaEnd", CVaEnd),
+        ("cVaStart", CVaStart),
+        ("divExact", DivExact),
+        ("divFloor", DivFloor),
+        ("divTrunc", DivTrunc),
+        ("embedFile", EmbedFile),
+        ("enumFromInt", EnumFromInt),
+        ("errorFromInt", ErrorFromInt),
+        ("errorName", ErrorName),
+        ("errorReturnTrace", ErrorReturnTrace),
+        ("errSetCast", ErrSetCast),
+        ("exp", Exp),
+        ("exp2", Exp2),
+        ("export", Export),
+        ("extern", Extern),
+        ("fabs", Fabs),
+        ("fence", Fence),
+        ("field", Field),
+        ("fieldParentPtr", FieldParentPtr),
+        ("floatCast", FloatCast),
+        ("floatFromInt", FloatFromInt),
+        ("floor", Floor),
+        ("frameAddress", FrameAddress),
+        ("hasDecl", HasDecl),
+        ("hasField", HasField),
+        ("import", Import),
+        ("inComptime", InComptime),
+        ("intCast", IntCast),
+        ("intFromBool", IntFromBool),
+        ("intFromEnum", IntFromEnum),
+        ("intFromError", IntFromError),
+        ("intFromFloat", IntFromFloat),
+        ("intFromPtr", IntFromPtr),
+        ("log", Log),
+        ("log2", Log2),
+        ("log10", Log10),
+        ("max", Max),
+        ("memcpy", Memcpy),
+        ("memset", Memset),
+        ("min", Min),
+        ("mod", Mod),
+        ("mulWithOverflow", MulWithOverflow),
+        ("offsetOf", OffsetOf),
+        ("panic", Panic),
+        ("popCount", PopCount),
+        ("prefetch", Prefetch),
+        ("ptrCast", PtrCast),
+        ("ptrFromInt", PtrFromInt),
+        ("reduce", Reduce),
+        ("rem", Rem),
+        ("returnAddress", ReturnAddress),
+        ("round", Round),
+        ("select", Select),
+        ("setAlignStack", SetAlignStack),
+        ("setCold", SetCold),
+        ("setEvalBranchQuota", SetEvalBranchQuota),
+        ("setFloatMode", SetFloatMode),
+        ("setRuntimeSafety", SetRuntimeSafety),
+        ("shlExact", ShlExact),
+        ("shlWithOverflow", ShlWithOverflow),
+        ("shrExact", ShrExact),
+        ("shuffle", Shuffle),
+        ("sin", Sin),
+        ("sizeOf", SizeOf),
+        ("splat", Splat),
+        ("sqrt", Sqrt),
+        ("src", Src),
+        ("subWithOverflow", SubWithOverflow),
+        ("tagName", TagName),
+        ("tan", Tan),
+        ("This", This),
+        ("trap", Trap),
+        ("trunc", Trunc),
+        ("truncate", Truncate),
+        ("Type", Type),
+        ("typeInfo", TypeInfo),
+        ("typeName", TypeName),
+        ("TypeOf", TypeOf),
+        ("unionInit", UnionInit),
+        ("Vector", Vector),
+        ("volatileCast", VolatileCast),
+        ("wasmMemoryGrow", WasmMemoryGrow),
+        ("wasmMemorySize", WasmMemorySize),
+        ("workGroupId", WorkGroupId),
+        ("workGroupSize", WorkGroupSize),
+        ("workItemId", WorkItemId),
+    ])
+});
+
+impl BuiltinFn {
+    /// Looks up a builtin by its name, without the `@`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        BUILTIN_MAP.get(name).copied()
+    }
+
+    /// The name of the builtin, without the `@`.
+    pub fn name(&self) -> &'static str {
+        use BuiltinFn::*;
+        match self {
+            AddrSpaceCast => "addrSpaceCast",
+            AddWithOverflow => "addWithOverflow",
+            AlignCast => "alignCast",
+            AlignOf => "alignOf",
+            As => "as",
+            AtomicLoad => "atomicLoad",
+            AtomicRmw => "atomicRmw",
+            AtomicStore => "atomicStore",
+            BitCast => "bitCast",
+            BitOffsetOf => "bitOffsetOf",
+            BitReverse => "bitReverse",
+            BitSizeOf => "bitSizeOf",
+            Breakpoint => "breakpoint",
+            ByteSwap => "byteSwap",
+            Call => "call",
+            CDefine => "cDefine",
+            Ceil => "ceil",
+            CImport => "cImport",
+            CInclude => "cInclude",
+            Clz => "clz",
+            CmpxchgStrong => "cmpxchgStrong",
+            CmpxchgWeak => "cmpxchgWeak",
+            CompileError => "compileError",
+            CompileLog => "compileLog",
+            ConstCast => "constCast",
+            Cos => "cos",
+            Ctz => "ctz",
+            CUndef => "cUndef",
+            CVaArg => "cVaArg",
+            CVaCopy => "cVaCopy",
+            CVaEnd => "cVaEnd",
+            CVaStart => "cVaStart",
+            DivExact => "divExact",
+            DivFloor => "divFloor",
+            DivTrunc => "divTrunc",
+            EmbedFile => "embedFile",
+            EnumFromInt => "enumFromInt",
+            ErrorFromInt => "errorFromInt",
+            ErrorName => "errorName",
+            ErrorReturnTrace => "errorReturnTrace",
+            ErrSetCast => "errSetCast",
+            Exp => "exp",
+            Exp2 => "exp2",
+            Export => "export",
+            Extern => "extern",
+            Fabs => "fabs",
+            Fence => "fence",
+            Field => "field",
+            FieldParentPtr => "fieldParentPtr",
+            FloatCast => "floatCast",
+            FloatFromInt => "floatFromInt",
+            Floor => "floor",
+            FrameAddress => "frameAddress",
+            HasDecl => "hasDecl",
+            HasField => "hasField",
+            Import => "import",
+            InComptime => "inComptime",
+            IntCast => "intCast",
+            IntFromBool => "intFromBool",
+            IntFromEnum => "intFromEnum",
+            IntFromError => "intFromError",
+            IntFromFloat => "intFromFloat",
+            IntFromPtr => "intFromPtr",
+            Log => "log",
+            Log2 => "log2",
+            Log10 => "log10",
+            Max => "max",
+            Memcpy => "memcpy",
+            Memset => "memset",
+            Min => "min",
+            Mod => "mod",
+            MulWithOverflow => "mulWithOverflow",
+            OffsetOf => "offsetOf",
+            Panic => "panic",
+            PopCount => "popCount",
+            Prefetch => "prefetch",
+            PtrCast => "ptrCast",
+            PtrFromInt => "ptrFromInt",
+            Reduce => "reduce",
+            Rem => "rem",
+            ReturnAddress => "returnAddress",
+            Round => "round",
+            Select => "select",
+            SetAlignStack => "setAlignStack",
+            SetCold => "setCold",
+            SetEvalBranchQuota => "setEvalBranchQuota",
+            SetFloatMode => "setFloatMode",
+            SetRuntimeSafety => "setRuntimeSafety",
+            ShlExact => "shlExact",
+            ShlWithOverflow => "shlWithOverflow",
+            ShrExact => "shrExact",
+            Shuffle => "shuffle",
+            Sin => "sin",
+            SizeOf => "sizeOf",
+            Splat => "splat",
+            Sqrt => "sqrt",
+            Src => "src",
+            SubWithOverflow => "subWithOverflow",
+            TagName => "tagName",
+            Tan => "tan",
+            This => "This",
+            Trap => "trap",
+            Trunc => "trunc",
+            Truncate => "truncate",
+            Type => "Type",
+            TypeInfo => "typeInfo",
+            TypeName => "typeName",
+            TypeOf => "TypeOf",
+            UnionInit => "unionInit",
+            Vector => "Vector",
+            VolatileCast => "volatileCast",
+            WasmMemoryGrow => "wasmMemoryGrow",
+            WasmMemorySize => "wasmMemorySize",
+            WorkGroupId => "workGroupId",
+            WorkGroupSize => "workGroupSize",
+            WorkItemId => "workItemId",
+        }
+    }
+
+    pub fn arity(&self) -> Arity {
+        use BuiltinFn::*;
+        match self {
+            Breakpoint | CVaStart | ErrorReturnTrace | FrameAddress | InComptime
+            | ReturnAddress | Src | This | Trap => Arity::Exact(0),
+            AddrSpaceCast | AlignCast | AlignOf | BitCast | BitReverse | BitSizeOf | ByteSwap
+            | Ceil | CImport | CInclude | Clz | CompileError | ConstCast | Cos | Ctz | CUndef
+            | CVaCopy | CVaEnd | EmbedFile | EnumFromInt | ErrorFromInt | ErrorName
+            | ErrSetCast | Exp | Exp2 | Fabs | Fence | FloatCast | FloatFromInt | Floor
+            | Import | IntCast | IntFromBool | IntFromEnum | IntFromError | IntFromFloat
+            | IntFromPtr | Log | Log2 | Log10 | Panic | PopCount | PtrCast | PtrFromInt | Round
+            | SetAlignStack | SetCold | SetEvalBranchQuota | SetFloatMode | SetRuntimeSafety
+            | Sin | SizeOf | Splat | Sqrt | TagName | Tan | Trunc | Truncate | Type | TypeInfo
+            | TypeName | VolatileCast | WasmMemorySize | WorkGroupId | WorkGroupSize
+            | WorkItemId => Arity::Exact(1),
+            AddWithOverflow | As | BitOffsetOf | CDefine | CVaArg | DivExact | DivFloor
+            | DivTrunc | Export | Extern | Field | HasDecl | HasField | Memcpy | Memset | Mod
+            | MulWithOverflow | OffsetOf | Prefetch | Reduce | Rem | ShlExact | ShlWithOverflow
+            | ShrExact | SubWithOverflow | Vector | WasmMemoryGrow => Arity::Exact(2),
+            AtomicLoad | Call | FieldParentPtr | UnionInit => Arity::Exact(3),
+            AtomicStore | Select | Shuffle => Arity::Exact(4),
+            AtomicRmw => Arity::Exact(5),
+            CmpxchgStrong | CmpxchgWeak => Arity::Exact(6),
+            CompileLog => Arity::AtLeast(0),
+            TypeOf => Arity::AtLeast(1),
+            Max | Min => Arity::AtLeast(2),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::fmt;
+
+use super::Span;
+
+/// An error found while tokenizing. The tokenizer doesn't stop
+/// at errors, it records them and keeps going with a best effort
+/// token so that as many errors as possible can be reported at once.
+/// The span points at the offending part of the source, which isn't
+/// necessarily the whole token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub(crate) span: Span,
+    pub(crate) kind: LexErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexErrorKind {
+    /// A character that can't start any token, such as `$`, or
+    /// a control character inside of a string or character literal.
+    InvalidCharacter(char),
+
+    /// Source that isn't valid UTF-8. Only reported by `Tokenizer::from_utf8`.
+    InvalidUtf8,
+
+    /// A digit that isn't valid for the radix of the number literal,
+    /// such as the `2` in `0b102`.
+    InvalidDigit { digit: char, radix: u32 },
+
+    /// A radix prefix or exponent that isn't followed by any digits, as in `0x` or `1e`.
+    ExpectedDigits,
+
+    /// A decimal number literal starting with a zero, such as `0123`.
+    LeadingZero,
+
+    /// A `_` digit separator that isn't followed by a digit.
+    TrailingUnderscore,
+
+    /// Two `_` digit separators in a row.
+    RepeatedUnderscore,
+
+    /// A float literal too large for an `f64`, such as `1e400`.
+    FloatOutOfRange,
+
+    /// A tab character inside of a `\\` multiline string literal.
+    TabInMultilineString,
+
+    /// A string literal that reaches the end of the line or file
+    /// without a closing `"`.
+    UnterminatedString,
+
+    /// A character literal that reaches the end of the line or file
+    /// without a closing `'`.
+    UnterminatedChar,
+
+    /// A `''` character literal with nothing in it.
+    EmptyChar,
+
+    /// A character literal with more than one codepoint in it, such as `'ab'`.
+    MultipleCodepointsInChar,
+
+    /// An `@name` that isn't one of the builtin functions.
+    UnknownBuiltin(String),
+
+    /// An `@""` identifier with nothing in it.
+    EmptyIdentifier,
+
+    /// A `\` followed by a character that doesn't start an escape sequence.
+    InvalidEscapeCharacter(char),
+
+    /// A `\x` escape that isn't followed by exactly two hex digits.
+    InvalidHexEscape,
+
+    /// A `\u` escape that isn't of the form `\u{N}` with 1 to 6 hex digits.
+    InvalidUnicodeEscape,
+
+    /// A `\u{N}` escape whose value isn't a unicode scalar value.
+    InvalidUnicodeCodepoint(u32),
+}
+
+impl LexError {
+    pub fn new(span: Span, kind: LexErrorKind) -> Self {
+        Self { span, kind }
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn kind(&self) -> &LexErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for LexErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidCharacter(c) => write!(f, "invalid character: {c:?}"),
+            Self::InvalidUtf8 => write!(f, "invalid UTF-8"),
+            Self::InvalidDigit { digit, radix } => {
+                let base = match radix {
+                    2 => "binary",
+                    8 => "octal",
+                    16 => "hex",
+                    _ => "decimal",
+                };
+                write!(f, "invalid digit '{digit}' for {base} base")
+            }
+            Self::ExpectedDigits => write!(f, "expected digits"),
+            Self::LeadingZero => write!(f, "number literal has a leading zero"),
+            Self::TrailingUnderscore => write!(f, "trailing digit separator"),
+            Self::RepeatedUnderscore => write!(f, "repeated digit separator"),
+            Self::FloatOutOfRange => write!(f, "float literal out of range"),
+            Self::TabInMultilineString => {
+                write!(f, "tab characters are not allowed in multiline strings")
+            }
+            Self::UnterminatedString => write!(f, "unterminated string literal"),
+            Self::UnterminatedChar => write!(f, "unterminated character literal"),
+            Self::EmptyChar => write!(f, "empty character literal"),
+            Self::MultipleCodepointsInChar => {
+                write!(f, "character literal must contain exactly one codepoint")
+            }
+            Self::UnknownBuiltin(name) => write!(f, "invalid builtin function: '@{name}'"),
+            Self::EmptyIdentifier => write!(f, "identifier cannot be empty"),
+            Self::InvalidEscapeCharacter(c) => write!(f, "invalid escape character: '{c}'"),
+            Self::InvalidHexEscape => write!(f, "expected two hex digits after '\\x'"),
+            Self::InvalidUnicodeEscape => {
+                write!(f, "expected unicode escape of the form '\\u{{NNNNNN}}'")
+            }
+            Self::InvalidUnicodeCodepoint(c) => {
+                write!(
+                    f,
+                    "unicode escape does not correspond to a valid codepoint: 0x{c:x}"
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl std::error::Error for LexError {}
@@ -1,5 +1,14 @@
+mod bigint;
+mod builtin;
+mod error;
 mod token;
+#[allow(clippy::module_inception)]
 mod tokenizer;
+mod trivia;
 
+pub use bigint::*;
+pub use builtin::*;
+pub use error::*;
 pub use token::*;
 pub use tokenizer::*;
+pub use trivia::*;
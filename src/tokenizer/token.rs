@@ -1,33 +1,256 @@
+use std::borrow::Cow;
+
+use super::{BigUint, BuiltinFn};
+use crate::source::SourceFile;
+
 /// It is used to specify the location of
-/// tokens in the source text. As far as I know,
-/// Zig doesn't have support for multi line tokens.
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// tokens in the source text, as a range of byte offsets.
+/// Line and column numbers are only computed when they are
+/// asked for, as most spans never end up being displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct Span {
-    pub(crate) col: usize,
-    pub(crate) width: usize,
-    pub(crate) line: usize,
-    // lexeme: String,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+/// A zero based line and column pair, with the column counted in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineCol {
+    pub line: usize,
+    pub col: usize,
+}
+
+/// The unit a column is counted in. Terminals mostly want unicode
+/// scalar values, while LSP clients count in UTF-16 code units by default.
+/// None of these are grapheme clusters, so a column can still point into
+/// the middle of something like a flag emoji.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColumnUnit {
+    Byte,
+    Char,
+    Utf16,
+}
+
+/// A zero based line with the column counted in every `ColumnUnit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Position {
+    pub line: usize,
+    pub byte_col: usize,
+    pub char_col: usize,
+    pub utf16_col: usize,
+}
+
+impl Position {
+    /// Computes the position of the byte `offset` into `line_text`,
+    /// which is the text of line number `line` up to at least `offset`.
+    pub fn in_line(line: usize, line_text: &str, offset: usize) -> Self {
+        let before = &line_text[..offset];
+        Self {
+            line,
+            byte_col: offset,
+            char_col: before.chars().count(),
+            utf16_col: before.chars().map(char::len_utf16).sum(),
+        }
+    }
+
+    pub fn col(&self, unit: ColumnUnit) -> usize {
+        match unit {
+            ColumnUnit::Byte => self.byte_col,
+            ColumnUnit::Char => self.char_col,
+            ColumnUnit::Utf16 => self.utf16_col,
+        }
+    }
+}
+
+impl From<Position> for LineCol {
+    fn from(position: Position) -> Self {
+        Self {
+            line: position.line,
+            col: position.byte_col,
+        }
+    }
 }
 
 impl Span {
-    pub fn new(col: usize, width: usize, line: usize) -> Self {
-        Self { col, width, line }
+    pub fn new(start: usize, end: usize) -> Self {
+        debug_assert!(start <= end, "span starts after it ends");
+        Self { start, end }
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns the part of `src` covered by the span.
+    /// `src` has to be the source the span was created from.
+    pub fn slice<'a>(&self, src: &'a str) -> &'a str {
+        &src[self.start..self.end]
+    }
+
+    /// Returns the smallest span covering both spans,
+    /// including anything that comes between them.
+    pub fn join(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    /// Joins all the spans together. Returns `None` if there are none.
+    pub fn merge(spans: impl IntoIterator<Item = Span>) -> Option<Span> {
+        spans.into_iter().reduce(Span::join)
+    }
+
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset < self.end
+    }
+
+    /// Computes the line and column the span starts at. This walks
+    /// the source up to the span, so it isn't meant for hot paths.
+    pub fn start_line_col(&self, src: &str) -> LineCol {
+        line_col(src, self.start)
+    }
+
+    /// Computes the line and column right after the end of the span.
+    pub fn end_line_col(&self, src: &str) -> LineCol {
+        line_col(src, self.end)
+    }
+
+    /// Resolves the span into the positions of its start and end using the
+    /// line index of `file`, which is much cheaper than `start_position`
+    /// and `end_position` when many spans of the same file are resolved.
+    pub fn resolve(&self, file: &SourceFile) -> (Position, Position) {
+        file.resolve(*self)
+    }
+
+    /// Like `start_line_col`, but with the column in every `ColumnUnit`.
+    pub fn start_position(&self, src: &str) -> Position {
+        position(src, self.start)
+    }
+
+    /// Like `end_line_col`, but with the column in every `ColumnUnit`.
+    pub fn end_position(&self, src: &str) -> Position {
+        position(src, self.end)
+    }
+}
+
+fn position(src: &str, offset: usize) -> Position {
+    let LineCol { line, col } = line_col(src, offset);
+    let line_start = offset - col;
+    Position::in_line(line, &src[line_start..], col)
+}
+
+fn line_col(src: &str, offset: usize) -> LineCol {
+    let before = &src.as_bytes()[..offset];
+    let line_start = before
+        .iter()
+        .rposition(|&c| c == b'\n')
+        .map_or(0, |newline| newline + 1);
+    LineCol {
+        line: before.iter().filter(|&&c| c == b'\n').count(),
+        col: offset - line_start,
+    }
+}
+
+/// A `"..."` or `\\` multiline string literal.
+/// Zig strings are just bytes, and `\xNN` escapes can produce bytes
+/// that aren't valid UTF-8, so the decoded value is kept as bytes.
+/// Both the raw text and the value are borrowed from the source
+/// when possible, the value only needs to be owned when escapes
+/// had to be decoded or multiple lines were joined.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StringLiteral<'src> {
+    /// The literal as written in the source, including the quotes
+    /// or, for multiline strings, the `\\` of every line.
+    pub(crate) raw: Cow<'src, str>,
+    /// The literal with all escape sequences decoded.
+    pub(crate) value: Cow<'src, [u8]>,
+}
+
+impl<'src> StringLiteral<'src> {
+    pub fn new(raw: impl Into<Cow<'src, str>>, value: Cow<'src, [u8]>) -> Self {
+        Self {
+            raw: raw.into(),
+            value,
+        }
+    }
+
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+
+    /// Returns the decoded value if it is valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.value).ok()
+    }
+
+    pub fn into_owned(self) -> StringLiteral<'static> {
+        StringLiteral {
+            raw: Cow::Owned(self.raw.into_owned()),
+            value: Cow::Owned(self.value.into_owned()),
+        }
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub enum TokenType {
-    /// A comptime_int
-    Integer(usize),
+pub enum TokenType<'src> {
+    /// A comptime_int. As those are unbounded, the value is
+    /// stored as a `BigUint`. Use `BigUint::to_u128` and friends
+    /// to get it out as a fixed size integer.
+    Integer(BigUint),
 
     /// A comptime_float
     Float(f64),
     Char(char),
-    String(String),
+    StringLiteral(StringLiteral<'src>),
+
+    /// One or more consecutive `\\` lines. The value is the
+    /// content of the lines joined by newlines, without escapes
+    /// being processed.
+    MultilineStringLiteral(StringLiteral<'src>),
+
+    /// An identifier that isn't a keyword or a primitive type.
+    /// For `@"..."` identifiers this is the decoded name.
+    Identifier(Cow<'src, str>),
+
+    /// A builtin function such as `@import`, holding
+    /// the name without the `@`. Unknown builtins are
+    /// reported as errors but still produce this token.
+    Builtin(Cow<'src, str>),
+
+    /// A plain `//` comment. These are only emitted when the
+    /// tokenizer is asked to keep comments.
+    LineComment(Cow<'src, str>),
+
+    /// A `///` doc comment, documenting the declaration after it.
+    DocComment(Cow<'src, str>),
+
+    /// A `//!` doc comment, documenting the enclosing container.
+    ContainerDocComment(Cow<'src, str>),
     Keyword(KeywordType),
     PrimitiveType(PrimitiveType),
 
-    Unknown(char),
+    /// Input that isn't the start of any token. The
+    /// reason is recorded as a `LexError`.
+    Invalid,
+
+    /// The end of the source. The tokenizer itself never emits this, it
+    /// is used by consumers such as the lossless token stream, which
+    /// attaches the trivia at the end of the file to it.
+    Eof,
 
     Ampersand,      // &
     AmpersandEqual, // &=
@@ -112,7 +335,285 @@ pub enum TokenType {
     Tilde, // ~
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+impl TokenType<'_> {
+    /// Converts the token type into one that doesn't borrow from the source.
+    pub fn into_owned(self) -> TokenType<'static> {
+        fn owned(text: Cow<'_, str>) -> Cow<'static, str> {
+            Cow::Owned(text.into_owned())
+        }
+
+        use TokenType::*;
+        match self {
+            StringLiteral(literal) => StringLiteral(literal.into_owned()),
+            MultilineStringLiteral(literal) => MultilineStringLiteral(literal.into_owned()),
+            Identifier(name) => Identifier(owned(name)),
+            Builtin(name) => Builtin(owned(name)),
+            LineComment(text) => LineComment(owned(text)),
+            DocComment(text) => DocComment(owned(text)),
+            ContainerDocComment(text) => ContainerDocComment(owned(text)),
+            Integer(value) => Integer(value),
+            Float(value) => Float(value),
+            Char(value) => Char(value),
+            Keyword(keyword) => Keyword(keyword),
+            PrimitiveType(primitive) => PrimitiveType(primitive),
+            Invalid => Invalid,
+            Eof => Eof,
+            Ampersand => Ampersand,
+            AmpersandEqual => AmpersandEqual,
+            Asterisk => Asterisk,
+            Asterisk2 => Asterisk2,
+            AsteriskEqual => AsteriskEqual,
+            AsteriskPercent => AsteriskPercent,
+            AsteriskPercentEqual => AsteriskPercentEqual,
+            AsteriskPipe => AsteriskPipe,
+            AsteriskPipeEqual => AsteriskPipeEqual,
+            Caret => Caret,
+            CaretEqual => CaretEqual,
+            Colon => Colon,
+            Comma => Comma,
+            Dot => Dot,
+            Dot2 => Dot2,
+            Dot3 => Dot3,
+            DotAsterisk => DotAsterisk,
+            DotQuestionMark => DotQuestionMark,
+            Equal => Equal,
+            EqualEqual => EqualEqual,
+            EqualArrow => EqualArrow,
+            ExclamationMark => ExclamationMark,
+            ExclamationMarkEqual => ExclamationMarkEqual,
+            LArrow => LArrow,
+            LArrow2 => LArrow2,
+            LArrow2Equal => LArrow2Equal,
+            LArrow2Pipe => LArrow2Pipe,
+            LArrow2PipeEqual => LArrow2PipeEqual,
+            LArrowEqual => LArrowEqual,
+            LBrace => LBrace,
+            LBracket => LBracket,
+            LParen => LParen,
+            Minus => Minus,
+            MinusEqual => MinusEqual,
+            MinusPercent => MinusPercent,
+            MinusPercentEqual => MinusPercentEqual,
+            MinusPipe => MinusPipe,
+            MinusPipeEqual => MinusPipeEqual,
+            MinusArrow => MinusArrow,
+            Percent => Percent,
+            PercentEqual => PercentEqual,
+            Pipe => Pipe,
+            Pipe2 => Pipe2,
+            PipeEqual => PipeEqual,
+            Plus => Plus,
+            Plus2 => Plus2,
+            PlusEqual => PlusEqual,
+            PlusPercent => PlusPercent,
+            PlusPercentEqual => PlusPercentEqual,
+            PlusPipe => PlusPipe,
+            PlusPipeEqual => PlusPipeEqual,
+            QuestionMark => QuestionMark,
+            RArrow => RArrow,
+            RArrow2 => RArrow2,
+            RArrow2Equal => RArrow2Equal,
+            RArrowEqual => RArrowEqual,
+            RBrace => RBrace,
+            RBracket => RBracket,
+            RParen => RParen,
+            Semicolon => Semicolon,
+            Slash => Slash,
+            SlashEqual => SlashEqual,
+            Tilde => Tilde,
+        }
+    }
+}
+
+/// The kind of a token without any of the data `TokenType` carries, for
+/// when tokens have to be stored compactly. The text of a token can be
+/// recovered from the source using its start offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenTag {
+    Integer,
+    Float,
+    Char,
+    StringLiteral,
+    MultilineStringLiteral,
+    Identifier,
+    Builtin,
+    LineComment,
+    DocComment,
+    ContainerDocComment,
+    Keyword(KeywordType),
+    PrimitiveType,
+    Invalid,
+    Eof,
+
+    Ampersand,      // &
+    AmpersandEqual, // &=
+
+    Asterisk,             // *
+    Asterisk2,            // **
+    AsteriskEqual,        // *=
+    AsteriskPercent,      // *%
+    AsteriskPercentEqual, // *%=
+    AsteriskPipe,         // *|
+    AsteriskPipeEqual,    // *|=
+
+    Caret,      // ^
+    CaretEqual, // ^=
+
+    Colon, // :
+    Comma, // ,
+
+    Dot,             // .
+    Dot2,            // ..
+    Dot3,            // ...
+    DotAsterisk,     // .*
+    DotQuestionMark, // .?
+
+    Equal,      // =
+    EqualEqual, // ==
+    EqualArrow, // =>
+
+    ExclamationMark,      // !
+    ExclamationMarkEqual, // !=
+
+    LArrow,           // <
+    LArrow2,          // <<
+    LArrow2Equal,     // <<=
+    LArrow2Pipe,      // <<|
+    LArrow2PipeEqual, // <<|=
+    LArrowEqual,      // <=
+
+    LBrace,   // {
+    LBracket, // [
+    LParen,   // (
+
+    Minus,             // -
+    MinusEqual,        // -=
+    MinusPercent,      // -%
+    MinusPercentEqual, // -%=,
+    MinusPipe,         // -|
+    MinusPipeEqual,    // -|=
+    MinusArrow,        // ->
+
+    Percent,      // %
+    PercentEqual, // %=
+
+    Pipe,      // |
+    Pipe2,     // ||
+    PipeEqual, // |=
+
+    Plus,             // +
+    Plus2,            // ++
+    PlusEqual,        // +=
+    PlusPercent,      // +%
+    PlusPercentEqual, // +%=
+    PlusPipe,         // +|
+    PlusPipeEqual,    // +|=
+
+    QuestionMark, // ?
+
+    RArrow,       // >
+    RArrow2,      // >>
+    RArrow2Equal, // >>=
+    RArrowEqual,  // >=
+
+    RBrace,   // }
+    RBracket, // ]
+    RParen,   // )
+
+    Semicolon, // ;
+
+    Slash,      // /
+    SlashEqual, // /=
+
+    Tilde, // ~
+}
+
+impl TokenType<'_> {
+    pub fn tag(&self) -> TokenTag {
+        use TokenType::*;
+        match self {
+            Integer(_) => TokenTag::Integer,
+            Float(_) => TokenTag::Float,
+            Char(_) => TokenTag::Char,
+            StringLiteral(_) => TokenTag::StringLiteral,
+            MultilineStringLiteral(_) => TokenTag::MultilineStringLiteral,
+            Identifier(_) => TokenTag::Identifier,
+            Builtin(_) => TokenTag::Builtin,
+            LineComment(_) => TokenTag::LineComment,
+            DocComment(_) => TokenTag::DocComment,
+            ContainerDocComment(_) => TokenTag::ContainerDocComment,
+            Keyword(keyword) => TokenTag::Keyword(*keyword),
+            PrimitiveType(_) => TokenTag::PrimitiveType,
+            Invalid => TokenTag::Invalid,
+            Eof => TokenTag::Eof,
+            Ampersand => TokenTag::Ampersand,
+            AmpersandEqual => TokenTag::AmpersandEqual,
+            Asterisk => TokenTag::Asterisk,
+            Asterisk2 => TokenTag::Asterisk2,
+            AsteriskEqual => TokenTag::AsteriskEqual,
+            AsteriskPercent => TokenTag::AsteriskPercent,
+            AsteriskPercentEqual => TokenTag::AsteriskPercentEqual,
+            AsteriskPipe => TokenTag::AsteriskPipe,
+            AsteriskPipeEqual => TokenTag::AsteriskPipeEqual,
+            Caret => TokenTag::Caret,
+            CaretEqual => TokenTag::CaretEqual,
+            Colon => TokenTag::Colon,
+            Comma => TokenTag::Comma,
+            Dot => TokenTag::Dot,
+            Dot2 => TokenTag::Dot2,
+            Dot3 => TokenTag::Dot3,
+            DotAsterisk => TokenTag::DotAsterisk,
+            DotQuestionMark => TokenTag::DotQuestionMark,
+            Equal => TokenTag::Equal,
+            EqualEqual => TokenTag::EqualEqual,
+            EqualArrow => TokenTag::EqualArrow,
+            ExclamationMark => TokenTag::ExclamationMark,
+            ExclamationMarkEqual => TokenTag::ExclamationMarkEqual,
+            LArrow => TokenTag::LArrow,
+            LArrow2 => TokenTag::LArrow2,
+            LArrow2Equal => TokenTag::LArrow2Equal,
+            LArrow2Pipe => TokenTag::LArrow2Pipe,
+            LArrow2PipeEqual => TokenTag::LArrow2PipeEqual,
+            LArrowEqual => TokenTag::LArrowEqual,
+            LBrace => TokenTag::LBrace,
+            LBracket => TokenTag::LBracket,
+            LParen => TokenTag::LParen,
+            Minus => TokenTag::Minus,
+            MinusEqual => TokenTag::MinusEqual,
+            MinusPercent => TokenTag::MinusPercent,
+            MinusPercentEqual => TokenTag::MinusPercentEqual,
+            MinusPipe => TokenTag::MinusPipe,
+            MinusPipeEqual => TokenTag::MinusPipeEqual,
+            MinusArrow => TokenTag::MinusArrow,
+            Percent => TokenTag::Percent,
+            PercentEqual => TokenTag::PercentEqual,
+            Pipe => TokenTag::Pipe,
+            Pipe2 => TokenTag::Pipe2,
+            PipeEqual => TokenTag::PipeEqual,
+            Plus => TokenTag::Plus,
+            Plus2 => TokenTag::Plus2,
+            PlusEqual => TokenTag::PlusEqual,
+            PlusPercent => TokenTag::PlusPercent,
+            PlusPercentEqual => TokenTag::PlusPercentEqual,
+            PlusPipe => TokenTag::PlusPipe,
+            PlusPipeEqual => TokenTag::PlusPipeEqual,
+            QuestionMark => TokenTag::QuestionMark,
+            RArrow => TokenTag::RArrow,
+            RArrow2 => TokenTag::RArrow2,
+            RArrow2Equal => TokenTag::RArrow2Equal,
+            RArrowEqual => TokenTag::RArrowEqual,
+            RBrace => TokenTag::RBrace,
+            RBracket => TokenTag::RBracket,
+            RParen => TokenTag::RParen,
+            Semicolon => TokenTag::Semicolon,
+            Slash => TokenTag::Slash,
+            SlashEqual => TokenTag::SlashEqual,
+            Tilde => TokenTag::Tilde,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum KeywordType {
     AddrSpace,
     Align,
@@ -181,6 +682,13 @@ pub enum PrimitiveType {
     I128,
     U128,
 
+    /// An integer type with a bit width other than the ones above,
+    /// such as `i33`. Widths go up to 65535.
+    SignedInt(u16),
+    /// An integer type with a bit width other than the ones above,
+    /// such as `u7`. Widths go up to 65535.
+    UnsignedInt(u16),
+
     ISize,
     USize,
 
@@ -216,74 +724,99 @@ pub enum PrimitiveType {
 /// TokenType. `Span` is mostly for error diagnostic purposes
 /// and `TokenType` is for the actual token information.
 #[derive(Debug, Clone)]
-pub struct Token(pub(crate) Span, pub(crate) TokenType);
+pub struct Token<'src>(pub(crate) Span, pub(crate) TokenType<'src>);
 
-impl Token {
-    pub fn new(pos: Span, r#type: TokenType) -> Self {
+impl<'src> Token<'src> {
+    pub fn new(pos: Span, r#type: TokenType<'src>) -> Self {
         Self(pos, r#type)
     }
 
+    /// Converts the token into one that doesn't borrow from the source.
+    pub fn into_owned(self) -> Token<'static> {
+        Token(self.0, self.1.into_owned())
+    }
+
     pub fn is_keyword(&self) -> bool {
         matches!(self.1, TokenType::Keyword(_))
     }
 
+    pub fn is_identifier(&self) -> bool {
+        matches!(self.1, TokenType::Identifier(_))
+    }
+
+    /// Returns the builtin function this token refers to, if it is a
+    /// builtin token naming a known builtin.
+    pub fn builtin_fn(&self) -> Option<BuiltinFn> {
+        match &self.1 {
+            TokenType::Builtin(name) => BuiltinFn::from_name(name),
+            _ => None,
+        }
+    }
+
     pub fn is_int(&self) -> bool {
         matches!(self.1, TokenType::Integer(_))
     }
 
+    pub fn span(&self) -> Span {
+        self.0
+    }
+
+    pub fn token_type(&self) -> &TokenType<'src> {
+        &self.1
+    }
+
+    /// Checks whether the token is of the same kind as `token_type`,
+    /// ignoring any data the variant carries.
     pub fn is_token_type(&self, token_type: TokenType) -> bool {
-        matches!(&self.1, token_type)
+        std::mem::discriminant(&self.1) == std::mem::discriminant(&token_type)
     }
 
     /// Checks if the token is an assign op.
     /// This will be useful when parsing.
     pub fn is_assign_op(&self) -> bool {
-        match &self.1 {
+        matches!(
+            &self.1,
             TokenType::AsteriskEqual
-            | TokenType::AsteriskPipeEqual
-            | TokenType::SlashEqual
-            | TokenType::PercentEqual
-            | TokenType::PlusEqual
-            | TokenType::PlusPipeEqual
-            | TokenType::MinusEqual
-            | TokenType::MinusPipeEqual
-            | TokenType::LArrow2Equal
-            | TokenType::LArrow2PipeEqual
-            | TokenType::RArrow2
-            | TokenType::AmpersandEqual
-            | TokenType::CaretEqual
-            | TokenType::PipeEqual
-            | TokenType::AsteriskPercentEqual
-            | TokenType::PlusPercentEqual
-            | TokenType::MinusPercentEqual
-            | TokenType::Equal => true,
-
-            _ => false,
-        }
+                | TokenType::AsteriskPipeEqual
+                | TokenType::SlashEqual
+                | TokenType::PercentEqual
+                | TokenType::PlusEqual
+                | TokenType::PlusPipeEqual
+                | TokenType::MinusEqual
+                | TokenType::MinusPipeEqual
+                | TokenType::LArrow2Equal
+                | TokenType::LArrow2PipeEqual
+                | TokenType::RArrow2Equal
+                | TokenType::AmpersandEqual
+                | TokenType::CaretEqual
+                | TokenType::PipeEqual
+                | TokenType::AsteriskPercentEqual
+                | TokenType::PlusPercentEqual
+                | TokenType::MinusPercentEqual
+                | TokenType::Equal
+        )
     }
 
     pub fn is_compare_op(&self) -> bool {
-        match &self.1 {
+        matches!(
+            &self.1,
             TokenType::EqualEqual
-            | TokenType::ExclamationMarkEqual
-            | TokenType::LArrow
-            | TokenType::RArrow
-            | TokenType::LArrowEqual
-            | TokenType::RArrowEqual => true,
-
-            _ => false,
-        }
+                | TokenType::ExclamationMarkEqual
+                | TokenType::LArrow
+                | TokenType::RArrow
+                | TokenType::LArrowEqual
+                | TokenType::RArrowEqual
+        )
     }
 
     pub fn is_bitwise_op(&self) -> bool {
-        match &self.1 {
+        matches!(
+            &self.1,
             TokenType::Ampersand
-            | TokenType::Caret
-            | TokenType::Pipe
-            | TokenType::Keyword(KeywordType::OrElse)
-            | TokenType::Keyword(KeywordType::Catch) => true,
-
-            _ => false,
-        }
+                | TokenType::Caret
+                | TokenType::Pipe
+                | TokenType::Keyword(KeywordType::OrElse)
+                | TokenType::Keyword(KeywordType::Catch)
+        )
     }
 }
@@ -1,7 +1,13 @@
 use once_cell::sync::Lazy;
-use std::{char, collections::HashMap};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+};
 
-use crate::tokenizer::{KeywordType, Span, Token, TokenType};
+use crate::tokenizer::{
+    BigUint, BuiltinFn, KeywordType, LexError, LexErrorKind, LosslessTokens, Span, StringLiteral,
+    Token, TokenType,
+};
 
 use super::PrimitiveType;
 
@@ -67,7 +73,7 @@ pub static KEYWORD_MAP: Lazy<HashMap<&'static str, KeywordType>> = Lazy::new(||
     ])
 });
 
-static PRIMITIVE_TYPES: Lazy<HashMap<&'static str, PrimitiveType>> = Lazy::new(|| {
+pub static PRIMITIVE_TYPES: Lazy<HashMap<&'static str, PrimitiveType>> = Lazy::new(|| {
     use PrimitiveType::*;
     HashMap::from([
         ("i8", I8),
@@ -95,6 +101,7 @@ static PRIMITIVE_TYPES: Lazy<HashMap<&'static str, PrimitiveType>> = Lazy::new(|
         ("f16", F16),
         ("f32", F32),
         ("f64", F64),
+        ("f80", F80),
         ("f128", F128),
         ("bool", Bool),
         ("anyopaque", AnyOpaque),
@@ -107,8 +114,29 @@ static PRIMITIVE_TYPES: Lazy<HashMap<&'static str, PrimitiveType>> = Lazy::new(|
     ])
 });
 
-static OPERATOR_MAP: Lazy<HashMap<&str, TokenType>> =
-    Lazy::new(|| HashMap::from([("+", TokenType::Plus)]));
+/// Looks up a primitive type by name. On top of the types in
+/// `PRIMITIVE_TYPES`, zig has arbitrary bit width integers such as
+/// `u7` or `i33`, with the width going up to 65535.
+pub fn primitive_type(name: &str) -> Option<PrimitiveType> {
+    if let Some(primitive) = PRIMITIVE_TYPES.get(name) {
+        return Some(*primitive);
+    }
+
+    let (signedness, bits) = name.split_at_checked(1)?;
+    // Leading zeros, as in `u08`, make it a regular identifier.
+    if bits.is_empty() || (bits.starts_with('0') && bits.len() > 1) {
+        return None;
+    }
+    if !bits.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let bits = bits.parse::<u16>().ok()?;
+    match signedness {
+        "i" => Some(PrimitiveType::SignedInt(bits)),
+        "u" => Some(PrimitiveType::UnsignedInt(bits)),
+        _ => None,
+    }
+}
 
 /// A helper enum to avoid repition and make the source
 /// code more readable. As zig has quite a wide range of
@@ -116,7 +144,7 @@ static OPERATOR_MAP: Lazy<HashMap<&str, TokenType>> =
 /// them each time.
 enum OperatorType {
     Equal,
-    Other(u8),
+    Other,
     Percent,
     PercentEquals,
     Pipe,
@@ -125,6 +153,23 @@ enum OperatorType {
     None,
 }
 
+#[derive(PartialEq)]
+enum CommentKind {
+    /// A plain `//` comment, including `////`.
+    Line,
+    /// A `///` doc comment.
+    Doc,
+    /// A `//!` container doc comment.
+    ContainerDoc,
+}
+
+/// The value of a decoded escape sequence. `\xNN` escapes
+/// produce raw bytes while `\u{NNNNNN}` produces a codepoint.
+enum Escaped {
+    Byte(u8),
+    CodePoint(char),
+}
+
 /// So my idea for the tokenizer is that it should be
 /// implement the standard iterator trait.
 /// That way, we can also make it peekable easily.
@@ -133,26 +178,93 @@ enum OperatorType {
 /// Ideally, I would want to use a separate `TokenStream`
 /// type that can be constructed using something like
 /// `Tokenizer.tokenize()`.
-pub struct Tokenizer {
-    src: String,
+///
+/// The tokenizer borrows the source and the tokens it emits borrow
+/// their text from it wherever possible, so tokenizing doesn't allocate
+/// unless escape sequences have to be decoded. Use `OwnedTokenizer` when
+/// the tokens have to outlive the source.
+pub struct Tokenizer<'src> {
+    src: &'src str,
     pos: usize,
 
-    /// This should probably be based on grapheme clusters.
-    /// Man positioning is difficult.
-    col: usize,
-    line: usize,
+    errors: Vec<LexError>,
+
+    /// Whether plain `//` comments are emitted as tokens
+    /// rather than being skipped like whitespace.
+    keep_comments: bool,
 }
 
-impl Tokenizer {
-    pub fn new(src: String) -> Self {
+impl<'src> Tokenizer<'src> {
+    pub fn new(src: &'src str) -> Self {
         Self {
             src,
             pos: 0,
-            col: 0,
-            line: 0,
+            errors: Vec::new(),
+            keep_comments: false,
+        }
+    }
+
+    /// Creates a tokenizer over raw bytes, such as a memory mapped file.
+    /// Zig source has to be UTF-8, so invalid UTF-8 is reported as an error
+    /// pointing at the first offending byte.
+    pub fn from_utf8(src: &'src [u8]) -> Result<Self, LexError> {
+        match std::str::from_utf8(src) {
+            Ok(src) => Ok(Self::new(src)),
+            Err(error) => {
+                let start = error.valid_up_to();
+                let len = error.error_len().unwrap_or(src.len() - start);
+                let span = Span::new(start, start + len);
+                Err(LexError::new(span, LexErrorKind::InvalidUtf8))
+            }
+        }
+    }
+
+    /// Tokenizes the whole source, failing with every error found
+    /// if there were any.
+    pub fn tokenize(mut self) -> Result<Vec<Token<'src>>, Vec<LexError>> {
+        let tokens: Vec<_> = self.by_ref().collect();
+        if self.errors.is_empty() {
+            Ok(tokens)
+        } else {
+            Err(self.errors)
         }
     }
 
+    /// Turns the tokenizer into an iterator of results, where every error
+    /// is yielded right before the token it was found in. Invalid tokens
+    /// are only reported through their errors.
+    pub fn results(self) -> Results<'src> {
+        Results {
+            tokenizer: self,
+            reported: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Turns the tokenizer into a lossless token stream, where whitespace,
+    /// newlines and comments are attached to the tokens as trivia.
+    /// See `ConcreteToken` for how the trivia is distributed.
+    pub fn lossless(self) -> LosslessTokens<'src> {
+        LosslessTokens::new(self.src, self.keep_comments(true))
+    }
+
+    /// Makes the tokenizer emit plain `//` comments as `LineComment`
+    /// tokens instead of skipping them. Doc comments are always emitted.
+    pub fn keep_comments(mut self, keep: bool) -> Self {
+        self.keep_comments = keep;
+        self
+    }
+
+    /// The errors found so far. The tokenizer recovers from errors,
+    /// so this should be checked once the tokens have been consumed.
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
+
+    fn error(&mut self, span: Span, kind: LexErrorKind) {
+        self.errors.push(LexError::new(span, kind));
+    }
+
     /// Returns the current byte in the src.
     /// According to me atleast, the operation shouldn't
     /// be expensive as it just consists of very cheap functions
@@ -168,13 +280,24 @@ impl Tokenizer {
     fn advance(&mut self) -> Option<u8> {
         let byte = self.current();
         self.pos += 1;
-        self.col += 1;
         byte
     }
 
     fn advance_steps(&mut self, step: usize) {
         self.pos += step;
-        self.col += step;
+    }
+
+    /// Returns the full character at the cursor rather than just a byte.
+    fn current_char(&self) -> Option<char> {
+        self.src.get(self.pos..)?.chars().next()
+    }
+
+    /// Consumes the remaining `rest` bytes of an operator whose first byte
+    /// has already been consumed and creates its token.
+    fn operator(&mut self, rest: usize, token_type: TokenType<'src>) -> Option<Token<'src>> {
+        self.advance_steps(rest);
+        let span = Span::new(self.pos - rest - 1, self.pos);
+        Some(Token(span, token_type))
     }
 
     fn get_operator(&mut self, op: u8) -> OperatorType {
@@ -192,70 +315,577 @@ impl Tokenizer {
                 if Some(b'=') == self.peek() {
                     OperatorType::PipeEquals
                 } else {
-                    OperatorType::PipeEquals
+                    OperatorType::Pipe
                 }
             }
             None => OperatorType::None,
-            _ => OperatorType::Other(self.current().unwrap()),
+            _ => OperatorType::Other,
+        }
+    }
+
+    /// Consumes a run of digits in the given radix. Zig allows a single
+    /// `_` between two digits as a separator, so an underscore is only
+    /// consumed when it is directly followed by another digit.
+    /// The digits are pushed onto `digits` with the separators stripped out.
+    fn consume_digits(&mut self, radix: u32, digits: &mut String) {
+        while let Some(c) = self.current() {
+            if (c as char).is_digit(radix) {
+                digits.push(c as char);
+                self.advance();
+            } else if c == b'_' && self.peek().is_some_and(|p| (p as char).is_digit(radix)) {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Consumes an optionally signed decimal exponent, as used by both
+    /// decimal (`e`) and hexadecimal (`p`) floats. The exponent marker
+    /// itself is expected to be the current byte.
+    /// Nothing is consumed if the marker is not followed by digits.
+    fn consume_exponent(&mut self) -> Option<i32> {
+        let (sign, offset) = match self.peek() {
+            Some(b'-') => (-1, 2),
+            Some(b'+') => (1, 2),
+            _ => (1, 1),
+        };
+        let first = self.src.as_bytes().get(self.pos + offset).copied()?;
+        if !first.is_ascii_digit() {
+            return None;
+        }
+        self.advance_steps(offset);
+
+        let mut digits = String::new();
+        self.consume_digits(10, &mut digits);
+        // An exponent that doesn't fit is going to over or underflow
+        // the float anyway, so saturating is good enough here.
+        Some(digits.parse::<i32>().unwrap_or(i32::MAX) * sign)
+    }
+
+    /// Reports and consumes whatever directly follows a number literal
+    /// and looks like it was meant to be part of it, such as the `2`
+    /// in `0b102` or a trailing `_`.
+    fn consume_invalid_number_suffix(&mut self, radix: u32) {
+        let Some(c) = self
+            .current()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == b'_')
+        else {
+            return;
+        };
+
+        let offset = self.pos;
+        let kind = match c {
+            b'_' if self.peek() == Some(b'_') => LexErrorKind::RepeatedUnderscore,
+            b'_' => LexErrorKind::TrailingUnderscore,
+            // The exponent would have been consumed if it had any digits.
+            b'e' | b'E' if radix == 10 => LexErrorKind::ExpectedDigits,
+            b'p' | b'P' if radix == 16 => LexErrorKind::ExpectedDigits,
+            c => LexErrorKind::InvalidDigit {
+                digit: c as char,
+                radix,
+            },
+        };
+        self.error(Span::new(offset, offset + 1), kind);
+
+        while self
+            .current()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == b'_')
+        {
+            self.advance();
+        }
+    }
+
+    /// Lexes an integer or float literal. `first` is the already consumed
+    /// first digit. Supports decimal, hex (`0x`), octal (`0o`) and binary (`0b`)
+    /// integers as well as decimal and hex floats, all with `_` separators.
+    ///
+    /// A literal ends at the first byte that can't continue it, so
+    /// `1..2` lexes as `1`, `..`, `2` rather than as a malformed float.
+    fn lex_number(&mut self, first: u8) -> Token<'src> {
+        let start = self.pos - 1;
+
+        let radix = match (first, self.current()) {
+            (b'0', Some(b'x')) => 16,
+            (b'0', Some(b'o')) => 8,
+            (b'0', Some(b'b')) => 2,
+            _ => 10,
+        };
+
+        let mut int_digits = String::new();
+        if radix == 10 {
+            int_digits.push(first as char);
+            self.consume_digits(10, &mut int_digits);
+            if first == b'0' && int_digits.len() > 1 {
+                self.error(Span::new(start, start + 1), LexErrorKind::LeadingZero);
+            }
+        } else {
+            // Skip the radix prefix.
+            self.advance();
+            self.consume_digits(radix, &mut int_digits);
+            if int_digits.is_empty() {
+                self.error(Span::new(start, self.pos), LexErrorKind::ExpectedDigits);
+                int_digits.push('0');
+            }
+        }
+
+        let is_float_radix = radix == 10 || radix == 16;
+        let mut frac_digits = String::new();
+        if is_float_radix
+            && self.current() == Some(b'.')
+            && self.peek().is_some_and(|c| (c as char).is_digit(radix))
+        {
+            self.advance();
+            self.consume_digits(radix, &mut frac_digits);
+        }
+
+        let exponent = match (radix, self.current()) {
+            (10, Some(b'e' | b'E')) | (16, Some(b'p' | b'P')) => self.consume_exponent(),
+            _ => None,
+        };
+
+        self.consume_invalid_number_suffix(radix);
+        let span = Span::new(start, self.pos);
+
+        if frac_digits.is_empty() && exponent.is_none() {
+            // `int_digits` is never empty and only holds digits of `radix`.
+            let value = BigUint::from_str_radix(&int_digits, radix).unwrap();
+            return Token(span, TokenType::Integer(value));
+        }
+
+        let value = if radix == 16 {
+            parse_hex_float(&int_digits, &frac_digits, exponent.unwrap_or(0))
+        } else {
+            let mut literal = int_digits;
+            if !frac_digits.is_empty() {
+                literal.push('.');
+                literal.push_str(&frac_digits);
+            }
+            if let Some(exponent) = exponent {
+                literal.push('e');
+                literal.push_str(&exponent.to_string());
+            }
+            // The literal only ever contains ascii digits, a dot and an exponent.
+            literal.parse::<f64>().unwrap()
+        };
+        if !value.is_finite() {
+            self.error(span, LexErrorKind::FloatOutOfRange);
+        }
+        Token(span, TokenType::Float(value))
+    }
+
+    /// Decodes an escape sequence. The `\` is expected to be already consumed.
+    /// Returns `None` if the escape is invalid, in which case an error is recorded,
+    /// or if the line ends right after the backslash, which is left to the caller
+    /// to report.
+    fn lex_escape(&mut self) -> Option<Escaped> {
+        let start = self.pos - 1;
+        let escaped = match self.current_char() {
+            None | Some('\n') => return None,
+            Some('n') => Escaped::Byte(b'\n'),
+            Some('r') => Escaped::Byte(b'\r'),
+            Some('t') => Escaped::Byte(b'\t'),
+            Some('\\') => Escaped::Byte(b'\\'),
+            Some('\'') => Escaped::Byte(b'\''),
+            Some('"') => Escaped::Byte(b'"'),
+            Some('x') => {
+                self.advance();
+                let hex = self
+                    .src
+                    .get(self.pos..self.pos + 2)
+                    .filter(|hex| hex.bytes().all(|c| c.is_ascii_hexdigit()));
+                return match hex.map(|hex| u8::from_str_radix(hex, 16).unwrap()) {
+                    Some(byte) => {
+                        self.advance_steps(2);
+                        Some(Escaped::Byte(byte))
+                    }
+                    None => {
+                        let span = Span::new(start, self.pos);
+                        self.error(span, LexErrorKind::InvalidHexEscape);
+                        None
+                    }
+                };
+            }
+            Some('u') => {
+                self.advance();
+                return self.lex_unicode_escape(start);
+            }
+            Some(c) => {
+                self.advance_steps(c.len_utf8());
+                let span = Span::new(start, self.pos);
+                self.error(span, LexErrorKind::InvalidEscapeCharacter(c));
+                return None;
+            }
+        };
+        self.advance();
+        Some(escaped)
+    }
+
+    /// Decodes the `{NNNNNN}` part of a `\u{NNNNNN}` escape.
+    /// `start` is the offset of the backslash, for error reporting.
+    fn lex_unicode_escape(&mut self, start: usize) -> Option<Escaped> {
+        if self.current() != Some(b'{') {
+            let span = Span::new(start, self.pos);
+            self.error(span, LexErrorKind::InvalidUnicodeEscape);
+            return None;
+        }
+        self.advance();
+
+        let mut digits = String::new();
+        while let Some(c) = self.current().filter(u8::is_ascii_hexdigit) {
+            digits.push(c as char);
+            self.advance();
+        }
+        if self.current() != Some(b'}') || digits.is_empty() || digits.len() > 6 {
+            if self.current() == Some(b'}') {
+                self.advance();
+            }
+            let span = Span::new(start, self.pos);
+            self.error(span, LexErrorKind::InvalidUnicodeEscape);
+            return None;
+        }
+        self.advance();
+
+        let value = u32::from_str_radix(&digits, 16).unwrap();
+        match char::from_u32(value) {
+            Some(c) => Some(Escaped::CodePoint(c)),
+            None => {
+                let span = Span::new(start, self.pos);
+                self.error(span, LexErrorKind::InvalidUnicodeCodepoint(value));
+                None
+            }
         }
     }
 
-    pub fn next_token(&mut self) -> Option<Token> {
+    /// Lexes a `"..."` string literal. The opening quote is expected
+    /// to be already consumed. Zig string literals can't span multiple
+    /// lines, so reaching a newline means the literal is unterminated.
+    /// Invalid escapes are reported and left out of the value.
+    fn lex_string(&mut self) -> Token<'src> {
+        let start = self.pos - 1;
+        // The value can be borrowed from the source until the first
+        // escape sequence, after which it has to be decoded into a buffer.
+        let mut decoded: Option<Vec<u8>> = None;
+
+        let content_end = loop {
+            match self.current_char() {
+                None | Some('\n') => {
+                    let span = Span::new(start, self.pos);
+                    self.error(span, LexErrorKind::UnterminatedString);
+                    break self.pos;
+                }
+                Some('"') => {
+                    self.advance();
+                    break self.pos - 1;
+                }
+                Some('\\') => {
+                    let value = decoded
+                        .get_or_insert_with(|| self.src.as_bytes()[start + 1..self.pos].to_vec());
+                    self.advance();
+                    match self.lex_escape() {
+                        Some(Escaped::Byte(byte)) => value.push(byte),
+                        Some(Escaped::CodePoint(c)) => {
+                            value.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes())
+                        }
+                        None => {}
+                    }
+                }
+                Some(c) => {
+                    if c.is_ascii_control() {
+                        let span = Span::new(self.pos, self.pos + 1);
+                        self.error(span, LexErrorKind::InvalidCharacter(c));
+                    }
+                    if let Some(value) = &mut decoded {
+                        value.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                    }
+                    self.advance_steps(c.len_utf8());
+                }
+            }
+        };
+
+        let value = match decoded {
+            Some(value) => Cow::Owned(value),
+            None => Cow::Borrowed(&self.src.as_bytes()[start + 1..content_end]),
+        };
+        let raw = &self.src[start..self.pos];
+        let span = Span::new(start, self.pos);
+        Token(
+            span,
+            TokenType::StringLiteral(StringLiteral::new(raw, value)),
+        )
+    }
+
+    /// Lexes a `'...'` character literal. The opening quote is expected
+    /// to be already consumed. Invalid literals are reported and produce
+    /// a `Char` token holding the unicode replacement character.
+    fn lex_char(&mut self) -> Token<'src> {
+        let start = self.pos - 1;
+
+        let value = match self.current_char() {
+            None | Some('\n') => None,
+            Some('\'') => {
+                self.advance();
+                let span = Span::new(start, self.pos);
+                self.error(span, LexErrorKind::EmptyChar);
+                return Token(span, TokenType::Char(char::REPLACEMENT_CHARACTER));
+            }
+            Some('\\') => {
+                self.advance();
+                match self.lex_escape() {
+                    Some(Escaped::Byte(byte)) => Some(byte as char),
+                    Some(Escaped::CodePoint(c)) => Some(c),
+                    // The error has already been reported.
+                    None => Some(char::REPLACEMENT_CHARACTER),
+                }
+            }
+            Some(c) => {
+                self.advance_steps(c.len_utf8());
+                Some(c)
+            }
+        };
+
+        if let Some(value) = value.filter(|_| self.current() == Some(b'\'')) {
+            self.advance();
+            let span = Span::new(start, self.pos);
+            return Token(span, TokenType::Char(value));
+        }
+
+        // Anything else is either an unterminated literal or one with
+        // too many codepoints, which one depends on whether we find
+        // the closing quote before the end of the line.
+        while let Some(c) = self.current_char() {
+            match c {
+                '\n' => break,
+                '\'' => {
+                    self.advance();
+                    let span = Span::new(start, self.pos);
+                    self.error(span, LexErrorKind::MultipleCodepointsInChar);
+                    return Token(span, TokenType::Char(char::REPLACEMENT_CHARACTER));
+                }
+                // Skip over escaped quotes so `'a\''` is reported as a whole.
+                '\\' if self.peek() == Some(b'\'') => self.advance_steps(2),
+                c => self.advance_steps(c.len_utf8()),
+            }
+        }
+        let span = Span::new(start, self.pos);
+        self.error(span, LexErrorKind::UnterminatedChar);
+        Token(span, TokenType::Char(char::REPLACEMENT_CHARACTER))
+    }
+
+    /// Lexes consecutive `\\` lines into a single multiline string literal.
+    /// The first `\` is expected to be already consumed. Only whitespace may
+    /// come between the end of one line and the `\\` of the next.
+    fn lex_multiline_string(&mut self) -> Token<'src> {
+        let start = self.pos - 1;
+        let mut lines = Vec::new();
+
+        // Skip the second `\`.
+        self.advance();
+        loop {
+            let content_start = self.pos;
+            while !matches!(self.current(), None | Some(b'\n')) {
+                if self.current() == Some(b'\t') {
+                    let span = Span::new(self.pos, self.pos + 1);
+                    self.error(span, LexErrorKind::TabInMultilineString);
+                }
+                self.advance();
+            }
+            let content = &self.src.as_bytes()[content_start..self.pos];
+            lines.push(content.strip_suffix(b"\r").unwrap_or(content));
+
+            let indent = self.src.as_bytes()[self.pos..]
+                .iter()
+                .skip(1)
+                .take_while(|c| matches!(c, b' ' | b'\t' | b'\r'))
+                .count();
+            if self
+                .src
+                .as_bytes()
+                .get(self.pos + 1 + indent..self.pos + 3 + indent)
+                != Some(&b"\\\\"[..])
+            {
+                break;
+            }
+
+            self.advance_steps(indent + 3);
+        }
+
+        // A single line can be borrowed as is, anything more needs the
+        // lines joined without the indentation between them.
+        let value = match lines.as_slice() {
+            [line] => Cow::Borrowed(*line),
+            lines => Cow::Owned(lines.join(&b'\n')),
+        };
+        let raw = &self.src[start..self.pos];
+        let span = Span::new(start, self.pos);
+        Token(
+            span,
+            TokenType::MultilineStringLiteral(StringLiteral::new(raw, value)),
+        )
+    }
+
+    /// Lexes an identifier, keyword or primitive type. The first
+    /// character is expected to be already consumed.
+    fn lex_identifier(&mut self) -> Token<'src> {
+        let start = self.pos - 1;
+        while self
+            .current()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == b'_')
+        {
+            self.advance();
+        }
+
+        let identifier = &self.src[start..self.pos];
+        let span = Span::new(start, self.pos);
+        let token_type = if let Some(keyword) = KEYWORD_MAP.get(identifier) {
+            TokenType::Keyword(*keyword)
+        } else if let Some(primitive) = primitive_type(identifier) {
+            TokenType::PrimitiveType(primitive)
+        } else {
+            TokenType::Identifier(Cow::Borrowed(identifier))
+        };
+        Token(span, token_type)
+    }
+
+    /// Lexes an `@"..."` identifier, which allows any string to be used
+    /// as an identifier, including keywords. Only the `@` is expected to
+    /// be consumed. The same escapes as in string literals are allowed.
+    fn lex_quoted_identifier(&mut self) -> Token<'src> {
+        self.advance();
+        let Token(span, token_type) = self.lex_string();
+        let TokenType::StringLiteral(literal) = token_type else {
+            unreachable!("lex_string always produces a string literal")
+        };
+
+        let span = Span::new(span.start - 1, span.end);
+        // Literals with invalid escapes or no closing quote were reported already.
+        if literal.raw == "\"\"" {
+            self.error(span, LexErrorKind::EmptyIdentifier);
+        }
+        let name = match literal.value {
+            Cow::Borrowed(value) => String::from_utf8_lossy(value),
+            Cow::Owned(value) => Cow::Owned(String::from_utf8_lossy(&value).into_owned()),
+        };
+        Token(span, TokenType::Identifier(name))
+    }
+
+    /// Lexes an `@name` builtin. Only the `@` is expected to be consumed.
+    /// Unknown builtins are reported but still produce a `Builtin` token.
+    fn lex_builtin(&mut self) -> Token<'src> {
+        let start = self.pos - 1;
+        while self
+            .current()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == b'_')
+        {
+            self.advance();
+        }
+
+        let name = &self.src[start + 1..self.pos];
+        let span = Span::new(start, self.pos);
+        if BuiltinFn::from_name(name).is_none() {
+            self.error(span, LexErrorKind::UnknownBuiltin(name.to_string()));
+        }
+        Token(span, TokenType::Builtin(Cow::Borrowed(name)))
+    }
+
+    /// Classifies the `//` comment starting at the cursor.
+    fn comment_kind(&self) -> CommentKind {
+        let rest = &self.src.as_bytes()[self.pos..];
+        if rest.starts_with(b"//!") {
+            CommentKind::ContainerDoc
+        } else if rest.starts_with(b"///") && !rest.starts_with(b"////") {
+            CommentKind::Doc
+        } else {
+            CommentKind::Line
+        }
+    }
+
+    /// Skips over whitespace and, unless they are being kept,
+    /// plain line comments. Doc comments are always tokens.
+    fn skip_trivia(&mut self) {
+        loop {
+            match self.current() {
+                Some(b' ' | b'\t' | b'\r') => {
+                    self.advance();
+                }
+                Some(b'\n') => {
+                    self.advance();
+                }
+                Some(b'/')
+                    if self.peek() == Some(b'/')
+                        && !self.keep_comments
+                        && self.comment_kind() == CommentKind::Line =>
+                {
+                    while !matches!(self.current(), None | Some(b'\n')) {
+                        self.advance();
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Lexes a `//`, `///` or `//!` comment up to the end of the line.
+    /// The text of the token is everything after the slashes (or `!`),
+    /// without the line ending.
+    fn lex_comment(&mut self) -> Token<'src> {
+        // The first slash has already been consumed.
+        self.pos -= 1;
+        let start = self.pos;
+        let kind = self.comment_kind();
+
+        while !matches!(self.current(), None | Some(b'\n')) {
+            self.advance();
+        }
+
+        let prefix = if kind == CommentKind::Line { 2 } else { 3 };
+        let text = &self.src[start + prefix..self.pos];
+        let text = Cow::Borrowed(text.strip_suffix('\r').unwrap_or(text));
+        let span = Span::new(start, self.pos);
+        let token_type = match kind {
+            CommentKind::Line => TokenType::LineComment(text),
+            CommentKind::Doc => TokenType::DocComment(text),
+            CommentKind::ContainerDoc => TokenType::ContainerDocComment(text),
+        };
+        Token(span, token_type)
+    }
+
+    pub fn next_token(&mut self) -> Option<Token<'src>> {
+        self.skip_trivia();
         if let Some(c) = self.advance() {
             match c {
-                b'{' => Some(Token(
-                    Span::new(self.col - 1, 1, self.line),
-                    TokenType::LBrace,
-                )),
+                b'{' => Some(Token(Span::new(self.pos - 1, self.pos), TokenType::LBrace)),
                 b'[' => Some(Token(
-                    Span::new(self.col - 1, 1, self.line),
+                    Span::new(self.pos - 1, self.pos),
                     TokenType::LBracket,
                 )),
-                b'(' => Some(Token(
-                    Span::new(self.col - 1, 1, self.line),
-                    TokenType::LParen,
-                )),
-                b'}' => Some(Token(
-                    Span::new(self.col - 1, 1, self.line),
-                    TokenType::RBrace,
-                )),
+                b'(' => Some(Token(Span::new(self.pos - 1, self.pos), TokenType::LParen)),
+                b'}' => Some(Token(Span::new(self.pos - 1, self.pos), TokenType::RBrace)),
                 b']' => Some(Token(
-                    Span::new(self.col - 1, 1, self.line),
+                    Span::new(self.pos - 1, self.pos),
                     TokenType::RBracket,
                 )),
-                b')' => Some(Token(
-                    Span::new(self.col - 1, 1, self.line),
-                    TokenType::RParen,
-                )),
+                b')' => Some(Token(Span::new(self.pos - 1, self.pos), TokenType::RParen)),
                 b'?' => Some(Token(
-                    Span::new(self.col - 1, 1, self.line),
+                    Span::new(self.pos - 1, self.pos),
                     TokenType::QuestionMark,
                 )),
                 b';' => Some(Token(
-                    Span::new(self.col - 1, 1, self.line),
+                    Span::new(self.pos - 1, self.pos),
                     TokenType::Semicolon,
                 )),
-                b':' => Some(Token(
-                    Span::new(self.col - 1, 1, self.line),
-                    TokenType::Colon,
-                )),
-                b',' => Some(Token(
-                    Span::new(self.col - 1, 1, self.line),
-                    TokenType::Comma,
-                )),
-                b'~' => Some(Token(
-                    Span::new(self.col - 1, 1, self.line),
-                    TokenType::Tilde,
-                )),
+                b':' => Some(Token(Span::new(self.pos - 1, self.pos), TokenType::Colon)),
+                b',' => Some(Token(Span::new(self.pos - 1, self.pos), TokenType::Comma)),
+                b'~' => Some(Token(Span::new(self.pos - 1, self.pos), TokenType::Tilde)),
 
                 b'&' => match self.current() {
                     Some(b'=') => {
                         self.advance();
-                        let pos = Span::new(self.col - 2, 2, self.line);
+                        let pos = Span::new(self.pos - 2, self.pos);
                         Some(Token(pos, TokenType::AmpersandEqual))
                     }
                     _ => Some(Token(
-                        Span::new(self.col - 1, 1, self.line),
+                        Span::new(self.pos - 1, self.pos),
                         TokenType::Ampersand,
                     )),
                 },
@@ -263,23 +893,20 @@ impl Tokenizer {
                 b'^' => match self.current() {
                     Some(b'=') => {
                         self.advance();
-                        let span = Span::new(self.col - 2, 2, self.line);
+                        let span = Span::new(self.pos - 2, self.pos);
                         Some(Token(span, TokenType::CaretEqual))
                     }
-                    _ => Some(Token(
-                        Span::new(self.col - 1, 1, self.line),
-                        TokenType::Caret,
-                    )),
+                    _ => Some(Token(Span::new(self.pos - 1, self.pos), TokenType::Caret)),
                 },
 
                 b'!' => match self.current() {
                     Some(b'=') => {
                         self.advance();
-                        let span = Span::new(self.col - 2, 2, self.line);
+                        let span = Span::new(self.pos - 2, self.pos);
                         Some(Token(span, TokenType::ExclamationMarkEqual))
                     }
                     _ => Some(Token(
-                        Span::new(self.col - 1, 1, self.line),
+                        Span::new(self.pos - 1, self.pos),
                         TokenType::ExclamationMark,
                     )),
                 },
@@ -287,174 +914,150 @@ impl Tokenizer {
                 b'%' => match self.current() {
                     Some(b'=') => {
                         self.advance();
-                        let span = Span::new(self.col - 2, 2, self.line);
+                        let span = Span::new(self.pos - 2, self.pos);
                         Some(Token(span, TokenType::PercentEqual))
                     }
-                    _ => Some(Token(
-                        Span::new(self.col - 1, 1, self.line),
-                        TokenType::Percent,
-                    )),
+                    _ => Some(Token(Span::new(self.pos - 1, self.pos), TokenType::Percent)),
                 },
 
                 b'/' => match self.current() {
+                    Some(b'/') => Some(self.lex_comment()),
                     Some(b'=') => {
                         self.advance();
-                        let span = Span::new(self.col - 2, 2, self.line);
+                        let span = Span::new(self.pos - 2, self.pos);
                         Some(Token(span, TokenType::SlashEqual))
                     }
-                    _ => Some(Token(
-                        Span::new(self.col - 1, 1, self.line),
-                        TokenType::Slash,
-                    )),
+                    _ => Some(Token(Span::new(self.pos - 1, self.pos), TokenType::Slash)),
                 },
 
                 b'=' => match self.current() {
                     Some(b'=') => {
                         self.advance();
-                        let span = Span::new(self.col - 2, 2, self.line);
+                        let span = Span::new(self.pos - 2, self.pos);
                         Some(Token(span, TokenType::EqualEqual))
                     }
                     Some(b'>') => {
                         self.advance();
-                        let span = Span::new(self.col - 2, 2, self.line);
+                        let span = Span::new(self.pos - 2, self.pos);
                         Some(Token(span, TokenType::EqualArrow))
                     }
-                    _ => Some(Token(
-                        Span::new(self.col - 1, 1, self.line),
-                        TokenType::Equal,
-                    )),
+                    _ => Some(Token(Span::new(self.pos - 1, self.pos), TokenType::Equal)),
                 },
                 b'|' => match self.current() {
                     Some(b'|') => {
                         self.advance();
-                        let span = Span::new(self.col - 2, 2, self.line);
+                        let span = Span::new(self.pos - 2, self.pos);
                         Some(Token(span, TokenType::Pipe2))
                     }
                     Some(b'=') => {
                         self.advance();
-                        let span = Span::new(self.col - 2, 2, self.line);
+                        let span = Span::new(self.pos - 2, self.pos);
                         Some(Token(span, TokenType::PipeEqual))
                     }
-                    _ => Some(Token(
-                        Span::new(self.col - 1, 2, self.line),
-                        TokenType::Pipe,
-                    )),
+                    _ => Some(Token(Span::new(self.pos - 1, self.pos), TokenType::Pipe)),
                 },
 
                 b'.' => match self.current() {
                     Some(b'*') => {
                         self.advance();
-                        let span = Span::new(self.col - 2, 2, self.line);
+                        let span = Span::new(self.pos - 2, self.pos);
                         Some(Token(span, TokenType::DotAsterisk))
                     }
                     Some(b'?') => {
                         self.advance();
-                        let span = Span::new(self.col - 2, 2, self.line);
+                        let span = Span::new(self.pos - 2, self.pos);
                         Some(Token(span, TokenType::DotQuestionMark))
                     }
                     Some(b'.') => {
                         self.advance();
                         if self.current() == Some(b'.') {
                             self.advance();
-                            let span = Span::new(self.col - 3, 3, self.line);
+                            let span = Span::new(self.pos - 3, self.pos);
                             Some(Token(span, TokenType::Dot3))
                         } else {
-                            let span = Span::new(self.col - 2, 2, self.line);
+                            let span = Span::new(self.pos - 2, self.pos);
                             Some(Token(span, TokenType::Dot2))
                         }
                     }
-                    _ => Some(Token(Span::new(self.col - 1, 1, self.line), TokenType::Dot)),
+                    _ => Some(Token(Span::new(self.pos - 1, self.pos), TokenType::Dot)),
                 },
 
                 b'*' => match self.get_operator(c) {
-                    OperatorType::Same => {
-                        self.advance();
-                        let span = Span::new(self.col - 1, 2, self.line);
-                        Some(Token(span, TokenType::Asterisk2))
-                    }
-                    OperatorType::Equal => {
-                        self.advance();
-                        let span = Span::new(self.col - 1, 2, self.line);
-                        Some(Token(span, TokenType::AsteriskEqual))
-                    }
-                    OperatorType::Percent => {
-                        self.advance();
-                        let span = Span::new(self.col - 1, 2, self.line);
-                        Some(Token(span, TokenType::AsteriskPercent))
-                    }
+                    OperatorType::Same => self.operator(1, TokenType::Asterisk2),
+                    OperatorType::Equal => self.operator(1, TokenType::AsteriskEqual),
+                    OperatorType::Percent => self.operator(1, TokenType::AsteriskPercent),
                     OperatorType::PercentEquals => {
-                        self.advance_steps(2);
-                        let span = Span::new(self.col - 2, 3, self.line);
-                        Some(Token(span, TokenType::AsteriskPercentEqual))
-                    }
-                    OperatorType::Pipe => {
-                        self.advance();
-                        let span = Span::new(self.col - 2, 2, self.line);
-                        Some(Token(span, TokenType::AsteriskPipe))
-                    }
-                    OperatorType::PipeEquals => {
-                        self.advance_steps(2);
-                        let span = Span::new(self.col - 3, 3, self.line);
-                        Some(Token(span, TokenType::AsteriskPipeEqual))
-                    }
-                    _ => {
-                        let span = Span::new(self.col - 1, 1, self.line);
-                        Some(Token(span, TokenType::Asterisk))
+                        self.operator(2, TokenType::AsteriskPercentEqual)
                     }
+                    OperatorType::Pipe => self.operator(1, TokenType::AsteriskPipe),
+                    OperatorType::PipeEquals => self.operator(2, TokenType::AsteriskPipeEqual),
+                    _ => self.operator(0, TokenType::Asterisk),
                 },
 
-                b'a'..=b'z' | b'A'..=b'Z' => {
-                    let pos = self.pos;
-                    let mut identifier = Vec::new();
-                    identifier.push(c);
-                    'identifier: while let Some(character) = self.peek() {
-                        if character.is_ascii_alphanumeric() {
-                            identifier.push(character);
-                            self.advance();
+                b'+' => match self.get_operator(c) {
+                    OperatorType::Same => self.operator(1, TokenType::Plus2),
+                    OperatorType::Equal => self.operator(1, TokenType::PlusEqual),
+                    OperatorType::Percent => self.operator(1, TokenType::PlusPercent),
+                    OperatorType::PercentEquals => self.operator(2, TokenType::PlusPercentEqual),
+                    OperatorType::Pipe => self.operator(1, TokenType::PlusPipe),
+                    OperatorType::PipeEquals => self.operator(2, TokenType::PlusPipeEqual),
+                    _ => self.operator(0, TokenType::Plus),
+                },
+
+                // There is no `--` in zig, so `Same` is just a minus
+                // followed by another one.
+                b'-' => match self.get_operator(c) {
+                    _ if self.current() == Some(b'>') => self.operator(1, TokenType::MinusArrow),
+                    OperatorType::Equal => self.operator(1, TokenType::MinusEqual),
+                    OperatorType::Percent => self.operator(1, TokenType::MinusPercent),
+                    OperatorType::PercentEquals => self.operator(2, TokenType::MinusPercentEqual),
+                    OperatorType::Pipe => self.operator(1, TokenType::MinusPipe),
+                    OperatorType::PipeEquals => self.operator(2, TokenType::MinusPipeEqual),
+                    _ => self.operator(0, TokenType::Minus),
+                },
+
+                b'<' => match (self.current(), self.peek()) {
+                    (Some(b'<'), Some(b'|')) => {
+                        if self.src.as_bytes().get(self.pos + 2) == Some(&b'=') {
+                            self.operator(3, TokenType::LArrow2PipeEqual)
                         } else {
-                            break 'identifier;
+                            self.operator(2, TokenType::LArrow2Pipe)
                         }
                     }
-                    let span = Span::new(pos, identifier.len().try_into().unwrap(), self.line);
-                    Some(Token(
-                        span,
-                        TokenType::String(String::from_utf8(identifier).unwrap()),
-                    ))
-                }
-                b'0'..=b'9' => {
-                    self.pos += 1;
-                    let span = Span::new(self.pos, 1, self.line);
-                    Some(Token(span, TokenType::Integer(12)))
-                }
-                b' ' | b'\t' | b'\n' | b'\r' => {
-                    if c == b'\n' {
-                        self.line += 1;
-                        self.col = 0;
-                    }
-                    while let Some(w) = self.current() {
-                        match w {
-                            b' ' | b'\t' | b'\r' => {
-                                self.advance();
-                            }
-                            b'\n' => {
-                                self.pos += 1;
-                                self.line += 1;
-                                self.col = 0;
-                            }
-                            _ => return self.next_token(),
-                        }
-                    }
-                    None
+                    (Some(b'<'), Some(b'=')) => self.operator(2, TokenType::LArrow2Equal),
+                    (Some(b'<'), _) => self.operator(1, TokenType::LArrow2),
+                    (Some(b'='), _) => self.operator(1, TokenType::LArrowEqual),
+                    _ => self.operator(0, TokenType::LArrow),
+                },
+
+                b'>' => match (self.current(), self.peek()) {
+                    (Some(b'>'), Some(b'=')) => self.operator(2, TokenType::RArrow2Equal),
+                    (Some(b'>'), _) => self.operator(1, TokenType::RArrow2),
+                    (Some(b'='), _) => self.operator(1, TokenType::RArrowEqual),
+                    _ => self.operator(0, TokenType::RArrow),
+                },
+
+                b'a'..=b'z' | b'A'..=b'Z' | b'_' => Some(self.lex_identifier()),
+                b'@' if self.current() == Some(b'"') => Some(self.lex_quoted_identifier()),
+                b'@' if self
+                    .current()
+                    .is_some_and(|c| c.is_ascii_alphabetic() || c == b'_') =>
+                {
+                    Some(self.lex_builtin())
                 }
+                b'0'..=b'9' => Some(self.lex_number(c)),
+                b'"' => Some(self.lex_string()),
+                b'\'' => Some(self.lex_char()),
+                b'\\' if self.current() == Some(b'\\') => Some(self.lex_multiline_string()),
                 _ => {
                     let err_src = self.src.get((self.pos - 1)..).unwrap();
 
                     let unknown_c = err_src.chars().next().unwrap();
+                    let start = self.pos - 1;
                     self.pos += unknown_c.len_utf8() - 1;
-                    // self.col += 1;
-                    // println!("{err_src:?}");
-                    let span = Span::new(self.col - 1, unknown_c.len_utf8(), self.line);
-                    Some(Token(span, TokenType::Unknown(unknown_c)))
+                    let span = Span::new(start, self.pos);
+                    self.error(span, LexErrorKind::InvalidCharacter(unknown_c));
+                    Some(Token(span, TokenType::Invalid))
                 }
             }
         } else {
@@ -463,8 +1066,100 @@ impl Tokenizer {
     }
 }
 
-impl Iterator for Tokenizer {
-    type Item = Token;
+impl<'src> Iterator for Tokenizer<'src> {
+    type Item = Token<'src>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token()
+    }
+}
+
+/// Computes the value of a hex float such as `0x1.8p3` from its
+/// separator free integer and fractional hex digits and its binary exponent.
+fn parse_hex_float(int_digits: &str, frac_digits: &str, exponent: i32) -> f64 {
+    let mantissa = int_digits
+        .chars()
+        .chain(frac_digits.chars())
+        .fold(0.0, |acc, c| acc * 16.0 + c.to_digit(16).unwrap() as f64);
+    let exponent = exponent.saturating_sub(4 * frac_digits.len() as i32);
+    mantissa * 2f64.powi(exponent)
+}
+
+/// The iterator returned by `Tokenizer::results`.
+pub struct Results<'src> {
+    tokenizer: Tokenizer<'src>,
+    /// How many of the tokenizer's errors have been queued up already.
+    reported: usize,
+    pending: VecDeque<Result<Token<'src>, LexError>>,
+}
+
+impl<'src> Iterator for Results<'src> {
+    type Item = Result<Token<'src>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            let token = self.tokenizer.next_token();
+            let errors = &self.tokenizer.errors[self.reported..];
+            self.pending.extend(errors.iter().cloned().map(Err));
+            self.reported = self.tokenizer.errors.len();
+
+            match token {
+                Some(Token(_, TokenType::Invalid)) => {}
+                Some(token) => self.pending.push_back(Ok(token)),
+                None => break,
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+/// A tokenizer that owns its source, for when the tokens have to
+/// outlive it. It is a thin wrapper around `Tokenizer` that converts
+/// every token into an owned one as it is emitted.
+pub struct OwnedTokenizer {
+    src: String,
+    pos: usize,
+    errors: Vec<LexError>,
+    keep_comments: bool,
+}
+
+impl OwnedTokenizer {
+    pub fn new(src: String) -> Self {
+        Self {
+            src,
+            pos: 0,
+            errors: Vec::new(),
+            keep_comments: false,
+        }
+    }
+
+    /// See `Tokenizer::keep_comments`.
+    pub fn keep_comments(mut self, keep: bool) -> Self {
+        self.keep_comments = keep;
+        self
+    }
+
+    /// See `Tokenizer::errors`.
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
+
+    pub fn next_token(&mut self) -> Option<Token<'static>> {
+        let mut tokenizer = Tokenizer {
+            src: &self.src,
+            pos: self.pos,
+            errors: std::mem::take(&mut self.errors),
+            keep_comments: self.keep_comments,
+        };
+        let token = tokenizer.next_token().map(Token::into_owned);
+        self.pos = tokenizer.pos;
+        self.errors = tokenizer.errors;
+        token
+    }
+}
+
+impl Iterator for OwnedTokenizer {
+    type Item = Token<'static>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.next_token()
@@ -0,0 +1,210 @@
+use std::iter::Peekable;
+
+use super::{Span, Token, TokenType, Tokenizer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    /// A run of spaces, tabs and carriage returns.
+    Whitespace,
+    /// A single `\n`.
+    Newline,
+    /// A plain `//` comment, without its line ending.
+    /// Doc comments are tokens rather than trivia.
+    Comment,
+}
+
+/// Source text that doesn't matter to the parser but has to be
+/// kept around to reproduce the source exactly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia<'src> {
+    pub(crate) kind: TriviaKind,
+    pub(crate) span: Span,
+    pub(crate) text: &'src str,
+}
+
+impl<'src> Trivia<'src> {
+    pub fn kind(&self) -> TriviaKind {
+        self.kind
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn text(&self) -> &'src str {
+        self.text
+    }
+}
+
+/// A token along with the trivia around it. Trivia following a token on
+/// the same line is trailing trivia of that token, everything from the
+/// next newline on is leading trivia of the token after it. The trivia
+/// at the end of the source is leading trivia of a final `Eof` token.
+///
+/// Concatenating the leading trivia, text and trailing trivia of every
+/// token in a stream gives back the original source byte for byte.
+#[derive(Debug, Clone)]
+pub struct ConcreteToken<'src> {
+    pub(crate) leading: Vec<Trivia<'src>>,
+    pub(crate) token: Token<'src>,
+    pub(crate) text: &'src str,
+    pub(crate) trailing: Vec<Trivia<'src>>,
+}
+
+impl<'src> ConcreteToken<'src> {
+    pub fn leading(&self) -> &[Trivia<'src>] {
+        &self.leading
+    }
+
+    pub fn token(&self) -> &Token<'src> {
+        &self.token
+    }
+
+    /// The source text of the token itself, without any trivia.
+    pub fn text(&self) -> &'src str {
+        self.text
+    }
+
+    pub fn trailing(&self) -> &[Trivia<'src>] {
+        &self.trailing
+    }
+
+    /// Appends the token and all of its trivia to `out`.
+    pub fn write_to(&self, out: &mut String) {
+        self.leading
+            .iter()
+            .for_each(|trivia| out.push_str(trivia.text));
+        out.push_str(self.text);
+        self.trailing
+            .iter()
+            .for_each(|trivia| out.push_str(trivia.text));
+    }
+}
+
+/// A token or a piece of trivia, in source order.
+enum Piece<'src> {
+    Token(Token<'src>),
+    Trivia(Trivia<'src>),
+}
+
+/// Splits the source into tokens and trivia. Anything the tokenizer
+/// skips between two tokens is whitespace, as comments are kept as
+/// `LineComment` tokens and turned into trivia here.
+struct Pieces<'src> {
+    src: &'src str,
+    tokenizer: Tokenizer<'src>,
+    /// The end of the last piece handed out.
+    pos: usize,
+    /// The token after the gap currently being split into trivia.
+    next: Option<Token<'src>>,
+    done: bool,
+}
+
+impl<'src> Pieces<'src> {
+    fn trivia(&mut self, kind: TriviaKind, end: usize) -> Piece<'src> {
+        let span = Span::new(self.pos, end);
+        self.pos = end;
+        Piece::Trivia(Trivia {
+            kind,
+            span,
+            text: span.slice(self.src),
+        })
+    }
+}
+
+impl<'src> Iterator for Pieces<'src> {
+    type Item = Piece<'src>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next.is_none() && !self.done {
+            self.next = self.tokenizer.next_token();
+            self.done = self.next.is_none();
+        }
+        let gap_end = self.next.as_ref().map_or(self.src.len(), |t| t.0.start);
+
+        if self.pos < gap_end {
+            let gap = &self.src.as_bytes()[self.pos..gap_end];
+            if gap[0] == b'\n' {
+                return Some(self.trivia(TriviaKind::Newline, self.pos + 1));
+            }
+            let len = gap.iter().take_while(|&&c| c != b'\n').count();
+            return Some(self.trivia(TriviaKind::Whitespace, self.pos + len));
+        }
+
+        let token = self.next.take()?;
+        self.pos = token.0.end;
+        match token.1 {
+            TokenType::LineComment(_) => Some(Piece::Trivia(Trivia {
+                kind: TriviaKind::Comment,
+                span: token.0,
+                text: token.0.slice(self.src),
+            })),
+            _ => Some(Piece::Token(token)),
+        }
+    }
+}
+
+/// The iterator returned by `Tokenizer::lossless`.
+pub struct LosslessTokens<'src> {
+    src: &'src str,
+    pieces: Peekable<Pieces<'src>>,
+    finished: bool,
+}
+
+impl<'src> LosslessTokens<'src> {
+    pub(crate) fn new(src: &'src str, tokenizer: Tokenizer<'src>) -> Self {
+        let pieces = Pieces {
+            src,
+            tokenizer,
+            pos: 0,
+            next: None,
+            done: false,
+        };
+        Self {
+            src,
+            pieces: pieces.peekable(),
+            finished: false,
+        }
+    }
+}
+
+impl<'src> Iterator for LosslessTokens<'src> {
+    type Item = ConcreteToken<'src>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let mut leading = Vec::new();
+        let token = loop {
+            match self.pieces.next() {
+                Some(Piece::Trivia(trivia)) => leading.push(trivia),
+                Some(Piece::Token(token)) => break token,
+                None => {
+                    self.finished = true;
+                    let end = Span::new(self.src.len(), self.src.len());
+                    break Token(end, TokenType::Eof);
+                }
+            }
+        };
+
+        let mut trailing = Vec::new();
+        while let Some(Piece::Trivia(trivia)) = self.pieces.peek() {
+            if trivia.kind == TriviaKind::Newline {
+                break;
+            }
+            if let Some(Piece::Trivia(trivia)) = self.pieces.next() {
+                trailing.push(trivia);
+            }
+        }
+
+        let text = token.0.slice(self.src);
+        Some(ConcreteToken {
+            leading,
+            token,
+            text,
+            trailing,
+        })
+    }
+}
@@ -0,0 +1,87 @@
+use rusty_zig::{
+    ast::{
+        compact::{flags, FnDecl, FnProto, NodeTag, VarDecl},
+        CompactAst,
+    },
+    parser,
+};
+
+const SRC: &str = "\
+const std = @import(\"std\");
+
+pub fn add(a: i32, comptime b: i32) i32 {
+    return a + b;
+}
+
+var x: u8 = 1;
+";
+
+#[test]
+fn root_decls_point_at_their_main_tokens() {
+    let ast = CompactAst::parse(SRC);
+    assert!(!ast.has_errors(), "{:?}", ast.errors());
+
+    let decls = ast.root_decls();
+    let tags: Vec<_> = decls.iter().map(|&decl| ast.node_tag(decl)).collect();
+    assert_eq!(tags, [NodeTag::VarDecl, NodeTag::FnDecl, NodeTag::VarDecl]);
+    let main_tokens: Vec<_> = decls
+        .iter()
+        .map(|&decl| {
+            let token = ast.main_token(decl);
+            (ast.token_slice(token), ast.token_slice(token + 1))
+        })
+        .collect();
+    assert_eq!(main_tokens, [("const", "std"), ("fn", "add"), ("var", "x")]);
+}
+
+#[test]
+fn extra_data_holds_what_doesnt_fit_in_a_node() {
+    let ast = CompactAst::parse(SRC);
+    let decls = ast.root_decls();
+
+    let data = ast.node_data(decls[0]);
+    let var: VarDecl = ast.extra(data.lhs);
+    assert_eq!(var.ty, 0);
+    assert_eq!(ast.node_tag(data.rhs), NodeTag::BuiltinCall);
+    assert_eq!(ast.token_slice(ast.main_token(data.rhs)), "@import");
+    let args = ast.node_data(data.rhs);
+    assert_eq!(ast.extra_list(args.lhs, args.rhs).len(), 1);
+
+    let data = ast.node_data(decls[1]);
+    let decl: FnDecl = ast.extra(data.lhs);
+    assert_eq!(decl.flags & flags::PUB, flags::PUB);
+    assert_eq!(ast.node_tag(decl.proto), NodeTag::FnProto);
+    assert_eq!(ast.node_tag(data.rhs), NodeTag::Block);
+    let proto: FnProto = ast.extra(ast.node_data(decl.proto).lhs);
+    let params = ast.extra_list(proto.params_start, proto.params_end);
+    let params: Vec<_> = params
+        .iter()
+        .map(|&param| {
+            let data = ast.node_data(param);
+            let name = ast.token_slice(ast.main_token(param));
+            let ty = ast.token_slice(ast.main_token(data.lhs));
+            (name, ty, data.rhs & flags::COMPTIME != 0)
+        })
+        .collect();
+    assert_eq!(params, [("a", "i32", false), ("b", "i32", true)]);
+
+    let data = ast.node_data(decls[2]);
+    let var: VarDecl = ast.extra(data.lhs);
+    assert_eq!(var.flags & flags::PUB, 0);
+    assert_eq!(ast.token_slice(ast.main_token(var.ty)), "u8");
+    assert_eq!(ast.node_tag(data.rhs), NodeTag::IntegerLiteral);
+    assert_eq!(ast.token_slice(ast.main_token(data.rhs)), "1");
+}
+
+#[test]
+fn parsing_directly_matches_converting_a_tree() {
+    let tree = parser::parse(SRC);
+    let converted = CompactAst::from_tree(SRC, &tree);
+    let parsed = CompactAst::parse(SRC);
+    assert_eq!(parsed.tokens().tags, converted.tokens().tags);
+    assert_eq!(parsed.tokens().starts, converted.tokens().starts);
+    assert_eq!(parsed.nodes().tags, converted.nodes().tags);
+    assert_eq!(parsed.nodes().main_tokens, converted.nodes().main_tokens);
+    assert_eq!(parsed.nodes().data, converted.nodes().data);
+    assert_eq!(parsed.extra_data(), converted.extra_data());
+}
@@ -0,0 +1,49 @@
+use std::{fs, path::PathBuf};
+
+use rusty_zig::fmt;
+
+/// The golden files in `tests/fmt`: every `name.in.zig` along with
+/// `name.zig`, which is what `zig fmt` makes of it.
+fn golden_files() -> Vec<(PathBuf, String, String)> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fmt");
+    let mut files: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.to_string_lossy().ends_with(".in.zig"))
+        .map(|input| {
+            let name = input.file_name().unwrap().to_string_lossy();
+            let expected = dir.join(name.replace(".in.zig", ".zig"));
+            let src = fs::read_to_string(&input).unwrap();
+            let expected = fs::read_to_string(&expected).unwrap();
+            (input, src, expected)
+        })
+        .collect();
+    files.sort();
+    assert!(!files.is_empty(), "no golden files in {}", dir.display());
+    files
+}
+
+#[test]
+fn formats_like_zig_fmt() {
+    for (path, src, expected) in golden_files() {
+        let formatted = fmt::format(&src).unwrap();
+        assert_eq!(formatted, expected, "{}", path.display());
+    }
+}
+
+#[test]
+fn formatted_files_are_left_alone() {
+    for (path, _, expected) in golden_files() {
+        let formatted = fmt::format(&expected).unwrap();
+        assert_eq!(formatted, expected, "{}", path.display());
+    }
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    for (path, src, _) in golden_files() {
+        let once = fmt::format(&src).unwrap();
+        let twice = fmt::format(&once).unwrap();
+        assert_eq!(twice, once, "{}", path.display());
+    }
+}
@@ -0,0 +1,20 @@
+fn f() void {
+    // nothing yet
+}
+
+fn g(x: bool) void {
+    while (x) {
+        // spin
+    }
+    if (x) {
+        // then
+    } else {
+        // else
+    }
+    for (0..3) |_| { // trailing
+    }
+}
+
+const S = struct {
+    // no fields
+};
@@ -0,0 +1,8 @@
+const A = error{Bad};
+const B = error{Bad};
+const C = error{ Bad, Worse };
+const D = error{
+    Bad,
+};
+const E = error{};
+fn f() error{OutOfMemory}!void {}
@@ -0,0 +1,8 @@
+// Copyright (c) the authors.
+// Licensed under the MIT license.
+
+const std = @import("std");
+
+pub fn main() void {
+    std.debug.print("hi\n", .{});
+}
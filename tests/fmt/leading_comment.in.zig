@@ -0,0 +1,4 @@
+// The answer.
+const x  =  42;
+// Twice the answer.
+const y = x*2;
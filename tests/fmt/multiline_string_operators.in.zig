@@ -0,0 +1,20 @@
+const t =
+     e
+  -%      \\two
+    ;
+const u = prefix ++
+\\one
+      \\two
+++ suffix;
+fn f() !void {
+    const p = &
+  \\abc
+    ;
+    const r = try
+        \\x
+    ;
+    const s =
+        \\a
+        // after the string
+    ;
+}
@@ -0,0 +1,15 @@
+const s =
+    \\first
+    \\second
+;
+fn f() void {
+    print(
+        \\hello {s}
+        \\
+    , .{"world"});
+    const t =
+        \\one
+        \\two
+    ;
+    _ = t;
+}
@@ -0,0 +1,21 @@
+const p = .{
+    .x = 1,
+    .y = 2,
+};
+const q = .{ .x = 1, .y = 2 };
+const c = .{x};
+fn f(
+    a: u8,
+    b: u8,
+) void {
+    g(
+        a,
+        b,
+    );
+    g(a, b);
+}
+const E = enum {
+    a,
+    b,
+};
+const U = enum { a, b };
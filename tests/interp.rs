@@ -0,0 +1,397 @@
+use std::thread;
+
+use rusty_zig::{
+    diagnostic::Diagnostic,
+    interp::{self, EvalError, EvalErrorKind, Interpreter},
+    parser,
+};
+
+/// A program with `body` as the body of `main`, after `decls`.
+fn program(decls: &str, body: &str) -> String {
+    format!(
+        "const std = @import(\"std\");\nconst print = std.debug.print;\n{decls}\npub fn main() void {{\n{body}\n}}\n"
+    )
+}
+
+/// Runs `program(decls, body)` and returns what it printed. It runs on a
+/// thread of its own, as the interpreter needs more stack than the test
+/// threads have.
+fn eval(decls: &str, body: &str) -> Result<String, EvalError> {
+    let src = program(decls, body);
+    let run = move || {
+        let ast = parser::parse(&src);
+        assert!(!ast.has_errors(), "{:?}", ast.errors);
+        let mut interpreter = Interpreter::new().capture_output(true);
+        interpreter.run(&ast).map(|()| interpreter.take_output())
+    };
+    thread::Builder::new()
+        .stack_size(interp::STACK_SIZE)
+        .spawn(run)
+        .unwrap()
+        .join()
+        .unwrap()
+}
+
+fn run(decls: &str, body: &str) -> Result<String, EvalErrorKind> {
+    eval(decls, body).map_err(|error| error.kind().clone())
+}
+
+const U128_MAX: &str = "340282366920938463463374607431768211455";
+
+#[test]
+fn u128_wraps_around_at_128_bits() {
+    let output = run(
+        "",
+        "var a: u128 = 0;\n\
+         print(\"{} \", .{a -% 1});\n\
+         a -%= 1;\n\
+         print(\"{} {}\", .{ a, a +% 2 });",
+    );
+    assert_eq!(output, Ok(format!("{U128_MAX} {U128_MAX} 1")));
+}
+
+#[test]
+fn u128_max_is_a_valid_literal() {
+    let output = run(
+        "",
+        "const x: u128 = 0xFFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF;\n\
+         print(\"{} {x}\", .{ x, x });",
+    );
+    assert_eq!(
+        output,
+        Ok(format!("{U128_MAX} ffffffffffffffffffffffffffffffff"))
+    );
+    let output = run(
+        "",
+        "const x: u128 = 0x1_0000_0000_0000_0000_0000_0000_0000_0000;",
+    );
+    assert!(
+        matches!(&output, Err(EvalErrorKind::Unsupported(what)) if what.contains("128 bits")),
+        "{output:?}"
+    );
+}
+
+#[test]
+fn u128_products_only_overflow_past_u128() {
+    let output = run(
+        "",
+        "var x: u128 = 0xFFFF_FFFF_FFFF_FFFF;\n\
+         print(\"{}\", .{x * 0xFFFF_FFFF_FFFF_FFFF});",
+    );
+    assert_eq!(
+        output,
+        Ok("340282366920938463426481119284349108225".to_string())
+    );
+    let output = run(
+        "",
+        "var x: u128 = 0x1_0000_0000_0000_0000;\n\
+         print(\"{}\", .{x * x});",
+    );
+    assert_eq!(output, Err(EvalErrorKind::Overflow));
+}
+
+const SUM: &str = "fn sum(n: u32) u32 {\n    if (n == 0) return 0;\n    return n + sum(n - 1);\n}";
+
+#[test]
+fn recursion_goes_deep() {
+    assert_eq!(
+        run(SUM, "print(\"{}\", .{sum(100)});"),
+        Ok("5050".to_string())
+    );
+    assert_eq!(
+        run(SUM, "print(\"{}\", .{sum(900)});"),
+        Ok("405450".to_string())
+    );
+}
+
+#[test]
+fn unbounded_recursion_is_a_stack_overflow() {
+    let decls = "fn forever(n: u64) u64 {\n    return forever(n + 1);\n}";
+    assert_eq!(
+        run(decls, "_ = forever(0);"),
+        Err(EvalErrorKind::StackOverflow)
+    );
+}
+
+#[test]
+fn overflow_is_an_error() {
+    let cases = [
+        "var x: u8 = 255;\n_ = x + 1;",
+        "var x: u8 = 0;\n_ = x - 1;",
+        "var x: i8 = -128;\n_ = -x;",
+        "var x: i32 = 0x10000;\n_ = x * x;",
+        "var x: u64 = 0;\nx -= 1;",
+    ];
+    for body in cases {
+        assert_eq!(run("", body), Err(EvalErrorKind::Overflow), "{body}");
+    }
+}
+
+#[test]
+fn wrapping_operators_wrap_around() {
+    let output = run(
+        "",
+        "var x: u8 = 250;\n\
+         var y: i8 = 127;\n\
+         var z: u16 = 0x8000;\n\
+         print(\"{} {} {} {} {}\", .{ x +% 10, x *% 2, y +% 1, -%y, z *% 2 });\n\
+         x +%= 6;\n\
+         y +%= 1;\n\
+         print(\" {} {}\", .{ x, y });",
+    );
+    assert_eq!(output, Ok("4 244 -128 -127 0 0 -128".to_string()));
+}
+
+#[test]
+fn saturating_operators_stop_at_the_limits() {
+    let output = run(
+        "",
+        "var x: u8 = 250;\n\
+         var y: i8 = -100;\n\
+         print(\"{} {} {} {} {} {}\", .{ x +| 10, x -| 251, x *| 2, y -| 100, y *| 2, y *| -2 });\n\
+         x +|= 100;\n\
+         print(\" {} {}\", .{ x, @as(u8, 3) <<| 7 });",
+    );
+    assert_eq!(output, Ok("255 0 255 -128 -128 127 255 255".to_string()));
+}
+
+#[test]
+fn try_catch_and_orelse() {
+    let decls = "\
+const Error = error{ TooBig, Odd };
+
+fn half(n: u32) Error!u32 {
+    if (n > 100) return error.TooBig;
+    if (n % 2 == 1) return error.Odd;
+    return n / 2;
+}
+
+fn quarter(n: u32) Error!u32 {
+    return half(try half(n));
+}
+
+fn find(items: []const u8, item: u8) ?usize {
+    for (items, 0..) |x, i| {
+        if (x == item) return i;
+    }
+    return null;
+}";
+    let output = run(
+        decls,
+        "print(\"{} \", .{try quarter(8)});\n\
+         print(\"{} \", .{half(7) catch 0});\n\
+         const name = half(200) catch |err| @errorName(err);\n\
+         print(\"{s} \", .{name});\n\
+         quarter(6) catch |err| print(\"{s} \", .{@errorName(err)});\n\
+         const items = [_]u8{ 3, 1, 4 };\n\
+         print(\"{} {}\", .{ find(&items, 4) orelse 99, find(&items, 5) orelse 99 });",
+    );
+    assert_eq!(output, Ok("2 0 TooBig Odd 2 99".to_string()));
+}
+
+#[test]
+fn unwrapping_null_is_an_error() {
+    let output = run(
+        "",
+        "var x: ?u8 = 1;\nprint(\"{}\", .{x.?});\nx = null;\n_ = x.?;",
+    );
+    assert_eq!(output, Err(EvalErrorKind::NullUnwrap));
+}
+
+#[test]
+fn switch_ranges_and_lists() {
+    let decls = "\
+fn class(c: u8) []const u8 {
+    return switch (c) {
+        'a'...'z' => \"lower\",
+        'A'...'Z' => \"upper\",
+        '0'...'9', '_' => \"word\",
+        ' ', '\\t', '\\n' => \"space\",
+        else => \"other\",
+    };
+}";
+    let output = run(
+        decls,
+        "for (\"qQ7_ \\t!\") |c| print(\"{s} \", .{class(c)});",
+    );
+    assert_eq!(
+        output,
+        Ok("lower upper word word space space other ".to_string())
+    );
+}
+
+#[test]
+fn labeled_break_and_continue() {
+    let output = run(
+        "",
+        "var pairs: u32 = 0;\n\
+         outer: for (0..5) |i| {\n\
+             for (0..5) |j| {\n\
+                 if (j > i) continue :outer;\n\
+                 if (i == 4) break :outer;\n\
+                 pairs += 1;\n\
+             }\n\
+         }\n\
+         var n: u32 = 0;\n\
+         const found = search: while (n < 100) : (n += 1) {\n\
+             if (n * n > 50) break :search n;\n\
+         } else 0;\n\
+         const block = blk: {\n\
+             if (found > 5) break :blk found * 10;\n\
+             break :blk 0;\n\
+         };\n\
+         print(\"{} {} {}\", .{ pairs, found, block });",
+    );
+    assert_eq!(output, Ok("10 8 80".to_string()));
+}
+
+const PAIR: &str = "\
+fn Pair(comptime T: type) type {
+    return struct {
+        a: T,
+        b: T,
+
+        fn sum(self: @This()) T {
+            return self.a + self.b;
+        }
+    };
+}";
+
+#[test]
+fn generic_types_are_made_once_per_argument() {
+    let output = run(
+        PAIR,
+        "const p = Pair(u8){ .a = 1, .b = 2 };\n\
+         print(\"{} {} {} \", .{ p.sum(), Pair(u32) == Pair(u32), Pair(u32) == Pair(i32) });\n\
+         print(\"{s} {}\", .{ @typeName(@TypeOf(p)), @TypeOf(p.a) == u8 });",
+    );
+    assert_eq!(output, Ok("3 true false Pair(u8) true".to_string()));
+}
+
+#[test]
+fn comptime_blocks_and_parameters() {
+    let decls = "\
+fn square(comptime n: u32) u32 {
+    return n * n;
+}
+
+const table = blk: {
+    var t: [5]u8 = undefined;
+    for (&t, 0..) |*x, i| x.* = @intCast(i * i);
+    break :blk t;
+};
+
+comptime {
+    if (table[2] != 4) @compileError(\"bad table\");
+}";
+    let output = run(
+        decls,
+        "const n = comptime square(12);\n\
+         print(\"{} {} {any}\", .{ n, square(3), table });",
+    );
+    assert_eq!(output, Ok("144 9 { 0, 1, 4, 9, 16 }".to_string()));
+
+    let output = run(
+        "comptime {\n    @compileError(\"nope\");\n}",
+        "print(\"unreachable\", .{});",
+    );
+    assert_eq!(output, Err(EvalErrorKind::CompileError("nope".to_string())));
+}
+
+#[test]
+fn type_queries() {
+    let decls = "\
+const S = struct { x: u8, y: u32, z: u16 };
+const E = enum { a, b, c };
+const U = union(enum) { int: i32, float: f64 };";
+    let output = run(
+        decls,
+        "print(\"{} {} {} {} {}\\n\", .{ @sizeOf(S), @sizeOf(u24), @alignOf(S), @sizeOf(E), @sizeOf(U) });\n\
+         print(\"{s} {s} {s}\\n\", .{ @typeName(S), @typeName(?*const [3]u8), @typeName(@TypeOf(1.5)) });\n\
+         inline for (@typeInfo(S).Struct.fields) |f| print(\"{s}:{s} \", .{ f.name, @typeName(f.type) });\n\
+         print(\"{} {}\", .{ @typeInfo(u7).Int.bits, @typeInfo(E).Enum.fields.len });",
+    );
+    assert_eq!(
+        output,
+        Ok("8 4 4 1 16\nS ?*const [3]u8 comptime_float\nx:u8 y:u32 z:u16 7 3".to_string())
+    );
+}
+
+#[test]
+fn runtime_values_cant_be_used_at_comptime() {
+    let output = run(
+        "fn square(comptime n: u32) u32 {\n    return n * n;\n}",
+        "var n: u32 = 3;\nn += 1;\n_ = square(n);",
+    );
+    assert_eq!(output, Err(EvalErrorKind::NotComptime("n".to_string())));
+
+    let output = run("", "var n: comptime_int = 3;\nn += 1;");
+    assert_eq!(
+        output,
+        Err(EvalErrorKind::ComptimeVar("comptime_int".to_string()))
+    );
+}
+
+#[test]
+fn comptime_calls_are_shown_in_the_diagnostic() {
+    let decls = "\
+fn check(comptime n: u32) u32 {
+    if (n > 10) @compileError(\"too big\");
+    return n;
+}
+
+fn outer(comptime n: u32) u32 {
+    return check(n * 2);
+}";
+    let body = "const x = comptime outer(8);\n_ = x;";
+    let error = eval(decls, body).unwrap_err();
+    let calls: Vec<_> = error
+        .trace()
+        .iter()
+        .map(|frame| (frame.name.as_str(), frame.comptime))
+        .collect();
+    assert_eq!(calls, [("check", true), ("outer", true), ("main", false)]);
+
+    let src = program(decls, body);
+    let rendered = Diagnostic::from(&error).render("test.zig", &src, false);
+    assert!(rendered.contains("error: too big"), "{rendered}");
+    assert!(
+        rendered.contains("------------ in comptime call to 'check'"),
+        "{rendered}"
+    );
+    assert!(
+        rendered.contains("-------- in comptime call to 'outer'"),
+        "{rendered}"
+    );
+}
+
+#[test]
+fn f16_is_rounded_to_half_precision() {
+    let output = run(
+        "",
+        "const a: f16 = 0.1;\n\
+         const b: f16 = 1.0 / 3.0;\n\
+         var c: f16 = 2048;\n\
+         c += 1;\n\
+         const max: f16 = 65519;\n\
+         const tiny: f16 = 0.00000006;\n\
+         print(\"{d} {d} {d} {d} {d} {d} \", .{ a, b, a + b, c, max, tiny });\n\
+         print(\"{} {d:.6}\", .{ max * 2, a });",
+    );
+    assert_eq!(
+        output,
+        Ok("0.1 0.3333 0.433 2048 65500 0.00000006 inf 0.099976".to_string())
+    );
+}
+
+#[test]
+fn floats_print_as_their_shortest_decimal() {
+    let output = run(
+        "",
+        "const a: f32 = 0.1;\n\
+         const b: f64 = 0.1;\n\
+         const c: f32 = 16777217;\n\
+         print(\"{d} {d} {d} {} {}\", .{ a, b, a + 0.2, c, a * 3 });",
+    );
+    assert_eq!(output, Ok("0.1 0.1 0.3 1.6777216e7 3e-1".to_string()));
+}
@@ -0,0 +1,40 @@
+use rusty_zig::tokenizer::{LexErrorKind, Span, TokenType, Tokenizer};
+
+/// The errors found in `src`, along with the text they point at.
+fn errors(src: &str) -> Vec<(LexErrorKind, &str)> {
+    let mut tokenizer = Tokenizer::new(src);
+    while tokenizer.next_token().is_some() {}
+    tokenizer
+        .errors()
+        .iter()
+        .map(|error| (error.kind().clone(), error.span().slice(src)))
+        .collect()
+}
+
+#[test]
+fn quoted_identifiers_cannot_be_empty() {
+    assert_eq!(
+        errors("const @\"\" = 1;"),
+        [(LexErrorKind::EmptyIdentifier, "@\"\"")]
+    );
+    // The identifier is still there for the parser.
+    let tokens: Vec<_> = Tokenizer::new("@\"\"").collect();
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].span(), Span::new(0, 3));
+    assert_eq!(tokens[0].token_type(), &TokenType::Identifier("".into()));
+
+    assert_eq!(errors("@\" \" @\"a\" @\"\\x41\""), []);
+    // Unterminated literals are only reported as such.
+    assert_eq!(errors("@\""), [(LexErrorKind::UnterminatedString, "\"")]);
+}
+
+#[test]
+fn float_literals_must_fit_in_an_f64() {
+    for src in ["1e99999999999", "1e400", "0x1p99999", "1.5e309"] {
+        assert_eq!(errors(src), [(LexErrorKind::FloatOutOfRange, src)], "{src}");
+    }
+    let max = format!("{:e}", f64::MAX);
+    for src in ["1e308", &max, "0x1.fffffffffffffp1023", "1e-400", "0.0"] {
+        assert_eq!(errors(src), [], "{src}");
+    }
+}
@@ -0,0 +1,146 @@
+use rusty_zig::tokenizer::{TokenType, Tokenizer, TriviaKind};
+
+/// A small xorshift generator, so the inputs are random but the
+/// same on every run.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.next() as usize % items.len()]
+    }
+}
+
+const FRAGMENTS: &[&str] = &[
+    "const",
+    "var",
+    "fn",
+    "pub",
+    "return",
+    "if",
+    "else",
+    "while",
+    "x",
+    "foo_bar",
+    "u8",
+    "i33",
+    "@import",
+    "@as",
+    "@foo",
+    "@\"weird name\"",
+    "0",
+    "123",
+    "0x1F",
+    "0b102",
+    "1e",
+    "1.5e-3",
+    "0x1p4",
+    "1__0",
+    "\"str\"",
+    "\"esc\\n\\x41\\u{1F600}\"",
+    "\"unterminated",
+    "'a'",
+    "'\\''",
+    "''",
+    "'ab'",
+    "\\\\multi line",
+    "// comment",
+    "/// doc",
+    "//! top",
+    "//",
+    "+",
+    "+=",
+    "+%=",
+    "<<|=",
+    ".*",
+    ".?",
+    "...",
+    "=>",
+    "->",
+    "{",
+    "}",
+    "(",
+    ")",
+    "[",
+    "]",
+    ";",
+    ",",
+    ":",
+    "|",
+    "||",
+    "!",
+    "~",
+    "$",
+    "#",
+    "`",
+    "\\",
+    "\"",
+    "'",
+    "@",
+    "é",
+    "日本",
+    "🦀",
+    " ",
+    "  ",
+    "\t",
+    "\r",
+    "\n",
+    "\r\n",
+    "\n\n",
+];
+
+fn random_input(rng: &mut Rng) -> String {
+    let len = rng.next() % 40;
+    let mut src = String::new();
+    for _ in 0..len {
+        match rng.next() % 4 {
+            0 => src.push(char::from(b' ' + (rng.next() % 95) as u8)),
+            1 => src.push(char::from_u32((rng.next() % 0x3000) as u32).unwrap_or('?')),
+            _ => src.push_str(rng.pick(FRAGMENTS)),
+        }
+    }
+    src
+}
+
+fn round_trip(src: &str) -> String {
+    let mut out = String::new();
+    for token in Tokenizer::new(src).lossless() {
+        token.write_to(&mut out);
+    }
+    out
+}
+
+#[test]
+fn lossless_tokens_round_trip_arbitrary_input() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..5000 {
+        let src = random_input(&mut rng);
+        assert_eq!(round_trip(&src), src, "round trip failed for {src:?}");
+    }
+}
+
+#[test]
+fn trivia_attaches_to_neighbouring_tokens() {
+    let src = "const x = 1; // one\n\n  // two\nvar y = 2;\n";
+    let tokens: Vec<_> = Tokenizer::new(src).lossless().collect();
+
+    let semicolon = &tokens[4];
+    assert_eq!(semicolon.text(), ";");
+    let trailing: Vec<_> = semicolon.trailing().iter().map(|t| t.kind()).collect();
+    assert_eq!(trailing, [TriviaKind::Whitespace, TriviaKind::Comment]);
+
+    let var = &tokens[5];
+    assert_eq!(var.text(), "var");
+    let leading: Vec<_> = var.leading().iter().map(|t| t.text()).collect();
+    assert_eq!(leading, ["\n", "\n", "  ", "// two", "\n"]);
+
+    let eof = tokens.last().unwrap();
+    assert!(matches!(eof.token().token_type(), TokenType::Eof));
+    assert_eq!(eof.leading()[0].text(), "\n");
+}
@@ -0,0 +1,117 @@
+use rusty_zig::tokenizer::{Span, TokenType, Tokenizer};
+
+fn token_types(src: &str) -> Vec<TokenType<'_>> {
+    Tokenizer::new(src)
+        .map(|token| token.token_type().clone())
+        .collect()
+}
+
+#[test]
+fn every_operator_lexes_as_a_single_token() {
+    use TokenType::*;
+    let operators = [
+        ("&", Ampersand),
+        ("&=", AmpersandEqual),
+        ("*", Asterisk),
+        ("**", Asterisk2),
+        ("*=", AsteriskEqual),
+        ("*%", AsteriskPercent),
+        ("*%=", AsteriskPercentEqual),
+        ("*|", AsteriskPipe),
+        ("*|=", AsteriskPipeEqual),
+        ("^", Caret),
+        ("^=", CaretEqual),
+        (":", Colon),
+        (",", Comma),
+        (".", Dot),
+        ("..", Dot2),
+        ("...", Dot3),
+        (".*", DotAsterisk),
+        (".?", DotQuestionMark),
+        ("=", Equal),
+        ("==", EqualEqual),
+        ("=>", EqualArrow),
+        ("!", ExclamationMark),
+        ("!=", ExclamationMarkEqual),
+        ("<", LArrow),
+        ("<<", LArrow2),
+        ("<<=", LArrow2Equal),
+        ("<<|", LArrow2Pipe),
+        ("<<|=", LArrow2PipeEqual),
+        ("<=", LArrowEqual),
+        ("{", LBrace),
+        ("[", LBracket),
+        ("(", LParen),
+        ("-", Minus),
+        ("-=", MinusEqual),
+        ("-%", MinusPercent),
+        ("-%=", MinusPercentEqual),
+        ("-|", MinusPipe),
+        ("-|=", MinusPipeEqual),
+        ("->", MinusArrow),
+        ("%", Percent),
+        ("%=", PercentEqual),
+        ("|", Pipe),
+        ("||", Pipe2),
+        ("|=", PipeEqual),
+        ("+", Plus),
+        ("++", Plus2),
+        ("+=", PlusEqual),
+        ("+%", PlusPercent),
+        ("+%=", PlusPercentEqual),
+        ("+|", PlusPipe),
+        ("+|=", PlusPipeEqual),
+        ("?", QuestionMark),
+        (">", RArrow),
+        (">>", RArrow2),
+        (">>=", RArrow2Equal),
+        (">=", RArrowEqual),
+        ("}", RBrace),
+        ("]", RBracket),
+        (")", RParen),
+        (";", Semicolon),
+        ("/", Slash),
+        ("/=", SlashEqual),
+        ("~", Tilde),
+    ];
+
+    for (src, expected) in operators {
+        let tokens: Vec<_> = Tokenizer::new(src).collect();
+        assert_eq!(tokens.len(), 1, "{src:?} lexed as {tokens:?}");
+        assert_eq!(tokens[0].token_type(), &expected, "{src:?}");
+        assert_eq!(tokens[0].span(), Span::new(0, src.len()), "{src:?}");
+    }
+}
+
+#[test]
+fn operators_use_maximal_munch() {
+    use TokenType::*;
+    assert_eq!(token_types("+++"), [Plus2, Plus]);
+    assert_eq!(token_types("+%=="), [PlusPercentEqual, Equal]);
+    assert_eq!(token_types("--"), [Minus, Minus]);
+    assert_eq!(token_types("->>"), [MinusArrow, RArrow]);
+    assert_eq!(token_types("-|-%"), [MinusPipe, MinusPercent]);
+    assert_eq!(token_types("<<<"), [LArrow2, LArrow]);
+    assert_eq!(token_types("<<|=<="), [LArrow2PipeEqual, LArrowEqual]);
+    assert_eq!(token_types(">>>="), [RArrow2, RArrowEqual]);
+    assert_eq!(token_types("***"), [Asterisk2, Asterisk]);
+    assert_eq!(token_types("*|=|"), [AsteriskPipeEqual, Pipe]);
+    assert_eq!(token_types("....."), [Dot3, Dot2]);
+}
+
+#[test]
+fn operator_spans_follow_each_other() {
+    let spans: Vec<_> = Tokenizer::new("a +%= b <<| c")
+        .map(|token| token.span())
+        .collect();
+    assert_eq!(
+        spans,
+        [
+            Span::new(0, 1),
+            Span::new(2, 5),
+            Span::new(6, 7),
+            Span::new(8, 11),
+            Span::new(12, 13),
+        ]
+    );
+}
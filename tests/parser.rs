@@ -0,0 +1,164 @@
+use rusty_zig::{
+    ast::{DeclKind, Expr, ExprKind},
+    parser::{self, ParseErrorKind},
+};
+
+/// The expression with every operator and its operands in parentheses,
+/// such as `(Or a (And b c))`.
+fn shape(src: &str) -> String {
+    fn go(expr: &Expr, src: &str) -> String {
+        match &expr.kind {
+            ExprKind::Binary { op, lhs, rhs } => {
+                format!("({op:?} {} {})", go(lhs, src), go(rhs, src))
+            }
+            ExprKind::Catch { lhs, capture, rhs } => {
+                let capture = match capture {
+                    Some(capture) => format!(" |{}|", capture.name.name),
+                    None => String::new(),
+                };
+                format!("(Catch{capture} {} {})", go(lhs, src), go(rhs, src))
+            }
+            ExprKind::Unary { op, expr } => format!("({op:?} {})", go(expr, src)),
+            _ => expr.span.slice(src).to_string(),
+        }
+    }
+    match parser::parse_expr(src) {
+        Ok(expr) => go(&expr, src),
+        Err(errors) => panic!("{src} failed to parse: {errors:?}"),
+    }
+}
+
+#[test]
+fn binary_operators_bind_by_precedence() {
+    let cases = [
+        ("a or b and c", "(Or a (And b c))"),
+        ("a and b or c", "(Or (And a b) c)"),
+        ("a == b and c != d", "(And (Equal a b) (NotEqual c d))"),
+        ("x orelse y + 1", "(OrElse x (Add y 1))"),
+        ("a catch |e| b", "(Catch |e| a b)"),
+        ("a catch b orelse c", "(OrElse (Catch a b) c)"),
+        ("a + b * c", "(Add a (Mul b c))"),
+        ("a << b + c", "(Shl a (Add b c))"),
+        ("a & b << c", "(BitAnd a (Shl b c))"),
+        ("a < b | c", "(Less a (BitOr b c))"),
+        ("-a * b", "(Mul (Negate a) b)"),
+        ("try a + b", "(Add (Try a) b)"),
+        ("!a and b", "(And (Not a) b)"),
+        ("a ++ b ** c", "(Concat a (Repeat b c))"),
+        ("e1 || e2", "(MergeErrorSets e1 e2)"),
+    ];
+    for (src, expected) in cases {
+        assert_eq!(shape(src), expected, "{src}");
+    }
+}
+
+#[test]
+fn operators_of_the_same_precedence_are_left_associative() {
+    assert_eq!(shape("a - b - c"), "(Sub (Sub a b) c)");
+    assert_eq!(shape("a / b * c"), "(Mul (Div a b) c)");
+    assert_eq!(shape("a orelse b orelse c"), "(OrElse (OrElse a b) c)");
+    assert_eq!(shape("a or b or c"), "(Or (Or a b) c)");
+}
+
+#[test]
+fn parentheses_are_kept() {
+    assert_eq!(shape("(a + b) * c"), "(Mul (a + b) c)");
+    assert_eq!(shape("(a == b) == c"), "(Equal (a == b) c)");
+}
+
+#[test]
+fn comparisons_cannot_be_chained() {
+    // The error points at the second comparison.
+    for (src, op) in [
+        ("a == b == c", "=="),
+        ("a < b < c", "<"),
+        ("a != b <= c", "<="),
+    ] {
+        let errors = parser::parse_expr(src).unwrap_err();
+        assert_eq!(errors.len(), 1, "{src}");
+        assert_eq!(
+            errors[0].kind(),
+            &ParseErrorKind::ChainedComparison,
+            "{src}"
+        );
+        assert_eq!(errors[0].span().slice(src), op, "{src}");
+    }
+}
+
+#[test]
+fn every_broken_declaration_is_reported() {
+    let src = "\
+const a = ;
+const b: u8 = 1;
+fn f() void {
+    const x = 1 +;
+    const y = ;
+    return;
+}
+var c = )];
+const d = 2;
+";
+    let ast = parser::parse(src);
+    assert!(ast.has_errors());
+    assert_eq!(ast.errors.len(), 4, "{:?}", ast.errors);
+    for error in &ast.errors {
+        assert!(
+            matches!(error.kind(), ParseErrorKind::Expected { .. }),
+            "{error:?}"
+        );
+    }
+
+    let names: Vec<_> = ast
+        .decls
+        .iter()
+        .map(|decl| match &decl.kind {
+            DeclKind::Var(var) => var.name.name.to_string(),
+            DeclKind::Fn(decl) => decl.proto.name.as_ref().unwrap().name.to_string(),
+            DeclKind::Error => "<error>".to_string(),
+            kind => panic!("unexpected {kind:?}"),
+        })
+        .collect();
+    assert_eq!(names, ["<error>", "b", "f", "<error>", "d"]);
+}
+
+#[test]
+fn broken_statements_keep_the_rest_of_the_block() {
+    let src = "fn f() void {\n    const x = 1 +;\n    const y = ;\n    return;\n}\n";
+    let ast = parser::parse(src);
+    assert_eq!(ast.errors.len(), 2, "{:?}", ast.errors);
+    let DeclKind::Fn(decl) = &ast.decls[0].kind else {
+        panic!("expected a function, got {:?}", ast.decls[0].kind);
+    };
+    assert_eq!(decl.body.as_ref().unwrap().stmts.len(), 3);
+}
+
+#[test]
+fn broken_fields_keep_the_rest_of_the_container() {
+    let src = "const S = struct {\n    a: u8 = ,\n    b: u16,\n    fn f() void {}\n};\n";
+    let ast = parser::parse(src);
+    assert_eq!(ast.errors.len(), 1, "{:?}", ast.errors);
+    let DeclKind::Var(var) = &ast.decls[0].kind else {
+        panic!("expected a variable, got {:?}", ast.decls[0].kind);
+    };
+    let Some(ExprKind::Container(container)) = var.value.as_ref().map(|value| &value.kind) else {
+        panic!("expected a container, got {:?}", var.value);
+    };
+    let members: Vec<_> = container
+        .decls
+        .iter()
+        .map(|member| match &member.kind {
+            DeclKind::Field(field) => field.name.as_ref().unwrap().name.to_string(),
+            DeclKind::Fn(decl) => decl.proto.name.as_ref().unwrap().name.to_string(),
+            DeclKind::Error => "<error>".to_string(),
+            kind => panic!("unexpected {kind:?}"),
+        })
+        .collect();
+    assert_eq!(members, ["<error>", "b", "f"]);
+}
+
+#[test]
+fn primitive_type_names_can_be_field_names() {
+    let src = "const x = @typeInfo(T).Struct.fields[0].type;";
+    let ast = parser::parse(src);
+    assert!(!ast.has_errors(), "{:?}", ast.errors);
+}
@@ -0,0 +1,148 @@
+use rusty_zig::repl::{Output, Repl};
+
+/// Feeds `lines` to a new REPL, and returns the output of every input.
+fn session(lines: &str) -> Vec<Output> {
+    let mut repl = Repl::new();
+    let mut outputs: Vec<_> = lines.lines().filter_map(|line| repl.line(line)).collect();
+    outputs.extend(repl.finish());
+    outputs
+}
+
+fn out(out: &str) -> Output {
+    Output {
+        out: out.to_string(),
+        err: String::new(),
+    }
+}
+
+#[test]
+fn inputs_are_read_until_their_brackets_are_closed() {
+    let mut repl = Repl::new();
+    assert_eq!(repl.prompt(), "> ");
+    assert_eq!(repl.line("fn add(a: u32, b: u32) u32 {"), None);
+    assert_eq!(repl.prompt(), "... ");
+    assert_eq!(repl.line("    return a + b;"), None);
+    assert_eq!(repl.line("}"), Some(Output::default()));
+    assert_eq!(repl.prompt(), "> ");
+    assert_eq!(repl.line("add("), None);
+    assert_eq!(repl.line("    1,"), None);
+    assert_eq!(repl.line("    2,"), None);
+    assert_eq!(repl.line(")"), Some(out("3 (u32)\n")));
+}
+
+#[test]
+fn unclosed_input_is_handled_at_the_end() {
+    let outputs = session("const x = 1;\n(x +");
+    assert_eq!(outputs.len(), 2);
+    assert!(
+        outputs[1].err.contains("<input 2>:1:5: error: expected"),
+        "{}",
+        outputs[1].err
+    );
+}
+
+#[test]
+fn declarations_are_kept_across_inputs() {
+    let outputs = session(
+        "const std = @import(\"std\");\n\
+         const base = 10;\n\
+         fn scale(x: u32) u32 {\n    return x * base;\n}\n\
+         var total: u32 = 0;\n\
+         total += scale(4)\n\
+         total += scale(2)\n\
+         std.debug.print(\"total is {}\\n\", .{total})\n\
+         total\n\
+         :names",
+    );
+    assert_eq!(outputs[6].err, "total is 60\n");
+    assert_eq!(outputs[7], out("60 (u32)\n"));
+    assert_eq!(outputs[8], out("base scale std total\n"));
+}
+
+#[test]
+fn reset_forgets_declarations() {
+    let outputs = session("const x = 1;\n:reset\nx\nconst x = 2;\nx");
+    assert_eq!(outputs[1], out("forgot everything declared so far\n"));
+    assert!(
+        outputs[2].err.contains("use of undeclared identifier 'x'"),
+        "{}",
+        outputs[2].err
+    );
+    assert_eq!(outputs[4], out("2 (comptime_int)\n"));
+}
+
+#[test]
+fn modes_change_what_is_shown() {
+    let outputs =
+        session(":mode\n:mode tokens\n1 + x\n:mode ast\n1 + x\n:mode nope\n:mode eval\n1 + 2");
+    assert_eq!(outputs[0], out("eval\n"));
+    let tokens: Vec<_> = outputs[2].out.lines().collect();
+    assert_eq!(tokens.len(), 3, "{tokens:?}");
+    assert!(tokens[1].contains("Plus"), "{tokens:?}");
+    assert!(outputs[4].out.contains("Binary"), "{}", outputs[4].out);
+    assert_eq!(
+        outputs[5].err,
+        "error: unknown mode 'nope', expected tokens, ast or eval\n"
+    );
+    assert_eq!(outputs[7], out("3 (comptime_int)\n"));
+}
+
+#[test]
+fn history_can_be_run_again() {
+    let outputs = session("var n: u32 = 1;\nn += 1\nn\n!2\n!!\n!3\n!9");
+    assert_eq!(outputs[3], out("n += 1\n"));
+    assert_eq!(outputs[4], out("n += 1\n"));
+    assert_eq!(outputs[5], out("n\n4 (u32)\n"));
+    assert_eq!(outputs[6].err, "error: no input !9 in the history\n");
+}
+
+#[test]
+fn errors_point_at_the_input_they_are_in() {
+    let outputs = session(
+        "const limit: u8 = 200;\n\
+         fn grow(x: u8) u8 {\n    return x + limit;\n}\n\
+         grow(10)\n\
+         grow(100)\n\
+         fn twice(x: u8) u8 {\n    return grow(x) * 2;\n}\n\
+         twice(10)\n\
+         limit + 100",
+    );
+    assert_eq!(outputs[2], out("210 (u8)\n"));
+
+    // The overflow is in the second input, and the call in the fourth.
+    let err = &outputs[3].err;
+    assert!(
+        err.starts_with("<input 2>:2:12: error: integer overflow\n"),
+        "{err}"
+    );
+    assert!(err.contains("2 |     return x + limit;"), "{err}");
+    assert!(
+        err.contains("note: in call to 'grow' at <input 4>:1:1"),
+        "{err}"
+    );
+
+    // The overflow is in `twice`, which is in the fifth input.
+    let err = &outputs[5].err;
+    assert!(
+        err.starts_with("<input 5>:2:12: error: integer overflow\n"),
+        "{err}"
+    );
+    assert!(
+        err.contains("note: in call to 'twice' at <input 6>:1:1"),
+        "{err}"
+    );
+
+    // Errors in the input being evaluated have no calls to note.
+    let err = &outputs[6].err;
+    assert!(err.starts_with("<input 7>:1:1: error: "), "{err}");
+    assert!(err.contains("1 | limit + 100"), "{err}");
+    assert!(!err.contains("note:"), "{err}");
+}
+
+#[test]
+fn a_semicolon_can_be_left_off() {
+    let outputs = session("const x = 5\nvar y: u8 = x\ny += 1\ny * 2");
+    assert_eq!(outputs[0], Output::default());
+    assert_eq!(outputs[2], Output::default());
+    assert_eq!(outputs[3], out("12 (u8)\n"));
+}